@@ -1,52 +1,205 @@
+mod jets;
+mod model_atom;
+mod non_lte;
+pub(crate) mod stratification;
+
+use jets::{ChromosphericJet, JetFluxes, JetKind};
+use model_atom::{ModelAtom, Redistribution};
+use non_lte::{NonLteSettings, NonLteSolution};
+use radiation::atomic_data;
+use radiation::irradiance::{self, ASTRONOMICAL_UNIT};
+use radiation::planck;
+use radiation::spectral_grid::{SpectralGrid, SpectralUnit};
+use radiation::transfer::{self, RadiatingLayer};
+use stratification::{AtmosphereVariant, ChromosphereLayer, StratifiedAtmosphere};
+
 // Define a struct for the Chromosphere
-struct Chromosphere {
-    radius: f64,
-    temperature: f64,
-    density: f64,
-    energy_generation: f64,
+#[derive(Clone)]
+pub(crate) struct Chromosphere {
+    pub(crate) radius: f64,
+    pub(crate) temperature: f64,
+    pub(crate) density: f64,
+    #[allow(dead_code)] // not used by the radiative model yet
+    pub(crate) energy_generation: f64,
+    // Height-resolved model; without one the chromosphere is isothermal
+    pub(crate) stratification: Option<StratifiedAtmosphere>,
+    // Spicules and other jets launched from the static chromosphere
+    pub(crate) jets: Vec<ChromosphericJet>,
 }
 
 impl Chromosphere {
-    // Method to calculate optical depth
-    fn calculate_optical_depth(&self, photosphere_radius: f64, wavelength: f64) -> f64 {
-        let kappa = self.get_absorption_coefficient(wavelength);
-        let chromosphere_thickness = self.radius - photosphere_radius;
-        kappa * chromosphere_thickness
+    // Method to calculate optical depth, integrating dtau = kappa * rho * ds
+    // through the stratified column above the photosphere
+    pub(crate) fn calculate_optical_depth(&self, photosphere_radius: f64, wavelength: f64) -> f64 {
+        match &self.stratification {
+            // From the temperature minimum up to the outer radius
+            Some(atmosphere) => {
+                let bottom = atmosphere.temperature_minimum().height;
+                let top = (self.radius - photosphere_radius).max(bottom);
+                transfer::optical_depth(&atmosphere.between(bottom, top), wavelength)
+            }
+            None => transfer::optical_depth(&self.column(photosphere_radius), wavelength),
+        }
+    }
+
+    // Isothermal column in hydrostatic equilibrium, with `density` taken as
+    // the density at the base of the chromosphere
+    fn column(&self, photosphere_radius: f64) -> ChromosphereColumn<'_> {
+        let thickness = self.radius - photosphere_radius;
+        let layers = (0..COLUMN_POINTS)
+            .map(|i| self.isothermal_layer(thickness * (1.0 - i as f64 / (COLUMN_POINTS - 1) as f64)))
+            .collect();
+        ChromosphereColumn {
+            chromosphere: self,
+            layers,
+        }
+    }
+
+    // Plasma of the isothermal column at `height`, ionized in LTE
+    fn isothermal_layer(&self, height: f64) -> ChromosphereLayer {
+        let density = self.density * (-height / self.scale_height()).exp();
+        let hydrogen_density = atomic_data::hydrogen_density(density);
+        ChromosphereLayer {
+            height,
+            temperature: self.temperature,
+            electron_density: atomic_data::electron_density(self.temperature, hydrogen_density),
+            hydrogen_density,
+            microturbulence: 0.0,
+        }
+    }
+
+    // Pressure scale height of the isothermal chromosphere
+    fn scale_height(&self) -> f64 {
+        BOLTZMANN_CONSTANT * self.temperature
+            / (CHROMOSPHERE_MEAN_MOLECULAR_WEIGHT * ATOMIC_MASS_UNIT * SOLAR_SURFACE_GRAVITY)
+    }
+
+    // Plasma at `height` above the photosphere, where jets are launched:
+    // the stratified model, or the isothermal column ionized in LTE
+    pub(crate) fn launch_conditions(&self, height: f64) -> ChromosphereLayer {
+        match &self.stratification {
+            Some(atmosphere) => atmosphere.at_height(height),
+            None => self.isothermal_layer(height),
+        }
+    }
+
+    // Mass and energy carried up by all the jets, per unit area of the chromosphere
+    pub(crate) fn jet_fluxes(&self) -> JetFluxes {
+        let surface_area = 4.0 * std::f64::consts::PI * self.radius * self.radius;
+        self.jets
+            .iter()
+            .map(|jet| jet.fluxes(&self.launch_conditions(jet.launch_height), surface_area))
+            .fold(JetFluxes::default(), JetFluxes::add)
+    }
+
+    // Source function of the isothermal column
+    pub(crate) fn source_function(&self, wavelength: f64) -> f64 {
+        planck::spectral_radiance_wavelength(wavelength, self.temperature)
+    }
+
+    // Emergent disk-centre spectrum; a stratified chromosphere is solved
+    // from below the photosphere up through the temperature rise, with
+    // non-LTE hydrogen for the ultraviolet when the grid reaches it
+    fn get_emission_spectrum(&self, grid: &SpectralGrid) -> Vec<(f64, f64)> {
+        let ultraviolet = grid.wavelengths().iter().any(|&wavelength| wavelength < BALMER_EDGE);
+        let hydrogen = if ultraviolet { self.hydrogen() } else { None };
+        self.emission_spectrum(hydrogen.as_ref(), grid)
+    }
+
+    // Non-LTE hydrogen in the stratified model: its populations and
+    // ionization set Lyman alpha and the Lyman and Balmer continua
+    pub(crate) fn hydrogen(&self) -> Option<NonLteSolution> {
+        self.stratification
+            .as_ref()
+            .map(|atmosphere| non_lte::solve(&ModelAtom::hydrogen(), atmosphere, &NonLteSettings::default()))
     }
 
-    // Method to get emission spectrum
-    fn get_emission_spectrum(&self) -> Vec<(f64, f64)> {
-        let mut spectrum = Vec::new();
-        let temperature = self.temperature;
-        // Iterate over wavelengths from 200nm to 700nm in steps of 1nm
-        for wavelength in (200..=700).map(|w| w as f64 * 1.0e-9) {
-            let intensity = blackbody_intensity(wavelength, temperature);
-            spectrum.push((wavelength, intensity));
+    // Disk-centre spectrum; shortward of the Balmer edge the lines and
+    // continua of a given hydrogen solution replace the LTE continuum
+    pub(crate) fn emission_spectrum(&self, hydrogen: Option<&NonLteSolution>, grid: &SpectralGrid) -> Vec<(f64, f64)> {
+        match &self.stratification {
+            Some(atmosphere) => grid.map_wavelengths(|wavelength| match hydrogen {
+                Some(solution) if wavelength < BALMER_EDGE => solution.emergent_intensity(wavelength, 1.0),
+                _ => transfer::emergent_intensity(atmosphere, wavelength, 1.0),
+            }),
+            None => {
+                let temperature = self.temperature;
+                grid.map_wavelengths(|wavelength| planck::spectral_radiance_wavelength(wavelength, temperature))
+            }
         }
-        spectrum
     }
 
-    // Function to calculate absorption coefficient
-    fn get_absorption_coefficient(&self, _wavelength: f64) -> f64 {
-        // Placeholder implementation for absorption coefficient calculation
-        // Here you can implement the actual logic to calculate the absorption coefficient
-        // For now, let's return a constant value as an example
-        0.5
+    // Spectral irradiance (W m^-2 m^-1) at `distance` from the flux of a
+    // hydrogen solution, with the chromosphere covering the whole disk
+    pub(crate) fn ultraviolet_irradiance(
+        &self,
+        hydrogen: &NonLteSolution,
+        grid: &SpectralGrid,
+        distance: f64,
+    ) -> Vec<(f64, f64)> {
+        let dilution = (self.radius / distance).powi(2);
+        grid.map_wavelengths(|wavelength| hydrogen.emergent_flux(wavelength) * dilution)
     }
 }
 
-// Dummy function for blackbody intensity
-fn blackbody_intensity(_wavelength: f64, _temperature: f64) -> f64 {
-    // Placeholder implementation
-    1.0
+// Depth points of the chromosphere, ordered from the top down
+struct ChromosphereColumn<'a> {
+    chromosphere: &'a Chromosphere,
+    layers: Vec<ChromosphereLayer>,
+}
+
+// The photosphere's continuous opacity (H- bound-free and free-free,
+// hydrogen and metal bound-free, Thomson scattering) at each depth point
+impl RadiatingLayer for ChromosphereColumn<'_> {
+    fn heights(&self) -> Vec<f64> {
+        self.layers.iter().map(|l| l.height).collect()
+    }
+
+    fn densities(&self) -> Vec<f64> {
+        self.layers.iter().map(|l| l.density()).collect()
+    }
+
+    fn opacities(&self, wavelength: f64) -> Vec<f64> {
+        self.layers.iter().map(|l| l.opacity(wavelength)).collect()
+    }
+
+    fn source_function(&self, wavelength: f64) -> Vec<f64> {
+        vec![self.chromosphere.source_function(wavelength); self.layers.len()]
+    }
 }
 
+const BOLTZMANN_CONSTANT: f64 = 1.380649e-23; // J/K
+const ATOMIC_MASS_UNIT: f64 = 1.66053907e-27; // kg
+const SOLAR_SURFACE_GRAVITY: f64 = 274.0; // m/s^2
+const CHROMOSPHERE_MEAN_MOLECULAR_WEIGHT: f64 = 1.3;
+const COLUMN_POINTS: usize = 200;
+
 // Constants for the chromosphere properties
 const CHROMOSPHERE_RADIUS: f64 = 7.0e8; // meters (example value)
 const CHROMOSPHERE_TEMPERATURE: f64 = 6000.0; // Kelvin (example value)
 const CHROMOSPHERE_DENSITY: f64 = 1.0e-5; // kg/m^3 (example value)
 const CHROMOSPHERE_ENERGY_GENERATION: f64 = 1.0e25; // W/m^3 (example value)
 
+// Continuum wavelengths (m) probing increasing heights: the H- opacity
+// minimum, visible, mid and far infrared, submillimetre and millimetre
+const BRIGHTNESS_WAVELENGTHS: [f64; 7] = [1.6e-6, 500.0e-9, 20.0e-6, 150.0e-6, 0.35e-3, 1.2e-3, 3.0e-3];
+const LIMB_WAVELENGTH: f64 = 1.2e-3; // m
+// Heights (m) at which the non-LTE departure coefficients are reported
+const DEPARTURE_HEIGHTS: [f64; 4] = [500.0e3, 1000.0e3, 1500.0e3, 2000.0e3];
+// Points along each reported jet trajectory
+const JET_SAMPLES: usize = 8;
+// Hydrogen n = 2 ionization edge (m); shortward of it hydrogen is solved out of LTE
+const BALMER_EDGE: f64 = 364.6e-9;
+// Ultraviolet bands (m) and their sampling: the Lyman continuum, Lyman alpha
+// and the Balmer continuum
+const LYMAN_CONTINUUM: (f64, f64, usize) = (50.0e-9, 91.1e-9, 60);
+const LYMAN_ALPHA: (f64, f64, usize) = (121.0e-9, 122.3e-9, 261);
+const BALMER_CONTINUUM: (f64, f64, usize) = (200.0e-9, 364.5e-9, 120);
+// Fraction of the quiet-Sun disk covered by the bright network
+const NETWORK_FILLING_FACTOR: f64 = 0.3;
+// Offsets (m) from the centre of Lyman alpha at which its profile is reported
+const LYMAN_ALPHA_OFFSETS: [f64; 6] = [0.0, 0.01e-9, 0.02e-9, 0.04e-9, 0.1e-9, 0.3e-9];
+
 fn main() {
     // Create an instance of the Chromosphere struct
     let chromosphere = Chromosphere {
@@ -54,6 +207,8 @@ fn main() {
         temperature: CHROMOSPHERE_TEMPERATURE,
         density: CHROMOSPHERE_DENSITY,
         energy_generation: CHROMOSPHERE_ENERGY_GENERATION,
+        stratification: Some(StratifiedAtmosphere::bundled(AtmosphereVariant::QuietSun)),
+        jets: JetKind::ALL.iter().map(|&kind| ChromosphericJet::typical(kind)).collect(),
     };
 
     // Example usage of methods for Chromosphere
@@ -62,6 +217,276 @@ fn main() {
     let chromosphere_optical_depth = chromosphere.calculate_optical_depth(photosphere_radius, wavelength);
     println!("Chromosphere Optical Depth: {}", chromosphere_optical_depth);
 
-    let chromosphere_emission_spectrum = chromosphere.get_emission_spectrum();
+    let chromosphere_emission_spectrum = chromosphere.get_emission_spectrum(&SpectralGrid::visible());
     println!("Chromosphere Emission Spectrum: {:?}", chromosphere_emission_spectrum);
+
+    // Height-time trajectories of the jets above the quiet Sun, and what
+    // they carry into the corona
+    for jet in &chromosphere.jets {
+        let base = chromosphere.launch_conditions(jet.launch_height);
+        println!(
+            "{}: launched at {:.0} km with {:.0} km/s, lifetime {:.0} s, reaches {:.0} km, mass {:.3e} kg",
+            jet.kind.name(),
+            jet.launch_height * 1.0e-3,
+            jet.launch_velocity * 1.0e-3,
+            jet.lifetime,
+            jet.maximum_height() * 1.0e-3,
+            jet.mass(&base)
+        );
+        for state in jet.trajectory(&base, jet.lifetime / JET_SAMPLES as f64) {
+            println!(
+                "  t = {:4.0} s: s {:6.0} km, h {:6.0} km, v {:6.1} km/s, T {:.3e} K, n_H {:.3e} m^-3, n_e {:.3e} m^-3",
+                state.age,
+                state.length * 1.0e-3,
+                state.height * 1.0e-3,
+                state.velocity * 1.0e-3,
+                state.temperature,
+                state.hydrogen_density,
+                state.electron_density
+            );
+        }
+    }
+    let fluxes = chromosphere.jet_fluxes();
+    println!(
+        "Jet mass flux: {:.3e} kg m^-2 s^-1 launched, {:.3e} kg m^-2 s^-1 into the corona",
+        fluxes.upward_mass, fluxes.coronal_mass
+    );
+    println!(
+        "Jet energy flux: {:.3e} W/m^2 kinetic at launch, {:.3e} W/m^2 into the corona",
+        fluxes.launched_energy, fluxes.coronal_energy
+    );
+
+    // Hydrogen ultraviolet of the quiet Sun from the non-LTE populations,
+    // and its irradiance at 1 AU
+    if let (Some(hydrogen), Some(atmosphere)) = (chromosphere.hydrogen(), &chromosphere.stratification) {
+        let ionization = hydrogen.ionization_fraction();
+        for height in DEPARTURE_HEIGHTS {
+            let k = atmosphere
+                .layers
+                .iter()
+                .enumerate()
+                .min_by(|a, b| (a.1.height - height).abs().total_cmp(&(b.1.height - height).abs()))
+                .map(|(k, _)| k)
+                .unwrap_or(0);
+            let layer = &atmosphere.layers[k];
+            println!(
+                "Hydrogen ionization at {:.0} km: {:.3e} non-LTE, {:.3e} in the model",
+                height * 1.0e-3,
+                ionization[k],
+                layer.proton_density() / layer.hydrogen_density
+            );
+        }
+        let centre = hydrogen.atom.line_wavelength(&hydrogen.atom.lines[0]);
+        let profile = SpectralGrid::from_points(
+            SpectralUnit::Wavelength,
+            LYMAN_ALPHA_OFFSETS.iter().map(|offset| centre + offset).collect(),
+        );
+        for (wavelength, intensity) in chromosphere.emission_spectrum(Some(&hydrogen), &profile) {
+            println!(
+                "Lyman alpha at {:+.2} nm: {:.4e} W m^-2 sr^-1 nm^-1 (T_b {:.0} K)",
+                (wavelength - centre) * 1.0e9,
+                intensity * 1.0e-9,
+                planck::brightness_temperature(wavelength, intensity)
+            );
+        }
+        // The disk is cell interior with the network over a fraction of it;
+        // the network is an order of magnitude brighter in Lyman alpha. The
+        // Balmer continuum is the continuum alone: without the blanketing of
+        // the near-ultraviolet metal lines it is some 2-3 times too bright
+        let network = non_lte::solve(
+            &ModelAtom::hydrogen(),
+            &StratifiedAtmosphere::bundled(AtmosphereVariant::Network),
+            &NonLteSettings::default(),
+        );
+        println!("Quiet-Sun disk: {:.0}% network", NETWORK_FILLING_FACTOR * 100.0);
+        for (name, (start, end, points)) in [
+            ("Lyman continuum", LYMAN_CONTINUUM),
+            ("Lyman alpha", LYMAN_ALPHA),
+            ("Balmer continuum (unblanketed)", BALMER_CONTINUUM),
+        ] {
+            let grid = SpectralGrid::linear(SpectralUnit::Wavelength, start, end, points);
+            let cell_interior = chromosphere.ultraviolet_irradiance(&hydrogen, &grid, ASTRONOMICAL_UNIT);
+            let spectrum: Vec<(f64, f64)> = cell_interior
+                .iter()
+                .zip(chromosphere.ultraviolet_irradiance(&network, &grid, ASTRONOMICAL_UNIT))
+                .map(|(&(wavelength, cell), (_, bright))| {
+                    (wavelength, (1.0 - NETWORK_FILLING_FACTOR) * cell + NETWORK_FILLING_FACTOR * bright)
+                })
+                .collect();
+            println!(
+                "{} irradiance at 1 AU ({:.1}-{:.1} nm): {:.4e} W/m^2",
+                name,
+                start * 1.0e9,
+                end * 1.0e9,
+                irradiance::total_irradiance(&spectrum)
+            );
+        }
+    }
+
+    // Structure of each bundled model and the brightness temperatures it
+    // produces, from the photospheric continua through the far-infrared
+    // continuum formed near the temperature minimum to the millimetre
+    // continuum formed in the chromospheric rise
+    // A model table given on the command line joins the bundled ones
+    let grid = SpectralGrid::from_points(SpectralUnit::Wavelength, BRIGHTNESS_WAVELENGTHS.to_vec());
+    // Model atoms (*.atom) given on the command line join the bundled ones as well
+    let mut models: Vec<StratifiedAtmosphere> =
+        AtmosphereVariant::ALL.iter().map(|&variant| StratifiedAtmosphere::bundled(variant)).collect();
+    let mut atoms = vec![ModelAtom::hydrogen(), ModelAtom::calcium_ii(), ModelAtom::magnesium_ii()];
+    for path in std::env::args().skip(1) {
+        if path.ends_with(".atom") {
+            match model_atom::load_model_atom(&path) {
+                Ok(atom) => atoms.push(atom),
+                Err(error) => println!("Could not load model atom {}: {}", path, error),
+            }
+            continue;
+        }
+        match stratification::load_atmosphere(&path) {
+            Ok(model) => models.push(model),
+            Err(error) => println!("Could not load chromosphere model {}: {}", path, error),
+        }
+    }
+    for atmosphere in models {
+        let minimum = atmosphere.temperature_minimum();
+        println!(
+            "{} model: temperature minimum {:.0} K at {:.0} km, n_e {:.2e} m^-3, n_H {:.2e} m^-3",
+            atmosphere.name,
+            minimum.temperature,
+            minimum.height * 1.0e-3,
+            minimum.electron_density,
+            minimum.hydrogen_density
+        );
+        for temperature in [6000.0, 1.0e4, 2.0e4] {
+            if let Some(height) = atmosphere.height_of_temperature(temperature) {
+                println!("  reaches {:.0} K at {:.0} km", temperature, height * 1.0e-3);
+            }
+        }
+        for height in [1000.0e3, 1500.0e3] {
+            let layer = atmosphere.at_height(height);
+            println!(
+                "  at {:.0} km: T {:.0} K, n_e {:.2e} m^-3, n_H {:.2e} m^-3, v_turb {:.1} km/s, P_gas {:.3e} Pa, P_turb {:.3e} Pa",
+                height * 1.0e-3,
+                layer.temperature,
+                layer.electron_density,
+                layer.hydrogen_density,
+                layer.microturbulence * 1.0e-3,
+                layer.gas_pressure(),
+                layer.turbulent_pressure()
+            );
+        }
+        let bottom = minimum.height;
+        let top = atmosphere.layers[0].height;
+        println!(
+            "  column mass above the temperature minimum: {:.3e} kg/m^2",
+            atmosphere.between(bottom, top).column_mass()
+        );
+        // The millimetre continuum forms in the rise, so the limb is brighter than disk centre
+        let limb_ratio =
+            atmosphere.emergent_intensity(LIMB_WAVELENGTH, 0.2) / atmosphere.emergent_intensity(LIMB_WAVELENGTH, 1.0);
+        println!("  I(mu = 0.2) / I(mu = 1) at {:.1} mm: {:.3}", LIMB_WAVELENGTH * 1.0e3, limb_ratio);
+
+        let chromosphere = Chromosphere {
+            stratification: Some(atmosphere),
+            ..chromosphere.clone()
+        };
+        for (wavelength, intensity) in chromosphere.get_emission_spectrum(&grid) {
+            println!(
+                "  brightness temperature at {:.4e} m: {:.0} K",
+                wavelength,
+                planck::brightness_temperature(wavelength, intensity)
+            );
+        }
+    }
+
+    // Non-LTE populations and line profiles in the quiet Sun; lines that may
+    // scatter with partial redistribution are shown both ways
+    let quiet = StratifiedAtmosphere::bundled(AtmosphereVariant::QuietSun);
+    for atom in &atoms {
+        let solution = non_lte::solve(atom, &quiet, &NonLteSettings::default());
+        println!(
+            "{} in the {} model: {} after {} iterations",
+            atom.name,
+            quiet.name,
+            if solution.converged { "converged" } else { "not converged" },
+            solution.iterations
+        );
+        for (level, data) in atom.levels.iter().enumerate() {
+            let departures = solution.departure_coefficients(level);
+            let samples: Vec<String> = DEPARTURE_HEIGHTS
+                .iter()
+                .map(|&height| {
+                    let k = quiet
+                        .layers
+                        .iter()
+                        .enumerate()
+                        .min_by(|a, b| (a.1.height - height).abs().total_cmp(&(b.1.height - height).abs()))
+                        .map(|(k, _)| k)
+                        .unwrap_or(0);
+                    format!("{:.3e}", departures[k])
+                })
+                .collect();
+            println!("  b({}) at 500/1000/1500/2000 km: {}", data.label, samples.join(" "));
+        }
+        let crd = atom.lines.iter().any(|l| l.redistribution == Redistribution::Partial).then(|| {
+            let settings = NonLteSettings {
+                partial_redistribution: false,
+                ..NonLteSettings::default()
+            };
+            non_lte::solve(atom, &quiet, &settings)
+        });
+        for (index, line) in atom.lines.iter().enumerate() {
+            let wavelength = atom.line_wavelength(line);
+            let describe = |profile: &[(f64, f64)]| -> String {
+                let brightness = |&(lambda, intensity): &(f64, f64)| planck::brightness_temperature(lambda, intensity);
+                let core = brightness(&profile[profile.len() / 2]);
+                let peak = profile.iter().map(brightness).fold(0.0, f64::max);
+                let wing = brightness(&profile[0]);
+                format!("T_b core {:.0} K, peak {:.0} K, wing {:.0} K", core, peak, wing)
+            };
+            print!(
+                "  {} {:.3} nm: {}",
+                atom.line_label(line),
+                wavelength * 1.0e9,
+                describe(&solution.line_profile(index, 1.0))
+            );
+            if let Some(height) = solution.formation_height(index, 0.0) {
+                print!(", core forms at {:.0} km", height * 1.0e-3);
+            }
+            println!();
+            if let (Some(crd), Redistribution::Partial) = (&crd, line.redistribution) {
+                println!("    with complete redistribution: {}", describe(&crd.line_profile(index, 1.0)));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn isothermal_opacity_follows_the_continuum() {
+        let photosphere_radius = 6.957e8;
+        let chromosphere = Chromosphere {
+            radius: photosphere_radius + 2.0e6,
+            temperature: CHROMOSPHERE_TEMPERATURE,
+            density: CHROMOSPHERE_DENSITY,
+            energy_generation: CHROMOSPHERE_ENERGY_GENERATION,
+            stratification: None,
+            jets: Vec::new(),
+        };
+        let column = chromosphere.column(photosphere_radius);
+        let base = |wavelength: f64| *column.opacities(wavelength).last().unwrap();
+
+        // H- bound-free falls to its threshold near 1.64 um, beyond which
+        // free-free absorption rises again; the Balmer edge adds a jump
+        let (blue, minimum, infrared) = (500.0e-9, 1.6e-6, 10.0e-6);
+        assert!(base(minimum) < 0.5 * base(blue), "{} vs {}", base(minimum), base(blue));
+        assert!(base(infrared) > 2.0 * base(minimum), "{} vs {}", base(infrared), base(minimum));
+        assert!(base(360.0e-9) > base(370.0e-9));
+
+        // The column optical depth follows the opacity
+        let depth = |wavelength: f64| chromosphere.calculate_optical_depth(photosphere_radius, wavelength);
+        assert!(depth(minimum) < depth(blue));
+    }
 }
@@ -0,0 +1,240 @@
+// Spicules and other chromospheric jets: plasma ejected upward along
+// inclined field lines from the middle chromosphere.
+//
+// Each jet launches a plug of chromospheric plasma at the density and
+// ionization of its launch height. The tip moves along the field as
+// s(t) = v0 t - a t^2 / 2; the plug keeps its particles and cross-section,
+// so the mean density of the column falls as it stretches. Type I spicules
+// (and the larger macrospicules) are decelerated by the shocks that drive
+// them or by gravity and fall back at the end of their lives. Type II
+// spicules rise fast with little deceleration while they are heated out of
+// the chromospheric passbands; plasma that reaches coronal temperatures
+// before the jet fades feeds the corona with mass and energy.
+//
+// Typical values follow De Pontieu et al. (2007), Pereira et al. (2012) for
+// spicules and Bohlin et al. (1975) for macrospicules.
+
+use super::stratification::ChromosphereLayer;
+use radiation::atomic_data;
+use radiation::planck::BOLTZMANN_CONSTANT;
+
+// Helium nuclei per hydrogen nucleus
+const HELIUM_ABUNDANCE: f64 = 0.085;
+// Plasma that a fading jet leaves at or above this temperature (K) stays in the corona
+const CORONAL_TEMPERATURE: f64 = 5.0e5;
+const SOLAR_SURFACE_GRAVITY: f64 = 274.0; // m/s^2
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum JetKind {
+    TypeISpicule,  // slow, shock-driven, rises and falls back
+    TypeIISpicule, // fast, heated and fading while still rising
+    Macrospicule,  // large cool jet of coronal holes, nearly ballistic
+}
+
+impl JetKind {
+    pub const ALL: [JetKind; 3] = [JetKind::TypeISpicule, JetKind::TypeIISpicule, JetKind::Macrospicule];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            JetKind::TypeISpicule => "type I spicule",
+            JetKind::TypeIISpicule => "type II spicule",
+            JetKind::Macrospicule => "macrospicule",
+        }
+    }
+
+    /// Whether the ejected plasma returns to the chromosphere.
+    pub fn falls_back(self) -> bool {
+        !matches!(self, JetKind::TypeIISpicule)
+    }
+}
+
+// A population of similar jets
+#[derive(Clone, Debug)]
+pub(crate) struct ChromosphericJet {
+    pub kind: JetKind,
+    pub launch_height: f64,             // m above the photosphere
+    pub launch_velocity: f64,           // m/s along the field
+    pub deceleration: f64,              // m/s^2 along the field
+    pub inclination: f64,               // rad from the vertical
+    pub lifetime: f64,                  // s; jets that fall back last 2 v0 / a
+    pub width: f64,                     // m
+    pub plug_length: f64,               // m, length of the launched column
+    pub final_temperature: Option<f64>, // K; None keeps the launch temperature
+    pub heating_time: f64,              // s, e-folding time of the heating
+    pub population: f64,                // jets present on the whole Sun at any moment
+}
+
+// A jet at one instant
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct JetState {
+    pub age: f64,              // s since launch
+    pub length: f64,           // m, tip distance along the field from the launch point
+    pub height: f64,           // m, tip height above the photosphere
+    pub velocity: f64,         // m/s along the field, negative when falling
+    pub temperature: f64,      // K
+    pub hydrogen_density: f64, // m^-3, mean over the column
+    pub electron_density: f64, // m^-3
+}
+
+// Mass and energy carried upward by the jets, per unit area of the surface
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct JetFluxes {
+    pub upward_mass: f64,     // kg m^-2 s^-1, everything launched
+    pub coronal_mass: f64,    // kg m^-2 s^-1, the part that stays in the corona
+    pub launched_energy: f64, // W/m^2, kinetic energy at launch
+    pub coronal_energy: f64,  // W/m^2, kinetic, thermal and potential energy left in the corona
+}
+
+impl ChromosphericJet {
+    /// Typical jets of one kind.
+    pub fn typical(kind: JetKind) -> Self {
+        match kind {
+            JetKind::TypeISpicule => ChromosphericJet {
+                kind,
+                launch_height: 1800.0e3,
+                launch_velocity: 25.0e3,
+                deceleration: 120.0,
+                inclination: 0.5,
+                lifetime: 2.0 * 25.0e3 / 120.0,
+                width: 700.0e3,
+                plug_length: 1.0e6,
+                final_temperature: None,
+                heating_time: f64::INFINITY,
+                population: 1.0e5,
+            },
+            JetKind::TypeIISpicule => ChromosphericJet {
+                kind,
+                launch_height: 2000.0e3,
+                launch_velocity: 100.0e3,
+                deceleration: 50.0,
+                inclination: 0.5,
+                lifetime: 100.0,
+                width: 400.0e3,
+                plug_length: 2.0e6,
+                final_temperature: Some(1.0e6),
+                heating_time: 40.0,
+                population: 2.0e5,
+            },
+            // Decelerated by gravity along the field alone
+            JetKind::Macrospicule => {
+                let deceleration = SOLAR_SURFACE_GRAVITY * 0.35f64.cos();
+                ChromosphericJet {
+                    kind,
+                    launch_height: 2000.0e3,
+                    launch_velocity: 110.0e3,
+                    deceleration,
+                    inclination: 0.35,
+                    lifetime: 2.0 * 110.0e3 / deceleration,
+                    width: 5.0e6,
+                    plug_length: 3.0e6,
+                    final_temperature: None,
+                    heating_time: f64::INFINITY,
+                    population: 100.0,
+                }
+            }
+        }
+    }
+
+    pub fn cross_section(&self) -> f64 {
+        std::f64::consts::PI * 0.25 * self.width * self.width
+    }
+
+    /// Mass (kg) of one jet launched from `base`.
+    pub fn mass(&self, base: &ChromosphereLayer) -> f64 {
+        base.density() * self.cross_section() * self.plug_length
+    }
+
+    /// Greatest tip height (m) above the photosphere.
+    pub fn maximum_height(&self) -> f64 {
+        let rise_time = if self.deceleration > 0.0 {
+            (self.launch_velocity / self.deceleration).min(self.lifetime)
+        } else {
+            self.lifetime
+        };
+        self.launch_height + self.tip_length(rise_time) * self.inclination.cos()
+    }
+
+    /// The jet `age` seconds after launch from `base`, or `None` once it
+    /// has faded or fallen back.
+    pub fn state(&self, base: &ChromosphereLayer, age: f64) -> Option<JetState> {
+        if !(0.0..=self.lifetime).contains(&age) {
+            return None;
+        }
+        let length = self.tip_length(age);
+        let hydrogen_density = base.hydrogen_density * self.plug_length / (self.plug_length + length);
+        let temperature = match self.final_temperature {
+            Some(hot) => hot + (base.temperature - hot) * (-age / self.heating_time).exp(),
+            None => base.temperature,
+        };
+        // Hydrogen keeps its launch ionization until heating ionizes it further
+        let cool_electrons = hydrogen_density * base.electron_density / base.hydrogen_density;
+        let electron_density = if temperature > base.temperature {
+            cool_electrons.max(atomic_data::electron_density(temperature, hydrogen_density))
+        } else {
+            cool_electrons
+        };
+        Some(JetState {
+            age,
+            length,
+            height: self.launch_height + length * self.inclination.cos(),
+            velocity: self.launch_velocity - self.deceleration * age,
+            temperature,
+            hydrogen_density,
+            electron_density,
+        })
+    }
+
+    /// Height-time trajectory sampled every `step` seconds over the lifetime.
+    pub fn trajectory(&self, base: &ChromosphereLayer, step: f64) -> Vec<JetState> {
+        let samples = (self.lifetime / step).floor() as usize;
+        (0..=samples)
+            .filter_map(|i| self.state(base, i as f64 * step))
+            .collect()
+    }
+
+    /// Mass and energy fluxes of the population over a surface of
+    /// `surface_area` m^2, with jets launched from `base`.
+    pub fn fluxes(&self, base: &ChromosphereLayer, surface_area: f64) -> JetFluxes {
+        // Jets born per unit area and time keep `population` alive at once
+        let rate = self.population / (self.lifetime * surface_area);
+        let mass = self.mass(base);
+        let mut fluxes = JetFluxes {
+            upward_mass: rate * mass,
+            launched_energy: rate * 0.5 * mass * self.launch_velocity * self.launch_velocity,
+            ..JetFluxes::default()
+        };
+        if self.kind.falls_back() {
+            return fluxes;
+        }
+        let Some(end) = self.state(base, self.lifetime) else {
+            return fluxes;
+        };
+        if end.temperature < CORONAL_TEMPERATURE {
+            return fluxes;
+        }
+        // Enthalpy 5/2 k T of every particle, per hydrogen nucleus
+        let particles = 1.0 + HELIUM_ABUNDANCE + end.electron_density / end.hydrogen_density;
+        let enthalpy = 2.5 * particles * BOLTZMANN_CONSTANT * end.temperature / atomic_data::mass_per_hydrogen();
+        let kinetic = 0.5 * end.velocity * end.velocity;
+        let potential = SOLAR_SURFACE_GRAVITY * (end.height - self.launch_height);
+        fluxes.coronal_mass = rate * mass;
+        fluxes.coronal_energy = rate * mass * (enthalpy + kinetic + potential);
+        fluxes
+    }
+
+    // Distance of the tip along the field, never below the launch point
+    fn tip_length(&self, age: f64) -> f64 {
+        (self.launch_velocity * age - 0.5 * self.deceleration * age * age).max(0.0)
+    }
+}
+
+impl JetFluxes {
+    pub fn add(self, other: JetFluxes) -> JetFluxes {
+        JetFluxes {
+            upward_mass: self.upward_mass + other.upward_mass,
+            coronal_mass: self.coronal_mass + other.coronal_mass,
+            launched_energy: self.launched_energy + other.launched_energy,
+            coronal_energy: self.coronal_energy + other.coronal_energy,
+        }
+    }
+}
@@ -0,0 +1,257 @@
+// Model atoms for the non-LTE solver: energy levels, bound-bound
+// transitions, bound-free continua and extra collisional couplings, read
+// from plain-text files (format documented in data/atoms/hydrogen.atom).
+
+use radiation::atomic_data::{self, Element};
+use radiation::planck::{PLANCK_CONSTANT, SPEED_OF_LIGHT};
+
+use std::error::Error;
+use std::fs;
+
+// Atoms shipped with the repository
+const HYDROGEN_ATOM: &str = include_str!("../../data/atoms/hydrogen.atom");
+const CALCIUM_II_ATOM: &str = include_str!("../../data/atoms/calcium_ii.atom");
+const MAGNESIUM_II_ATOM: &str = include_str!("../../data/atoms/magnesium_ii.atom");
+
+// Level energies are given in cm^-1; E = h c * (energy * 100)
+const WAVENUMBER_TO_JOULE: f64 = PLANCK_CONSTANT * SPEED_OF_LIGHT * 100.0;
+
+#[derive(Clone, Debug)]
+pub(crate) struct AtomicLevel {
+    pub label: String,
+    pub energy: f64,             // J above the ground level
+    pub statistical_weight: f64, // g
+    pub stage: u32,              // 1 = neutral, 2 = singly ionized, ...
+}
+
+// Frequency redistribution in a scattering line
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Redistribution {
+    Complete, // CRD: emission profile equals absorption profile
+    Partial,  // PRD: coherent scattering in the damping wings
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct AtomicLine {
+    pub upper: usize,
+    pub lower: usize,
+    pub oscillator_strength: f64,     // f_lu
+    pub radiative_damping: f64,       // s^-1
+    pub log_gamma_stark: f64,         // log10(s^-1 cm^3) per electron at 10^4 K
+    pub log_gamma_van_der_waals: f64, // log10(s^-1 cm^3) per hydrogen atom at 10^4 K
+    pub collision_strength: f64,      // effective Omega; 0 selects van Regemorter
+    pub redistribution: Redistribution,
+    pub points: usize, // frequency points from line centre outward
+    pub extent: f64,   // m/s, outermost Doppler offset
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct AtomicContinuum {
+    pub lower: usize,
+    pub cross_section: f64, // m^2 at the edge, falling as (lambda / lambda_edge)^3
+    pub points: usize,      // wavelengths between the edge and half of it
+}
+
+// Purely collisional coupling between two bound levels
+#[derive(Clone, Debug)]
+pub(crate) struct CollisionalCoupling {
+    pub upper: usize,
+    pub lower: usize,
+    pub collision_strength: f64,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct ModelAtom {
+    pub name: String,
+    pub element: &'static Element,
+    pub levels: Vec<AtomicLevel>, // the last level is the continuum
+    pub lines: Vec<AtomicLine>,
+    pub continua: Vec<AtomicContinuum>,
+    pub collisions: Vec<CollisionalCoupling>,
+}
+
+impl ModelAtom {
+    /// The hydrogen atom bundled with the repository (five levels and H II).
+    pub fn hydrogen() -> Self {
+        parse_model_atom("hydrogen", HYDROGEN_ATOM).expect("bundled hydrogen atom is well formed")
+    }
+
+    /// The Ca II atom bundled with the repository (H, K and the infrared triplet).
+    pub fn calcium_ii() -> Self {
+        parse_model_atom("Ca II", CALCIUM_II_ATOM).expect("bundled Ca II atom is well formed")
+    }
+
+    /// The Mg II atom bundled with the repository (h and k).
+    pub fn magnesium_ii() -> Self {
+        parse_model_atom("Mg II", MAGNESIUM_II_ATOM).expect("bundled Mg II atom is well formed")
+    }
+
+    pub fn continuum_level(&self) -> usize {
+        self.levels.len() - 1
+    }
+
+    /// Energy (J) needed to ionize `level` into the continuum.
+    pub fn ionization_energy(&self, level: usize) -> f64 {
+        self.levels[self.continuum_level()].energy - self.levels[level].energy
+    }
+
+    /// Vacuum wavelength (m) of a line.
+    pub fn line_wavelength(&self, line: &AtomicLine) -> f64 {
+        PLANCK_CONSTANT * SPEED_OF_LIGHT / (self.levels[line.upper].energy - self.levels[line.lower].energy)
+    }
+
+    /// Vacuum wavelength (m) of a continuum edge.
+    pub fn edge_wavelength(&self, continuum: &AtomicContinuum) -> f64 {
+        PLANCK_CONSTANT * SPEED_OF_LIGHT / self.ionization_energy(continuum.lower)
+    }
+
+    /// Einstein A_ul (s^-1) of a line from its oscillator strength.
+    pub fn einstein_a(&self, line: &AtomicLine) -> f64 {
+        let wavelength = self.line_wavelength(line);
+        let ratio = self.levels[line.lower].statistical_weight / self.levels[line.upper].statistical_weight;
+        // A = 2 pi e^2 nu^2 g_l f / (eps0 m_e c^3 g_u), or 6.670e13 g_l f / (g_u lambda^2) with lambda in nm
+        6.670e13 * ratio * line.oscillator_strength / (wavelength * 1.0e9).powi(2)
+    }
+
+    /// Label of a line as "upper-lower".
+    pub fn line_label(&self, line: &AtomicLine) -> String {
+        format!("{}-{}", self.levels[line.upper].label, self.levels[line.lower].label)
+    }
+}
+
+/// Parses a model atom (format documented in data/atoms/hydrogen.atom).
+pub(crate) fn parse_model_atom(name: &str, text: &str) -> Result<ModelAtom, Box<dyn Error>> {
+    let mut element = None;
+    let mut levels: Vec<AtomicLevel> = Vec::new();
+    let mut lines = Vec::new();
+    let mut continua = Vec::new();
+    let mut collisions = Vec::new();
+
+    for (number, raw) in text.lines().enumerate() {
+        let content = raw.split('#').next().unwrap_or("");
+        let fields: Vec<&str> = content.split_whitespace().collect();
+        if fields.is_empty() {
+            continue;
+        }
+        let context =
+            |message: String| -> Box<dyn Error> { format!("{} line {}: {}", name, number + 1, message).into() };
+        let expect = |count: usize| -> Result<(), Box<dyn Error>> {
+            if fields.len() < count {
+                return Err(context(format!(
+                    "{} needs {} fields, found {}",
+                    fields[0],
+                    count,
+                    fields.len()
+                )));
+            }
+            Ok(())
+        };
+        let value = |i: usize| -> Result<f64, Box<dyn Error>> {
+            fields[i]
+                .parse::<f64>()
+                .map_err(|e| context(format!("column {}: {}", i + 1, e)))
+        };
+        let level_index = |label: &str| -> Result<usize, Box<dyn Error>> {
+            levels
+                .iter()
+                .position(|l| l.label == label)
+                .ok_or_else(|| context(format!("unknown level {}", label)))
+        };
+
+        match fields[0] {
+            "element" => {
+                expect(2)?;
+                element = Some(
+                    atomic_data::element(fields[1]).ok_or_else(|| context(format!("unknown element {}", fields[1])))?,
+                );
+            }
+            "level" => {
+                expect(5)?;
+                levels.push(AtomicLevel {
+                    label: fields[1].to_string(),
+                    energy: value(2)? * WAVENUMBER_TO_JOULE,
+                    statistical_weight: value(3)?,
+                    stage: fields[4].parse().map_err(|e| context(format!("stage: {}", e)))?,
+                });
+            }
+            "line" => {
+                expect(11)?;
+                let redistribution = match fields[8] {
+                    "CRD" => Redistribution::Complete,
+                    "PRD" => Redistribution::Partial,
+                    other => return Err(context(format!("unknown redistribution {}", other))),
+                };
+                lines.push(AtomicLine {
+                    upper: level_index(fields[1])?,
+                    lower: level_index(fields[2])?,
+                    oscillator_strength: value(3)?,
+                    radiative_damping: value(4)?,
+                    log_gamma_stark: value(5)?,
+                    log_gamma_van_der_waals: value(6)?,
+                    collision_strength: value(7)?,
+                    redistribution,
+                    points: value(9)? as usize,
+                    extent: value(10)? * 1.0e3,
+                });
+            }
+            "continuum" => {
+                expect(4)?;
+                continua.push(AtomicContinuum {
+                    lower: level_index(fields[1])?,
+                    cross_section: value(2)?,
+                    points: value(3)? as usize,
+                });
+            }
+            "collision" => {
+                expect(4)?;
+                collisions.push(CollisionalCoupling {
+                    upper: level_index(fields[1])?,
+                    lower: level_index(fields[2])?,
+                    collision_strength: value(3)?,
+                });
+            }
+            other => return Err(context(format!("unknown record {}", other))),
+        }
+    }
+
+    let element = element.ok_or_else(|| format!("{}: missing element record", name))?;
+    if levels.len() < 2 {
+        return Err(format!("{}: an atom needs at least one bound level and the continuum", name).into());
+    }
+    let continuum_stage = levels[levels.len() - 1].stage;
+    if levels[..levels.len() - 1]
+        .iter()
+        .any(|l| l.stage + 1 != continuum_stage)
+    {
+        return Err(format!(
+            "{}: bound levels must lie one stage below the final continuum level",
+            name
+        )
+        .into());
+    }
+    for line in &lines {
+        if levels[line.upper].energy <= levels[line.lower].energy || line.upper == levels.len() - 1 {
+            return Err(format!(
+                "{}: line {}-{} is not a bound-bound transition",
+                name, line.upper, line.lower
+            )
+            .into());
+        }
+        if line.points < 2 {
+            return Err(format!("{}: a line needs at least two frequency points", name).into());
+        }
+    }
+    Ok(ModelAtom {
+        name: name.to_string(),
+        element,
+        levels,
+        lines,
+        continua,
+        collisions,
+    })
+}
+
+/// Loads a model atom from a local file.
+pub(crate) fn load_model_atom(path: &str) -> Result<ModelAtom, Box<dyn Error>> {
+    parse_model_atom(path, &fs::read_to_string(path)?)
+}
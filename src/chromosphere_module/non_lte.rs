@@ -0,0 +1,726 @@
+// Non-LTE statistical equilibrium of a model atom in a stratified chromosphere.
+//
+// Level populations follow from the balance of radiative and collisional
+// rates at every depth, with the radiation field computed self-consistently
+// by multilevel accelerated lambda iteration (Rybicki & Hummer 1991): the
+// diagonal of the Lambda operator is taken implicitly in the rate equations.
+// Optically thick continua such as the Lyman continuum still take a few
+// hundred iterations, as the local operator carries information between
+// depth points slowly where the grid is thin in optical depth. Each
+// transition is solved on its own frequency grid against the LTE continuum
+// of the atmosphere, so overlapping transitions do not see each other. The
+// background leaves out the hydrogen bound-free continua: in LTE at the
+// temperatures of the upper chromosphere they would flood the ultraviolet
+// with ionizing radiation, and they belong to a hydrogen solution of their
+// own. The temperature and electron density are those of the model.
+//
+// Lines flagged PRD can scatter with partial frequency redistribution in the
+// core-wing approximation to angle-averaged R_II (Gayley 2002): complete
+// redistribution in the Doppler core, coherent scattering in the damping
+// wings, with the coherent fraction set by elastic collisions. The emission
+// profile lags the radiation field by one iteration and is under-relaxed,
+// which keeps the coupled iteration stable.
+
+use super::model_atom::{AtomicLine, ModelAtom, Redistribution};
+use super::stratification::{ChromosphereLayer, StratifiedAtmosphere};
+use radiation::atomic_data::ELECTRON_MASS;
+use radiation::continuum;
+use radiation::line_profile;
+use radiation::numerics;
+use radiation::planck::{self, BOLTZMANN_CONSTANT, PLANCK_CONSTANT, SPEED_OF_LIGHT};
+use radiation::transfer;
+
+use std::f64::consts::PI;
+
+// pi e^2 / (4 pi eps0 m_e c): integrated line cross section per unit f (m^2 Hz)
+const LINE_CROSS_SECTION: f64 = 2.654e-6;
+// Collision rate coefficient 8.63e-6 cm^3 s^-1 K^1/2 per unit Omega, in SI
+const COLLISION_RATE: f64 = 8.629e-12;
+// Clustering of the line frequency grid toward line centre
+const LINE_GRID_STRETCH: f64 = 4.0;
+// Offset (Doppler widths) over which PRD lines turn from the CRD core to coherent wings
+const CORE_WING_TRANSITION: f64 = 3.0;
+// Fraction of each PRD emission-profile update that is applied
+const REDISTRIBUTION_RELAXATION: f64 = 0.5;
+// Populations below this (m^-3) are clamped after each iteration
+const POPULATION_FLOOR: f64 = 1.0e-30;
+
+/// Iteration controls of the solver.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct NonLteSettings {
+    pub partial_redistribution: bool, // honour PRD flags; otherwise every line is CRD
+    pub max_iterations: usize,
+    pub tolerance: f64, // maximum relative population change at convergence
+}
+
+impl Default for NonLteSettings {
+    fn default() -> Self {
+        NonLteSettings {
+            partial_redistribution: true,
+            max_iterations: 500,
+            tolerance: 1.0e-3,
+        }
+    }
+}
+
+// One radiative transition on its own frequency grid. Per unit population,
+// the lower level absorbs sigma, the upper level absorbs (stimulated
+// emission) ratio * sigma * rho and emits 2 h nu^3 / c^2 times that.
+#[derive(Clone, Debug)]
+struct Transition {
+    lower: usize,
+    upper: usize,
+    line: Option<usize>, // index into the atom's lines; None for a continuum
+    offsets: Vec<f64>,   // m/s from line centre, or 0 for continua
+    frequencies: Vec<f64>,
+    weights: Vec<f64>,                    // Hz, quadrature over the full transition
+    cross_sections: Vec<Vec<f64>>,        // [frequency][depth], m^2
+    upper_ratio: Vec<Vec<f64>>,           // [frequency][depth]
+    doppler_offsets: Vec<Vec<f64>>,       // [frequency][depth], offset in local Doppler widths
+    coherence: Vec<f64>,                  // [depth], coherent fraction of PRD scattering
+    redistribution: Vec<Vec<f64>>,        // [frequency][depth], rho = psi / phi
+    mean_intensity: Vec<Vec<f64>>,        // [frequency][depth], from the last iteration
+    background_opacity: Vec<Vec<f64>>,    // [frequency][depth], m^-1
+    background_emissivity: Vec<Vec<f64>>, // [frequency][depth], W m^-3 Hz^-1 sr^-1
+    partial: bool,
+}
+
+impl Transition {
+    // Opacity and emissivity of the transition alone at one frequency
+    fn coefficients(&self, j: usize, populations: &[Vec<f64>]) -> (Vec<f64>, Vec<f64>) {
+        let emission = 2.0 * PLANCK_CONSTANT * self.frequencies[j].powi(3) / (SPEED_OF_LIGHT * SPEED_OF_LIGHT);
+        populations
+            .iter()
+            .enumerate()
+            .map(|(k, n)| {
+                let absorption = self.cross_sections[j][k];
+                let stimulated = self.upper_ratio[j][k] * absorption * self.redistribution[j][k];
+                (
+                    n[self.lower] * absorption - n[self.upper] * stimulated,
+                    n[self.upper] * stimulated * emission,
+                )
+            })
+            .unzip()
+    }
+
+    // Emission profile ratio rho at a Doppler offset (m/s) within the line
+    // grid at depth `k`, interpolated linearly
+    fn redistribution_at(&self, offset: f64, k: usize) -> f64 {
+        let offset = offset.abs();
+        match self.offsets.iter().position(|&v| v >= offset) {
+            Some(0) => self.redistribution[0][k],
+            Some(j) => {
+                let fraction = (offset - self.offsets[j - 1]) / (self.offsets[j] - self.offsets[j - 1]);
+                self.redistribution[j - 1][k] + fraction * (self.redistribution[j][k] - self.redistribution[j - 1][k])
+            }
+            None => self.redistribution[self.offsets.len() - 1][k],
+        }
+    }
+
+    // Optical depth, source function and total opacity at one frequency,
+    // given the opacity and emissivity of the transition itself
+    fn formal_inputs(
+        &self,
+        j: usize,
+        opacity: &[f64],
+        emissivity: &[f64],
+        atmosphere: &StratifiedAtmosphere,
+    ) -> (Vec<f64>, Vec<f64>, Vec<f64>) {
+        let total: Vec<f64> = opacity
+            .iter()
+            .zip(&self.background_opacity[j])
+            .map(|(a, b)| a + b)
+            .collect();
+        let source = emissivity
+            .iter()
+            .zip(&self.background_emissivity[j])
+            .zip(&total)
+            .map(|((a, b), chi)| (a + b) / chi)
+            .collect();
+        let heights: Vec<f64> = atmosphere.layers.iter().map(|l| l.height).collect();
+        let densities: Vec<f64> = atmosphere.layers.iter().map(|l| l.density()).collect();
+        let mass_opacities: Vec<f64> = total.iter().zip(&densities).map(|(chi, rho)| chi / rho).collect();
+        let tau = transfer::optical_depth_scale(&heights, &densities, &mass_opacities);
+        (tau, source, total)
+    }
+}
+
+/// Converged (or last) state of the solver.
+#[derive(Clone, Debug)]
+pub(crate) struct NonLteSolution {
+    pub atom: ModelAtom,
+    pub atmosphere: StratifiedAtmosphere,
+    pub populations: Vec<Vec<f64>>,     // [depth][level], m^-3
+    pub lte_populations: Vec<Vec<f64>>, // [depth][level], m^-3
+    pub iterations: usize,
+    pub converged: bool,
+    transitions: Vec<Transition>,
+}
+
+impl NonLteSolution {
+    /// Departure coefficient b = n / n_LTE of `level` at every depth.
+    pub fn departure_coefficients(&self, level: usize) -> Vec<f64> {
+        self.populations
+            .iter()
+            .zip(&self.lte_populations)
+            .map(|(n, lte)| n[level] / lte[level])
+            .collect()
+    }
+
+    // The transition that carries a line of the atom
+    fn line_transition(&self, line: usize) -> &Transition {
+        self.transitions
+            .iter()
+            .find(|t| t.line == Some(line))
+            .expect("every line has a transition")
+    }
+
+    /// Emergent line profile at direction cosine `mu`, as (vacuum wavelength
+    /// in m, I_lambda in W m^-2 sr^-1 m^-1) pairs from blue to red.
+    pub fn line_profile(&self, line: usize, mu: f64) -> Vec<(f64, f64)> {
+        let transition = self.line_transition(line);
+        let centre = self.atom.line_wavelength(&self.atom.lines[line]);
+        let half: Vec<(f64, f64)> = (0..transition.frequencies.len())
+            .map(|j| {
+                let (opacity, emissivity) = transition.coefficients(j, &self.populations);
+                let (tau, source, _) = transition.formal_inputs(j, &opacity, &emissivity, &self.atmosphere);
+                let intensity = transfer::solve_outgoing_ray(&tau, &source, mu, None)[0];
+                let wavelength = SPEED_OF_LIGHT / transition.frequencies[j];
+                (
+                    transition.offsets[j],
+                    intensity * SPEED_OF_LIGHT / (wavelength * wavelength),
+                )
+            })
+            .collect();
+        // The atmosphere is static, so the profile is symmetric about line centre
+        half.iter()
+            .rev()
+            .map(|&(offset, intensity)| (centre * (1.0 - offset / SPEED_OF_LIGHT), intensity))
+            .chain(
+                half.iter()
+                    .skip(1)
+                    .map(|&(offset, intensity)| (centre * (1.0 + offset / SPEED_OF_LIGHT), intensity)),
+            )
+            .collect()
+    }
+
+    /// Emergent intensity I_lambda (W m^-2 sr^-1 m^-1) at any wavelength and
+    /// direction cosine `mu`, with every line and continuum of the atom over
+    /// the background continuum. As in the solution, each line extends only
+    /// over its own frequency grid, and PRD lines keep their emission profile.
+    pub fn emergent_intensity(&self, wavelength: f64, mu: f64) -> f64 {
+        let (tau, source) = self.formal_inputs_at(wavelength);
+        transfer::solve_outgoing_ray(&tau, &source, mu, None)[0] * SPEED_OF_LIGHT / (wavelength * wavelength)
+    }
+
+    /// Emergent flux F_lambda = 2 pi * integral of I(mu) mu dmu (W m^-2 m^-1).
+    pub fn emergent_flux(&self, wavelength: f64) -> f64 {
+        let (tau, source) = self.formal_inputs_at(wavelength);
+        let angular_integral: f64 = numerics::gauss_legendre_unit_interval()
+            .into_iter()
+            .map(|(mu, weight)| weight * mu * transfer::solve_outgoing_ray(&tau, &source, mu, None)[0])
+            .sum();
+        2.0 * PI * angular_integral * SPEED_OF_LIGHT / (wavelength * wavelength)
+    }
+
+    /// Fraction of the element in the continuum (next ionization stage) at every depth.
+    pub fn ionization_fraction(&self) -> Vec<f64> {
+        let continuum = self.atom.continuum_level();
+        self.populations
+            .iter()
+            .map(|n| n[continuum] / n.iter().sum::<f64>())
+            .collect()
+    }
+
+    // Optical depth and source function per frequency unit at one
+    // wavelength, from all transitions together
+    fn formal_inputs_at(&self, wavelength: f64) -> (Vec<f64>, Vec<f64>) {
+        let atom = &self.atom;
+        let frequency = SPEED_OF_LIGHT / wavelength;
+        let emission = 2.0 * PLANCK_CONSTANT * frequency.powi(3) / (SPEED_OF_LIGHT * SPEED_OF_LIGHT);
+        let continuum_level = atom.continuum_level();
+        let layers = &self.atmosphere.layers;
+        let (opacity, emissivity): (Vec<f64>, Vec<f64>) = layers
+            .iter()
+            .zip(&self.populations)
+            .enumerate()
+            .map(|(k, (layer, n))| {
+                let state = layer.continuum_state();
+                let absorption = continuum::background_absorption(&state, wavelength);
+                let mut opacity = absorption + continuum::scattering(&state);
+                let mut emissivity = absorption * planck::spectral_radiance_frequency(frequency, layer.temperature);
+                for (index, line) in atom.lines.iter().enumerate() {
+                    let centre = SPEED_OF_LIGHT / atom.line_wavelength(line);
+                    let offset = SPEED_OF_LIGHT * (frequency / centre - 1.0);
+                    if offset.abs() > line.extent {
+                        continue;
+                    }
+                    let (width, damping, _) = line_broadening(atom, line, layer);
+                    let cross_section = LINE_CROSS_SECTION
+                        * line.oscillator_strength
+                        * line_profile::voigt(damping, (frequency - centre) / width)
+                        / (PI.sqrt() * width);
+                    let transition = self.line_transition(index);
+                    let rho = if transition.partial {
+                        transition.redistribution_at(offset, k)
+                    } else {
+                        1.0
+                    };
+                    let ratio = atom.levels[line.lower].statistical_weight / atom.levels[line.upper].statistical_weight;
+                    let stimulated = ratio * cross_section * rho;
+                    opacity += n[line.lower] * cross_section - n[line.upper] * stimulated;
+                    emissivity += n[line.upper] * stimulated * emission;
+                }
+                for edge in &atom.continua {
+                    let threshold = SPEED_OF_LIGHT / atom.edge_wavelength(edge);
+                    if frequency < threshold {
+                        continue;
+                    }
+                    let cross_section = edge.cross_section * (threshold / frequency).powi(3);
+                    let stimulated = saha_boltzmann_ratio(atom, edge.lower, layer)
+                        * (-PLANCK_CONSTANT * frequency / (BOLTZMANN_CONSTANT * layer.temperature)).exp()
+                        * cross_section;
+                    opacity += n[edge.lower] * cross_section - n[continuum_level] * stimulated;
+                    emissivity += n[continuum_level] * stimulated * emission;
+                }
+                (opacity, emissivity)
+            })
+            .unzip();
+        let heights: Vec<f64> = layers.iter().map(|l| l.height).collect();
+        let densities: Vec<f64> = layers.iter().map(|l| l.density()).collect();
+        let mass_opacities: Vec<f64> = opacity.iter().zip(&densities).map(|(chi, rho)| chi / rho).collect();
+        let source = emissivity.iter().zip(&opacity).map(|(eta, chi)| eta / chi).collect();
+        (
+            transfer::optical_depth_scale(&heights, &densities, &mass_opacities),
+            source,
+        )
+    }
+
+    /// Height (m) where the vertical optical depth reaches unity at a Doppler
+    /// offset (m/s) from the centre of a line, or `None` if the whole model is thinner.
+    pub fn formation_height(&self, line: usize, offset: f64) -> Option<f64> {
+        let transition = self.line_transition(line);
+        let j = transition
+            .offsets
+            .iter()
+            .enumerate()
+            .min_by(|a, b| (a.1 - offset.abs()).abs().total_cmp(&(b.1 - offset.abs()).abs()))
+            .map(|(j, _)| j)?;
+        let (opacity, emissivity) = transition.coefficients(j, &self.populations);
+        let (tau, _, _) = transition.formal_inputs(j, &opacity, &emissivity, &self.atmosphere);
+        let layers = &self.atmosphere.layers;
+        if tau[0] >= 1.0 {
+            return Some(layers[0].height);
+        }
+        tau.windows(2)
+            .zip(layers.windows(2))
+            .find(|(t, _)| t[1] >= 1.0)
+            .map(|(t, l)| {
+                let fraction = (1.0 - t[0]) / (t[1] - t[0]);
+                l[0].height + fraction * (l[1].height - l[0].height)
+            })
+    }
+}
+
+/// Solves the statistical equilibrium of `atom` in `atmosphere`.
+///
+/// # Arguments
+///
+/// * `atom` - The model atom; its total density is the element abundance times n_H.
+/// * `atmosphere` - The stratified model that sets T, n_e, n_H and the microturbulence.
+/// * `settings` - Iteration controls and the PRD switch.
+///
+/// # Returns
+///
+/// The level populations with their LTE counterparts and the state needed
+/// for emergent profiles. Starts from LTE.
+pub(crate) fn solve(atom: &ModelAtom, atmosphere: &StratifiedAtmosphere, settings: &NonLteSettings) -> NonLteSolution {
+    let layers = &atmosphere.layers;
+    let totals: Vec<f64> = layers
+        .iter()
+        .map(|l| atom.element.abundance() * l.hydrogen_density)
+        .collect();
+    let lte_populations: Vec<Vec<f64>> = layers
+        .iter()
+        .zip(&totals)
+        .map(|(layer, &total)| lte_populations(atom, layer, total))
+        .collect();
+    let collisions: Vec<Vec<Vec<f64>>> = layers.iter().map(|layer| collision_rates(atom, layer)).collect();
+    let mut transitions = build_transitions(atom, atmosphere, settings);
+
+    let mut populations = lte_populations.clone();
+    let mut converged = false;
+    let mut iterations = 0;
+
+    while iterations < settings.max_iterations && !converged {
+        iterations += 1;
+        // Preconditioned rates: rates[k][from][to] per unit population of `from`
+        let mut rates = collisions.clone();
+        let mut actual = collisions.clone();
+        for transition in transitions.iter_mut() {
+            for j in 0..transition.frequencies.len() {
+                let (opacity, emissivity) = transition.coefficients(j, &populations);
+                let (tau, source, total) = transition.formal_inputs(j, &opacity, &emissivity, atmosphere);
+                let (mean, diagonal) = transfer::mean_intensity(&tau, &source);
+                let frequency = transition.frequencies[j];
+                let emission = 2.0 * PLANCK_CONSTANT * frequency.powi(3) / (SPEED_OF_LIGHT * SPEED_OF_LIGHT);
+                let factor = transition.weights[j] * 4.0 * PI / (PLANCK_CONSTANT * frequency);
+                let (lower, upper) = (transition.lower, transition.upper);
+                for k in 0..layers.len() {
+                    let psi = diagonal[k] / total[k];
+                    let effective = mean[k] - psi * emissivity[k];
+                    let absorption = transition.cross_sections[j][k];
+                    let stimulated = transition.upper_ratio[j][k] * absorption * transition.redistribution[j][k];
+                    rates[k][lower][upper] += factor * absorption * effective;
+                    rates[k][upper][lower] +=
+                        factor * (stimulated * effective + stimulated * emission * (1.0 - opacity[k] * psi));
+                    actual[k][lower][upper] += factor * absorption * mean[k];
+                    actual[k][upper][lower] += factor * stimulated * (mean[k] + emission);
+                }
+                transition.mean_intensity[j] = mean;
+            }
+        }
+        // The emission profiles of the next iteration, from the radiation
+        // field and populations of this one
+        let out_rates: Vec<Vec<f64>> = actual
+            .iter()
+            .map(|r| r.iter().map(|row| row.iter().sum()).collect())
+            .collect();
+        for transition in transitions.iter_mut().filter(|t| t.partial) {
+            update_redistribution(atom, transition, &populations, &out_rates);
+        }
+
+        let updated: Vec<Vec<f64>> = rates
+            .iter()
+            .zip(&populations)
+            .zip(&totals)
+            .map(|((r, current), &total)| statistical_equilibrium(r, current, total))
+            .collect();
+
+        let change = updated
+            .iter()
+            .flatten()
+            .zip(populations.iter().flatten())
+            .map(|(new, old)| ((new - old) / new).abs())
+            .fold(0.0, f64::max);
+        converged = change < settings.tolerance && updated.iter().flatten().all(|n| n.is_finite());
+        populations = updated;
+    }
+
+    NonLteSolution {
+        atom: atom.clone(),
+        atmosphere: atmosphere.clone(),
+        populations,
+        lte_populations,
+        iterations,
+        converged,
+        transitions,
+    }
+}
+
+// Saha-Boltzmann ratio (n_i / n_continuum)* at one depth
+fn saha_boltzmann_ratio(atom: &ModelAtom, level: usize, layer: &ChromosphereLayer) -> f64 {
+    let continuum = atom.continuum_level();
+    if level == continuum {
+        return 1.0;
+    }
+    let temperature = layer.temperature;
+    let thermal = PLANCK_CONSTANT * PLANCK_CONSTANT / (2.0 * PI * ELECTRON_MASS * BOLTZMANN_CONSTANT * temperature);
+    0.5 * layer.electron_density * atom.levels[level].statistical_weight / atom.levels[continuum].statistical_weight
+        * thermal.powf(1.5)
+        * (atom.ionization_energy(level) / (BOLTZMANN_CONSTANT * temperature)).exp()
+}
+
+// LTE populations summing to `total`
+fn lte_populations(atom: &ModelAtom, layer: &ChromosphereLayer, total: f64) -> Vec<f64> {
+    let ratios: Vec<f64> = (0..atom.levels.len())
+        .map(|i| saha_boltzmann_ratio(atom, i, layer))
+        .collect();
+    let sum: f64 = ratios.iter().sum();
+    ratios.iter().map(|r| total * r / sum).collect()
+}
+
+// Collisional rates[from][to] (s^-1) at one depth, upward rates from detailed balance
+fn collision_rates(atom: &ModelAtom, layer: &ChromosphereLayer) -> Vec<Vec<f64>> {
+    let num_levels = atom.levels.len();
+    let temperature = layer.temperature;
+    let electrons = layer.electron_density;
+    let mut rates = vec![vec![0.0; num_levels]; num_levels];
+    let omega_rate = |upper: usize, omega: f64| {
+        COLLISION_RATE * electrons * omega / (atom.levels[upper].statistical_weight * temperature.sqrt())
+    };
+
+    let mut add = |upper: usize, lower: usize, downward: f64| {
+        let boltzmann = atom.levels[upper].statistical_weight / atom.levels[lower].statistical_weight
+            * (-(atom.levels[upper].energy - atom.levels[lower].energy) / (BOLTZMANN_CONSTANT * temperature)).exp();
+        rates[upper][lower] += downward;
+        rates[lower][upper] += downward * boltzmann;
+    };
+
+    for line in &atom.lines {
+        let downward = if line.collision_strength > 0.0 {
+            omega_rate(line.upper, line.collision_strength)
+        } else {
+            // Van Regemorter (1962), with lambda in cm and n_e in cm^-3 folded into SI
+            let wavelength = atom.line_wavelength(line);
+            let y =
+                (atom.levels[line.upper].energy - atom.levels[line.lower].energy) / (BOLTZMANN_CONSTANT * temperature);
+            let floor = if atom.levels[line.lower].stage > 1 { 0.2 } else { 0.0 };
+            let gaunt = (0.276 * numerics::scaled_exponential_integral(y)).max(floor);
+            20.6 * wavelength.powi(3) * electrons / temperature.sqrt() * atom.einstein_a(line) * gaunt
+        };
+        add(line.upper, line.lower, downward);
+    }
+    for coupling in &atom.collisions {
+        add(
+            coupling.upper,
+            coupling.lower,
+            omega_rate(coupling.upper, coupling.collision_strength),
+        );
+    }
+
+    // Seaton's (1962) ionization rate; recombination from the Saha-Boltzmann ratio
+    let continuum = atom.continuum_level();
+    for edge in &atom.continua {
+        let y = atom.ionization_energy(edge.lower) / (BOLTZMANN_CONSTANT * temperature);
+        let gaunt = match atom.levels[edge.lower].stage {
+            1 => 0.1,
+            2 => 0.2,
+            _ => 0.3,
+        };
+        let ionization = 1.55e11 * electrons * gaunt * edge.cross_section / temperature.sqrt() * (-y).exp() / y;
+        rates[edge.lower][continuum] += ionization;
+        rates[continuum][edge.lower] += ionization * saha_boltzmann_ratio(atom, edge.lower, layer);
+    }
+    rates
+}
+
+// Frequency grids, cross sections and LTE background for every transition
+fn build_transitions(
+    atom: &ModelAtom,
+    atmosphere: &StratifiedAtmosphere,
+    settings: &NonLteSettings,
+) -> Vec<Transition> {
+    let layers = &atmosphere.layers;
+    let background = |frequency: f64| -> (Vec<f64>, Vec<f64>) {
+        let wavelength = SPEED_OF_LIGHT / frequency;
+        layers
+            .iter()
+            .map(|layer| {
+                let state = layer.continuum_state();
+                let absorption = continuum::background_absorption(&state, wavelength);
+                let emissivity = absorption * planck::spectral_radiance_frequency(frequency, layer.temperature);
+                (absorption + continuum::scattering(&state), emissivity)
+            })
+            .unzip()
+    };
+    let depth_count = layers.len();
+    let mut transitions = Vec::new();
+
+    for (index, line) in atom.lines.iter().enumerate() {
+        let centre = SPEED_OF_LIGHT / atom.line_wavelength(line);
+        let points = line.points;
+        let offsets: Vec<f64> = (0..points)
+            .map(|j| {
+                line.extent * (LINE_GRID_STRETCH * j as f64 / (points - 1) as f64).sinh() / LINE_GRID_STRETCH.sinh()
+            })
+            .collect();
+        let frequencies: Vec<f64> = offsets.iter().map(|v| centre * (1.0 + v / SPEED_OF_LIGHT)).collect();
+        // Trapezoid weights of the full symmetric grid folded onto the half
+        // grid: twice the half-grid weight, once for the centre point
+        let weights: Vec<f64> = (0..points)
+            .map(|j| {
+                let left = if j > 0 {
+                    frequencies[j] - frequencies[j - 1]
+                } else {
+                    0.0
+                };
+                let right = if j + 1 < points {
+                    frequencies[j + 1] - frequencies[j]
+                } else {
+                    0.0
+                };
+                left + right
+            })
+            .collect();
+
+        let mut profiles = vec![vec![0.0; depth_count]; points];
+        let mut doppler_offsets = vec![vec![0.0; depth_count]; points];
+        let mut coherence = vec![0.0; depth_count];
+        for (k, layer) in layers.iter().enumerate() {
+            let (width, damping, coherent) = line_broadening(atom, line, layer);
+            for j in 0..points {
+                let x = (frequencies[j] - centre) / width;
+                doppler_offsets[j][k] = x;
+                profiles[j][k] = line_profile::voigt(damping, x) / (PI.sqrt() * width);
+            }
+            // Renormalize so the quadrature integrates the profile to one
+            let norm: f64 = (0..points).map(|j| weights[j] * profiles[j][k]).sum();
+            for profile in profiles.iter_mut() {
+                profile[k] /= norm;
+            }
+            coherence[k] = coherent;
+        }
+
+        let ratio = atom.levels[line.lower].statistical_weight / atom.levels[line.upper].statistical_weight;
+        let (background_opacity, background_emissivity) = frequencies.iter().map(|&f| background(f)).unzip();
+        transitions.push(Transition {
+            lower: line.lower,
+            upper: line.upper,
+            line: Some(index),
+            offsets,
+            cross_sections: profiles
+                .iter()
+                .map(|p| {
+                    p.iter()
+                        .map(|phi| LINE_CROSS_SECTION * line.oscillator_strength * phi)
+                        .collect()
+                })
+                .collect(),
+            upper_ratio: vec![vec![ratio; depth_count]; points],
+            doppler_offsets,
+            coherence,
+            redistribution: vec![vec![1.0; depth_count]; points],
+            mean_intensity: vec![vec![0.0; depth_count]; points],
+            background_opacity,
+            background_emissivity,
+            partial: settings.partial_redistribution && line.redistribution == Redistribution::Partial,
+            frequencies,
+            weights,
+        });
+    }
+
+    let continuum = atom.continuum_level();
+    for edge in &atom.continua {
+        let threshold = SPEED_OF_LIGHT / atom.edge_wavelength(edge);
+        let points = edge.points.max(2);
+        // From the edge to twice its frequency
+        let frequencies: Vec<f64> = (0..points)
+            .map(|j| threshold * (1.0 + j as f64 / (points - 1) as f64))
+            .collect();
+        let step = threshold / (points - 1) as f64;
+        let weights: Vec<f64> = (0..points)
+            .map(|j| if j == 0 || j + 1 == points { 0.5 * step } else { step })
+            .collect();
+        let cross_sections: Vec<Vec<f64>> = frequencies
+            .iter()
+            .map(|f| vec![edge.cross_section * (threshold / f).powi(3); depth_count])
+            .collect();
+        // (n_l / n_c)* exp(-h nu / kT)
+        let upper_ratio = frequencies
+            .iter()
+            .map(|&f| {
+                layers
+                    .iter()
+                    .map(|layer| {
+                        saha_boltzmann_ratio(atom, edge.lower, layer)
+                            * (-PLANCK_CONSTANT * f / (BOLTZMANN_CONSTANT * layer.temperature)).exp()
+                    })
+                    .collect()
+            })
+            .collect();
+        let (background_opacity, background_emissivity) = frequencies.iter().map(|&f| background(f)).unzip();
+        transitions.push(Transition {
+            lower: edge.lower,
+            upper: continuum,
+            line: None,
+            offsets: vec![0.0; points],
+            cross_sections,
+            upper_ratio,
+            doppler_offsets: vec![vec![0.0; depth_count]; points],
+            coherence: vec![0.0; depth_count],
+            redistribution: vec![vec![1.0; depth_count]; points],
+            mean_intensity: vec![vec![0.0; depth_count]; points],
+            background_opacity,
+            background_emissivity,
+            partial: false,
+            frequencies,
+            weights,
+        });
+    }
+    transitions
+}
+
+// Doppler width (Hz), Voigt damping parameter and coherent fraction of
+// scattering of a line at one depth; radiative damping is coherent in the
+// atom's frame, Stark and van der Waals collisions are not
+fn line_broadening(atom: &ModelAtom, line: &AtomicLine, layer: &ChromosphereLayer) -> (f64, f64, f64) {
+    let centre = SPEED_OF_LIGHT / atom.line_wavelength(line);
+    let width = line_profile::doppler_width(
+        centre,
+        layer.temperature,
+        atom.element.mass_number,
+        layer.microturbulence,
+    );
+    let elastic = 10f64.powf(line.log_gamma_stark) * layer.electron_density * 1.0e-6
+        + 10f64.powf(line.log_gamma_van_der_waals)
+            * layer.neutral_hydrogen_density()
+            * 1.0e-6
+            * (layer.temperature / 1.0e4).powf(0.3);
+    let damping = (line.radiative_damping + elastic) / (4.0 * PI * width);
+    (
+        width,
+        damping,
+        line.radiative_damping / (line.radiative_damping + elastic),
+    )
+}
+
+// Core-wing PRD: rho = 1 + gamma a(x) B_lu (J_nu - J_bar) n_l / (n_u P_u),
+// normalized so the emission profile still integrates to one. The
+// populations and total rates out of the upper level P_u must be those that
+// produced J.
+fn update_redistribution(
+    atom: &ModelAtom,
+    transition: &mut Transition,
+    populations: &[Vec<f64>],
+    out_rates: &[Vec<f64>],
+) {
+    let line = &atom.lines[transition.line.expect("PRD applies to lines")];
+    let centre = SPEED_OF_LIGHT / atom.line_wavelength(line);
+    let einstein_b = 4.0 * PI / (PLANCK_CONSTANT * centre) * LINE_CROSS_SECTION * line.oscillator_strength;
+    let points = transition.frequencies.len();
+    for (k, n) in populations.iter().enumerate() {
+        let profile = |j: usize| transition.cross_sections[j][k] / (LINE_CROSS_SECTION * line.oscillator_strength);
+        let averaged: f64 = (0..points)
+            .map(|j| transition.weights[j] * profile(j) * transition.mean_intensity[j][k])
+            .sum();
+        let scale = transition.coherence[k] * einstein_b * n[transition.lower]
+            / (n[transition.upper] * out_rates[k][transition.upper]);
+        let rho: Vec<f64> = (0..points)
+            .map(|j| {
+                let wing = 1.0 - (-(transition.doppler_offsets[j][k] / CORE_WING_TRANSITION).powi(2)).exp();
+                (1.0 + scale * wing * (transition.mean_intensity[j][k] - averaged)).max(0.0)
+            })
+            .collect();
+        let norm: f64 = (0..points).map(|j| transition.weights[j] * profile(j) * rho[j]).sum();
+        for (j, value) in rho.iter().enumerate() {
+            let current = &mut transition.redistribution[j][k];
+            *current += REDISTRIBUTION_RELAXATION * (value / norm - *current);
+        }
+    }
+}
+
+// Solves the rate equations at one depth, replacing the equation of the most
+// populated level by particle conservation
+fn statistical_equilibrium(rates: &[Vec<f64>], current: &[f64], total: f64) -> Vec<f64> {
+    let num_levels = current.len();
+    let mut matrix = vec![vec![0.0; num_levels]; num_levels];
+    for (i, row) in matrix.iter_mut().enumerate() {
+        for j in 0..num_levels {
+            if i != j {
+                row[j] += rates[j][i];
+                row[i] -= rates[i][j];
+            }
+        }
+    }
+    let dominant = (0..num_levels)
+        .max_by(|&a, &b| current[a].total_cmp(&current[b]))
+        .unwrap_or(0);
+    matrix[dominant] = vec![1.0; num_levels];
+    let mut rhs = vec![0.0; num_levels];
+    rhs[dominant] = total;
+    match numerics::solve_linear_system(matrix, rhs) {
+        Some(solution) => solution.into_iter().map(|n| n.max(POPULATION_FLOOR)).collect(),
+        None => current.to_vec(),
+    }
+}
@@ -0,0 +1,310 @@
+// Height-resolved chromosphere from semi-empirical model tables.
+//
+// The tables in data/chromosphere give T(h), n_e(h), n_H(h) and the
+// microturbulent velocity from below the photospheric tau(500 nm) = 1 level,
+// through the temperature minimum and the chromospheric rise, up to the base
+// of the transition region, in the manner of the VAL (Vernazza, Avrett &
+// Loeser 1981) and FAL (Fontenla, Avrett & Loeser 1993) models. The gas
+// emits thermally at the tabulated temperature and ionization, through the
+// continuum opacity of a hydrogen-dominated gas. Thomson scattering adds
+// extinction but no thermal emission; the scattered mean intensity is left
+// out, which is fair where scattering is a small part of the extinction or
+// the radiation field lies far below the local Planck function. Shortward of
+// the Balmer edge the LTE hydrogen continua of the hottest layers make the
+// emergent ultraviolet far too bright; there the chromosphere takes its
+// spectrum from the non-LTE hydrogen populations instead (non_lte.rs).
+
+use radiation::atomic_data::{self, ELEMENTS, LOW_FIP_LIMIT};
+use radiation::continuum::{self, ContinuumState};
+use radiation::planck::{self, BOLTZMANN_CONSTANT};
+use radiation::transfer::{self, RadiatingLayer};
+
+use std::error::Error;
+use std::fs;
+
+// Models shipped with the repository
+const QUIET_SUN_MODEL: &str = include_str!("../../data/chromosphere/quiet.dat");
+const NETWORK_MODEL: &str = include_str!("../../data/chromosphere/network.dat");
+const PLAGE_MODEL: &str = include_str!("../../data/chromosphere/plage.dat");
+
+// Helium nuclei per hydrogen nucleus
+const HELIUM_ABUNDANCE: f64 = 0.085;
+
+// Bundled model variants
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum AtmosphereVariant {
+    QuietSun, // average cell interior, VAL C / FAL C
+    Network,  // bright network, FAL F
+    Plage,    // active-region plage, FAL P
+}
+
+impl AtmosphereVariant {
+    pub const ALL: [AtmosphereVariant; 3] = [
+        AtmosphereVariant::QuietSun,
+        AtmosphereVariant::Network,
+        AtmosphereVariant::Plage,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            AtmosphereVariant::QuietSun => "quiet Sun",
+            AtmosphereVariant::Network => "network",
+            AtmosphereVariant::Plage => "plage",
+        }
+    }
+
+    fn table(self) -> &'static str {
+        match self {
+            AtmosphereVariant::QuietSun => QUIET_SUN_MODEL,
+            AtmosphereVariant::Network => NETWORK_MODEL,
+            AtmosphereVariant::Plage => PLAGE_MODEL,
+        }
+    }
+}
+
+// One depth point of the model
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ChromosphereLayer {
+    pub height: f64,           // meters above tau(500 nm) = 1
+    pub temperature: f64,      // K
+    pub electron_density: f64, // m^-3
+    pub hydrogen_density: f64, // m^-3, neutral and ionized
+    pub microturbulence: f64,  // m/s
+}
+
+impl ChromosphereLayer {
+    /// Mass density in kg/m^3.
+    pub fn density(&self) -> f64 {
+        self.hydrogen_density * atomic_data::mass_per_hydrogen()
+    }
+
+    /// Proton density (m^-3): the electrons not supplied by the low-FIP metals.
+    pub fn proton_density(&self) -> f64 {
+        (self.electron_density - metal_electrons_per_hydrogen() * self.hydrogen_density)
+            .clamp(0.0, self.hydrogen_density)
+    }
+
+    pub fn neutral_hydrogen_density(&self) -> f64 {
+        self.hydrogen_density - self.proton_density()
+    }
+
+    /// Gas pressure (Pa) of hydrogen, helium and electrons.
+    pub fn gas_pressure(&self) -> f64 {
+        (self.hydrogen_density * (1.0 + HELIUM_ABUNDANCE) + self.electron_density)
+            * BOLTZMANN_CONSTANT
+            * self.temperature
+    }
+
+    /// Turbulent pressure 1/2 rho v_t^2 (Pa).
+    pub fn turbulent_pressure(&self) -> f64 {
+        0.5 * self.density() * self.microturbulence * self.microturbulence
+    }
+
+    pub fn continuum_state(&self) -> ContinuumState {
+        ContinuumState {
+            temperature: self.temperature,
+            electron_density: self.electron_density,
+            neutral_hydrogen_density: self.neutral_hydrogen_density(),
+            proton_density: self.proton_density(),
+        }
+    }
+
+    /// Continuous opacity in m^2/kg.
+    pub fn opacity(&self, wavelength: f64) -> f64 {
+        continuum::extinction(&self.continuum_state(), wavelength) / self.density()
+    }
+
+    /// Thermal source function: the Planck function weighted by the
+    /// absorption share of the extinction.
+    pub fn source_function(&self, wavelength: f64) -> f64 {
+        let state = self.continuum_state();
+        let absorption = continuum::absorption(&state, wavelength);
+        let destruction = absorption / (absorption + continuum::scattering(&state));
+        destruction * planck::spectral_radiance_wavelength(wavelength, self.temperature)
+    }
+
+    // Linear interpolation in temperature and velocity, logarithmic in the densities
+    fn interpolate(&self, other: &ChromosphereLayer, fraction: f64) -> ChromosphereLayer {
+        let linear = |a: f64, b: f64| a + fraction * (b - a);
+        let logarithmic = |a: f64, b: f64| a * (b / a).powf(fraction);
+        ChromosphereLayer {
+            height: linear(self.height, other.height),
+            temperature: linear(self.temperature, other.temperature),
+            electron_density: logarithmic(self.electron_density, other.electron_density),
+            hydrogen_density: logarithmic(self.hydrogen_density, other.hydrogen_density),
+            microturbulence: linear(self.microturbulence, other.microturbulence),
+        }
+    }
+}
+
+// Electrons per hydrogen nucleus from the low-FIP elements, fully singly
+// ionized throughout the chromosphere
+fn metal_electrons_per_hydrogen() -> f64 {
+    ELEMENTS
+        .iter()
+        .filter(|e| e.symbol != "H" && e.ionization_energy[0] < LOW_FIP_LIMIT)
+        .map(|e| e.abundance())
+        .sum()
+}
+
+// Layers ordered from the top of the model downward
+#[derive(Clone, Debug)]
+pub(crate) struct StratifiedAtmosphere {
+    pub name: String,
+    pub layers: Vec<ChromosphereLayer>,
+}
+
+impl StratifiedAtmosphere {
+    /// One of the models bundled with the repository.
+    pub fn bundled(variant: AtmosphereVariant) -> Self {
+        parse_atmosphere(variant.name(), variant.table()).expect("bundled chromosphere model is well formed")
+    }
+
+    /// The coolest layer: the temperature minimum between photosphere and chromosphere.
+    pub fn temperature_minimum(&self) -> ChromosphereLayer {
+        *self
+            .layers
+            .iter()
+            .min_by(|a, b| a.temperature.total_cmp(&b.temperature))
+            .expect("atmosphere has layers")
+    }
+
+    /// Lowest height (m) above the temperature minimum where the temperature
+    /// reaches `temperature`, or `None` if the model stays cooler.
+    pub fn height_of_temperature(&self, temperature: f64) -> Option<f64> {
+        let minimum = self.temperature_minimum().height;
+        self.layers
+            .windows(2)
+            .rev()
+            .filter(|pair| pair[1].height >= minimum)
+            .find(|pair| pair[1].temperature < temperature && pair[0].temperature >= temperature)
+            .map(|pair| {
+                let (upper, lower) = (&pair[0], &pair[1]);
+                let fraction = (temperature - lower.temperature) / (upper.temperature - lower.temperature);
+                lower.height + fraction * (upper.height - lower.height)
+            })
+    }
+
+    /// The model at `height` (m), clamped to the tabulated range.
+    pub fn at_height(&self, height: f64) -> ChromosphereLayer {
+        let top = self.layers[0];
+        let bottom = self.layers[self.layers.len() - 1];
+        if height >= top.height {
+            return top;
+        }
+        if height <= bottom.height {
+            return bottom;
+        }
+        let pair = self
+            .layers
+            .windows(2)
+            .find(|pair| height <= pair[0].height && height >= pair[1].height)
+            .expect("height lies inside the model");
+        let fraction = (height - pair[1].height) / (pair[0].height - pair[1].height);
+        pair[1].interpolate(&pair[0], fraction)
+    }
+
+    /// The part of the model between two heights (m), with the end points interpolated.
+    pub fn between(&self, bottom: f64, top: f64) -> StratifiedAtmosphere {
+        let mut layers = vec![self.at_height(top)];
+        layers.extend(
+            self.layers
+                .iter()
+                .copied()
+                .filter(|l| l.height < top && l.height > bottom),
+        );
+        layers.push(self.at_height(bottom));
+        StratifiedAtmosphere {
+            name: self.name.clone(),
+            layers,
+        }
+    }
+
+    /// Column mass (kg/m^2) above the bottom of the model.
+    pub fn column_mass(&self) -> f64 {
+        self.layers
+            .windows(2)
+            .map(|pair| 0.5 * (pair[0].density() + pair[1].density()) * (pair[0].height - pair[1].height))
+            .sum()
+    }
+
+    /// Emergent specific intensity I(lambda, mu) in W m^-2 sr^-1 m^-1.
+    pub fn emergent_intensity(&self, wavelength: f64, mu: f64) -> f64 {
+        transfer::emergent_intensity(self, wavelength, mu)
+    }
+}
+
+// Thermal emission at the tabulated temperature
+impl RadiatingLayer for StratifiedAtmosphere {
+    fn heights(&self) -> Vec<f64> {
+        self.layers.iter().map(|l| l.height).collect()
+    }
+
+    fn densities(&self) -> Vec<f64> {
+        self.layers.iter().map(|l| l.density()).collect()
+    }
+
+    fn opacities(&self, wavelength: f64) -> Vec<f64> {
+        self.layers.iter().map(|l| l.opacity(wavelength)).collect()
+    }
+
+    fn source_function(&self, wavelength: f64) -> Vec<f64> {
+        self.layers.iter().map(|l| l.source_function(wavelength)).collect()
+    }
+}
+
+/// Parses a model table (format documented in data/chromosphere/quiet.dat).
+/// Rows may be given in either height order.
+pub(crate) fn parse_atmosphere(name: &str, text: &str) -> Result<StratifiedAtmosphere, Box<dyn Error>> {
+    let mut layers = Vec::new();
+    for (number, raw) in text.lines().enumerate() {
+        let content = raw.split('#').next().unwrap_or("");
+        let fields: Vec<&str> = content.split_whitespace().collect();
+        if fields.is_empty() {
+            continue;
+        }
+        if fields.len() < 5 {
+            return Err(format!(
+                "{} line {}: expected 5 columns, found {}",
+                name,
+                number + 1,
+                fields.len()
+            )
+            .into());
+        }
+        let value = |i: usize| -> Result<f64, Box<dyn Error>> {
+            fields[i]
+                .parse::<f64>()
+                .map_err(|e| format!("{} line {}, column {}: {}", name, number + 1, i + 1, e).into())
+        };
+        let layer = ChromosphereLayer {
+            height: value(0)? * 1.0e3,
+            temperature: value(1)?,
+            electron_density: value(2)? * 1.0e6,
+            hydrogen_density: value(3)? * 1.0e6,
+            microturbulence: value(4)? * 1.0e3,
+        };
+        if layer.temperature <= 0.0 || layer.electron_density <= 0.0 || layer.hydrogen_density <= 0.0 {
+            return Err(format!(
+                "{} line {}: temperature and densities must be positive",
+                name,
+                number + 1
+            )
+            .into());
+        }
+        layers.push(layer);
+    }
+    if layers.len() < 2 {
+        return Err(format!("{}: a model needs at least two layers", name).into());
+    }
+    layers.sort_by(|a, b| b.height.total_cmp(&a.height));
+    Ok(StratifiedAtmosphere {
+        name: name.to_string(),
+        layers,
+    })
+}
+
+/// Loads a model table from a local file.
+pub(crate) fn load_atmosphere(path: &str) -> Result<StratifiedAtmosphere, Box<dyn Error>> {
+    parse_atmosphere(path, &fs::read_to_string(path)?)
+}
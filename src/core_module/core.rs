@@ -1,8 +1,13 @@
+mod diffusion;
+
+use diffusion::{evolve_settling, Composition, CompositionProfile, SettlingResult, ShellStructure};
+use radiation::spectral_grid::{SpectralGrid, SpectralUnit};
+
 // Trait to define common interface for layer properties
 trait LayerProperties {
     fn calculate(&self, depth: f64, core_radius: f64) -> f64;
     fn calculate_optical_depth(&self, wavelength: f64) -> f64;
-    fn calculate_emission_spectrum(&self) -> Vec<(f64, f64)>;
+    fn calculate_emission_spectrum(&self, grid: &SpectralGrid) -> Vec<(f64, f64)>;
     fn calculate_density(&self) -> Vec<(f64, f64)>;
 }
 
@@ -23,16 +28,11 @@ impl LayerProperties for TemperatureGradient {
         0.5
     }
 
-    fn calculate_emission_spectrum(&self) -> Vec<(f64, f64)> {
+    fn calculate_emission_spectrum(&self, grid: &SpectralGrid) -> Vec<(f64, f64)> {
         // Placeholder implementation for core emission spectrum calculation
         // Adjust this implementation based on the actual physics of the core
         // For now, we'll return a simple emission spectrum as an example
-        let mut spectrum = Vec::new();
-        for wavelength in 400..800 {
-            let intensity = 0.5 * (wavelength as f64 / 800.0); // Example intensity calculation
-            spectrum.push((wavelength as f64, intensity));
-        }
-        spectrum
+        grid.map_wavelengths(|wavelength| 0.5 * (wavelength / 800.0e-9)) // Example intensity calculation
     }
 
     fn calculate_density(&self) -> Vec<(f64, f64)> {
@@ -63,7 +63,7 @@ impl LayerProperties for DensityGradient {
         0.5
     }
 
-    fn calculate_emission_spectrum(&self) -> Vec<(f64, f64)> {
+    fn calculate_emission_spectrum(&self, _grid: &SpectralGrid) -> Vec<(f64, f64)> {
         // Placeholder implementation for core emission spectrum calculation
         // Adjust this implementation based on the actual physics of the core
         // For now, we'll return an empty spectrum as an example
@@ -149,7 +149,7 @@ impl LayerProperties for CoreProperties {
         0.5
     }
 
-    fn calculate_emission_spectrum(&self) -> Vec<(f64, f64)> {
+    fn calculate_emission_spectrum(&self, _grid: &SpectralGrid) -> Vec<(f64, f64)> {
         // Placeholder implementation for core emission spectrum calculation
         // Adjust this implementation based on the actual physics of the core
         // For now, we'll return an empty spectrum as an example
@@ -173,6 +173,8 @@ struct Sun {
     core: Layer<TemperatureGradient>,
     radiative_zone: Layer<TemperatureGradient>,
     convective_zone: Layer<TemperatureGradient>,
+    density: DensityGradient,
+    composition: CompositionProfile,
 }
 
 impl Sun {
@@ -190,20 +192,52 @@ impl Sun {
 
     fn temperature_at_depth(&self, depth: f64) -> f64 {
         match self.get_layer(depth) {
-            Some("Core") => self.core.calculate_property(depth, self.core.radius_min),
-            Some("Radiative Zone") => self.radiative_zone.calculate_property(depth, self.radiative_zone.radius_min),
-            Some("Convective Zone") => self.convective_zone.calculate_property(depth, self.convective_zone.radius_min),
+            Some("Core") => self.core.calculate_property(depth, self.core.radius_max),
+            Some("Radiative Zone") => self.radiative_zone.calculate_property(depth, self.radiative_zone.radius_max),
+            Some("Convective Zone") => self.convective_zone.calculate_property(depth, self.convective_zone.radius_max),
             _ => panic!("Depth outside Sun's bounds"),
         }
     }
 
+    // Density at a given depth; at fixed pressure and temperature the density
+    // scales with the mean molecular weight, so settling changes it
     fn density_at_depth(&self, depth: f64) -> f64 {
-        match self.get_layer(depth) {
-            Some("Core") => self.core.calculate_property(depth, self.core.radius_min),
-            Some("Radiative Zone") => self.radiative_zone.calculate_property(depth, self.radiative_zone.radius_min),
-            Some("Convective Zone") => self.convective_zone.calculate_property(depth, self.convective_zone.radius_min),
-            _ => panic!("Depth outside Sun's bounds"),
+        if self.get_layer(depth).is_none() {
+            panic!("Depth outside Sun's bounds");
         }
+        let initial_density = self.density.calculate(depth, self.core.radius_max);
+        let initial = Composition::new(PROTOSOLAR_HELIUM, PROTOSOLAR_METALS);
+        initial_density * self.mean_molecular_weight_at_depth(depth) / initial.mean_molecular_weight()
+    }
+
+    // Composition (X, Y, Z) at a given depth
+    fn composition_at_depth(&self, depth: f64) -> Composition {
+        self.composition.at_radius(depth)
+    }
+
+    fn mean_molecular_weight_at_depth(&self, depth: f64) -> f64 {
+        self.composition_at_depth(depth).mean_molecular_weight()
+    }
+
+    // Temperature and density of every composition shell, taken from the
+    // protosolar structure so the settling run sees a fixed background
+    fn structure_samples(&self) -> Vec<ShellStructure> {
+        (0..self.composition.shells.len())
+            .map(|i| {
+                let radius = self.composition.shell_radius(i);
+                ShellStructure {
+                    temperature: self.temperature_at_depth(radius),
+                    density: self.density.calculate(radius, self.core.radius_max),
+                }
+            })
+            .collect()
+    }
+
+    // Let helium and heavy elements settle for `age` seconds; the convective
+    // zone above the radiative interior stays fully mixed
+    fn apply_element_settling(&mut self, age: f64) -> SettlingResult {
+        let structure = self.structure_samples();
+        evolve_settling(&mut self.composition, &structure, self.radiative_zone.radius_max, age)
     }
 
     fn energy_generation_rate_at_depth(&self, depth: f64) -> f64 {
@@ -216,6 +250,12 @@ impl Sun {
     }
 }
 
+// Protosolar mass fractions of helium and heavy elements
+const PROTOSOLAR_HELIUM: f64 = 0.28;
+const PROTOSOLAR_METALS: f64 = 0.02;
+const COMPOSITION_SHELLS: usize = 200;
+const SOLAR_AGE: f64 = 4.57e9 * 3.156e7; // seconds
+
 fn main() {
     let solar_radius = 6.959e8; // meters
     let core_radius = 0.2 * solar_radius; // meters
@@ -230,7 +270,7 @@ fn main() {
         core_density,
     };
 
-    let sun = Sun {
+    let mut sun = Sun {
         core: Layer {
             radius_min: 0.0,
             radius_max: core_radius,
@@ -250,8 +290,34 @@ fn main() {
                 core_temperature: 1.0e7,
             },
         },
+        density: core_density_gradient,
+        composition: CompositionProfile::uniform(
+            solar_radius,
+            COMPOSITION_SHELLS,
+            Composition::new(PROTOSOLAR_HELIUM, PROTOSOLAR_METALS),
+        ),
     };
 
+    // Gravitational settling over the solar age
+    let settling = sun.apply_element_settling(SOLAR_AGE);
+    println!(
+        "Surface helium: {:.4} -> {:.4} (change {:.2e})",
+        settling.initial_surface_helium,
+        settling.surface_helium,
+        settling.surface_helium_change()
+    );
+    let (steepest_radius, steepest_gradient) = settling
+        .mean_molecular_weight_gradient
+        .iter()
+        .cloned()
+        .fold((0.0, 0.0_f64), |best, (r, g)| if g.abs() > best.1.abs() { (r, g) } else { best });
+    println!(
+        "Steepest mean molecular weight gradient: {:.3e} per m at r = {:.3e} m",
+        steepest_gradient, steepest_radius
+    );
+    let max_helium_gradient = settling.helium_gradient.iter().map(|(_, g)| g.abs()).fold(0.0, f64::max);
+    println!("Maximum interior helium gradient: {:.3e} per m", max_helium_gradient);
+
     let depth = 1.0e7; // meters
     if let Some(layer_name) = sun.get_layer(depth) {
         println!("Depth: {} meters (Layer: {})", depth, layer_name);
@@ -261,10 +327,14 @@ fn main() {
                 let density = sun.core.properties.calculate_density();
                 let energy_generation_rate = sun.core.calculate_property(depth, core_radius);
                 let optical_depth = sun.core.properties.calculate_optical_depth(500.0e-9); // Example wavelength: 500nm
-                let emission_spectrum = sun.core.properties.calculate_emission_spectrum();
+                let emission_spectrum = sun.core.properties.calculate_emission_spectrum(
+                    &SpectralGrid::with_step(SpectralUnit::Wavelength, 400.0e-9, 799.0e-9, 1.0e-9),
+                );
                 println!("Temperature: {:.2} K", temperature);
                 println!("Density: {:?}", density);
                 println!("Energy Generation Rate: {:.2} W/m^3", energy_generation_rate);
+                println!("Density (settled composition): {:.2} kg/m^3", sun.density_at_depth(depth));
+                println!("Mean Molecular Weight: {:.4}", sun.mean_molecular_weight_at_depth(depth));
                 println!("Optical Depth: {:.2}", optical_depth);
                 println!("Emission Spectrum: {:?}", emission_spectrum);
             }
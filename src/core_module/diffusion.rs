@@ -0,0 +1,356 @@
+// Element diffusion and gravitational settling in the radiative interior.
+//
+// Each heavy species is treated as a trace ion drifting through a fully
+// ionized hydrogen background, which reduces the Burgers equations to a
+// single species-hydrogen pair. The radial drift velocity combines gravity,
+// the electric field that keeps the electrons bound to the ions, and the
+// concentration gradient:
+//
+//     w_i = -D_i [ (A_i - Z_i / 2) m_u g / (k T) + d ln X_i / dr ]
+//
+// and the mass fraction evolves through the continuity equation
+//
+//     dX_i/dt = -1 / (rho r^2) d/dr (r^2 rho X_i w_i)
+//
+// Thermal diffusion and radiative levitation are neglected.
+
+const GRAVITATIONAL_CONSTANT: f64 = 6.674e-11; // m^3 kg^-1 s^-2
+const BOLTZMANN_CONSTANT: f64 = 1.380649e-23; // J/K
+const ATOMIC_MASS_UNIT: f64 = 1.66053907e-27; // kg
+
+// Helium: mass number and (fully ionized) charge
+const HELIUM_MASS_NUMBER: f64 = 4.0;
+const HELIUM_CHARGE: f64 = 2.0;
+
+// Heavy elements are represented by a single fully ionized oxygen-like ion
+const METAL_MASS_NUMBER: f64 = 16.0;
+const METAL_CHARGE: f64 = 8.0;
+
+// Fraction of the stability limit used for each explicit time step
+const COURANT_FACTOR: f64 = 0.4;
+
+// Mass fractions of hydrogen (X), helium (Y) and heavy elements (Z)
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Composition {
+    pub hydrogen: f64,
+    pub helium: f64,
+    pub metals: f64,
+}
+
+impl Composition {
+    pub fn new(helium: f64, metals: f64) -> Self {
+        Composition {
+            hydrogen: 1.0 - helium - metals,
+            helium,
+            metals,
+        }
+    }
+
+    // Mean molecular weight of a fully ionized mixture
+    pub fn mean_molecular_weight(&self) -> f64 {
+        1.0 / (2.0 * self.hydrogen + 0.75 * self.helium + 0.5 * self.metals)
+    }
+}
+
+// Thermodynamic state of one shell, sampled from the stellar structure
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ShellStructure {
+    pub temperature: f64, // K
+    pub density: f64,     // kg/m^3
+}
+
+// Per-shell composition on a uniform radial grid from the centre outwards
+pub(crate) struct CompositionProfile {
+    pub outer_radius: f64,
+    pub shells: Vec<Composition>,
+}
+
+impl CompositionProfile {
+    pub fn uniform(outer_radius: f64, num_shells: usize, initial: Composition) -> Self {
+        CompositionProfile {
+            outer_radius,
+            shells: vec![initial; num_shells],
+        }
+    }
+
+    pub fn shell_width(&self) -> f64 {
+        self.outer_radius / self.shells.len() as f64
+    }
+
+    // Radius of the centre of shell `index`
+    pub fn shell_radius(&self, index: usize) -> f64 {
+        (index as f64 + 0.5) * self.shell_width()
+    }
+
+    // Composition of the shell containing `radius`
+    pub fn at_radius(&self, radius: f64) -> Composition {
+        let index = (radius / self.shell_width()).max(0.0) as usize;
+        self.shells[index.min(self.shells.len() - 1)]
+    }
+}
+
+// Outcome of an evolutionary settling run
+pub(crate) struct SettlingResult {
+    pub initial_surface_helium: f64,
+    pub surface_helium: f64,
+    // (radius, dY/dr) at each interior shell interface, in 1/m
+    pub helium_gradient: Vec<(f64, f64)>,
+    // (radius, d(mu)/dr) at each interior shell interface, in 1/m
+    pub mean_molecular_weight_gradient: Vec<(f64, f64)>,
+}
+
+impl SettlingResult {
+    pub fn surface_helium_change(&self) -> f64 {
+        self.surface_helium - self.initial_surface_helium
+    }
+}
+
+// Species diffusing relative to the hydrogen background
+#[derive(Clone, Copy)]
+enum Species {
+    Helium,
+    Metals,
+}
+
+impl Species {
+    fn mass_number(self) -> f64 {
+        match self {
+            Species::Helium => HELIUM_MASS_NUMBER,
+            Species::Metals => METAL_MASS_NUMBER,
+        }
+    }
+
+    fn charge(self) -> f64 {
+        match self {
+            Species::Helium => HELIUM_CHARGE,
+            Species::Metals => METAL_CHARGE,
+        }
+    }
+
+    fn fraction(self, composition: &Composition) -> f64 {
+        match self {
+            Species::Helium => composition.helium,
+            Species::Metals => composition.metals,
+        }
+    }
+
+    fn set_fraction(self, composition: &mut Composition, value: f64) {
+        match self {
+            Species::Helium => composition.helium = value,
+            Species::Metals => composition.metals = value,
+        }
+        composition.hydrogen = 1.0 - composition.helium - composition.metals;
+    }
+}
+
+/// Diffusion coefficient of helium in hydrogen (Michaud & Proffitt 1993).
+///
+/// # Arguments
+///
+/// * `temperature` - The temperature in Kelvin.
+/// * `density` - The density in kg/m^3.
+/// * `hydrogen` - The local hydrogen mass fraction.
+///
+/// # Returns
+///
+/// The diffusion coefficient in m^2/s.
+pub(crate) fn helium_diffusion_coefficient(temperature: f64, density: f64, hydrogen: f64) -> f64 {
+    let temperature = temperature.max(1.0);
+    let density_cgs = (density * 1.0e-3).max(1.0e-12); // g/cm^3
+    let coefficient_cgs = 3.3e-15 * temperature.powf(2.5)
+        / (4.0 * density_cgs * coulomb_logarithm(temperature, density, hydrogen) * (0.7 + 0.3 * hydrogen));
+    coefficient_cgs * 1.0e-4
+}
+
+// Coulomb logarithm for electron-ion collisions (NRL formulary, T > 10 eV)
+fn coulomb_logarithm(temperature: f64, density: f64, hydrogen: f64) -> f64 {
+    let electron_density_cgs = density * (1.0 + hydrogen) / (2.0 * ATOMIC_MASS_UNIT) * 1.0e-6;
+    (24.0 - (electron_density_cgs.max(1.0).sqrt() / temperature).ln()).max(1.0)
+}
+
+// Diffusion coefficient of a species; the collision cross-section scales with
+// the square of the ion charge, so heavier ions diffuse more slowly than helium
+fn diffusion_coefficient(species: Species, temperature: f64, density: f64, hydrogen: f64) -> f64 {
+    let charge_ratio = HELIUM_CHARGE / species.charge();
+    helium_diffusion_coefficient(temperature, density, hydrogen) * charge_ratio * charge_ratio
+}
+
+/// Evolves the composition profile under gravitational settling and diffusion.
+///
+/// # Arguments
+///
+/// * `profile` - The composition profile, updated in place.
+/// * `structure` - The temperature and density of each shell of `profile`.
+/// * `convective_base` - Radius above which the convective zone keeps the composition fully mixed.
+/// * `age` - The evolutionary time to integrate over, in seconds.
+///
+/// # Returns
+///
+/// The change in surface helium and the resulting interior composition gradients.
+pub(crate) fn evolve_settling(
+    profile: &mut CompositionProfile,
+    structure: &[ShellStructure],
+    convective_base: f64,
+    age: f64,
+) -> SettlingResult {
+    assert_eq!(profile.shells.len(), structure.len(), "structure must sample every shell");
+
+    let num_shells = profile.shells.len();
+    let dr = profile.shell_width();
+    let face_radius: Vec<f64> = (0..=num_shells).map(|i| i as f64 * dr).collect();
+    let shell_mass: Vec<f64> = (0..num_shells)
+        .map(|i| {
+            4.0 / 3.0 * std::f64::consts::PI
+                * (face_radius[i + 1].powi(3) - face_radius[i].powi(3))
+                * structure[i].density
+        })
+        .collect();
+
+    // Gravity at each interior interface from the enclosed mass
+    let mut enclosed_mass = 0.0;
+    let mut face_gravity = vec![0.0; num_shells + 1];
+    for i in 0..num_shells {
+        enclosed_mass += shell_mass[i];
+        face_gravity[i + 1] = GRAVITATIONAL_CONSTANT * enclosed_mass / face_radius[i + 1].powi(2);
+    }
+
+    mix_convective_zone(profile, &shell_mass, convective_base);
+    let initial_surface_helium = profile.shells[num_shells - 1].helium;
+
+    let mut time = 0.0;
+    while time < age {
+        let dt = stable_time_step(profile, structure, &face_gravity).min(age - time);
+        for species in [Species::Helium, Species::Metals] {
+            advance_species(species, profile, structure, &face_radius, &face_gravity, &shell_mass, dt);
+        }
+        mix_convective_zone(profile, &shell_mass, convective_base);
+        time += dt;
+    }
+
+    let mut helium_gradient = Vec::with_capacity(num_shells - 1);
+    let mut mean_molecular_weight_gradient = Vec::with_capacity(num_shells - 1);
+    for i in 0..num_shells - 1 {
+        let (inner, outer) = (&profile.shells[i], &profile.shells[i + 1]);
+        helium_gradient.push((face_radius[i + 1], (outer.helium - inner.helium) / dr));
+        mean_molecular_weight_gradient.push((
+            face_radius[i + 1],
+            (outer.mean_molecular_weight() - inner.mean_molecular_weight()) / dr,
+        ));
+    }
+
+    SettlingResult {
+        initial_surface_helium,
+        surface_helium: profile.shells[num_shells - 1].helium,
+        helium_gradient,
+        mean_molecular_weight_gradient,
+    }
+}
+
+// Face-averaged diffusion coefficient and settling velocity between shells i and i + 1
+fn face_transport(
+    species: Species,
+    profile: &CompositionProfile,
+    structure: &[ShellStructure],
+    face_gravity: &[f64],
+    i: usize,
+) -> (f64, f64, f64) {
+    let (inner, outer) = (&structure[i], &structure[i + 1]);
+    let temperature = 0.5 * (inner.temperature + outer.temperature);
+    let density = 0.5 * (inner.density + outer.density);
+    let hydrogen = 0.5 * (profile.shells[i].hydrogen + profile.shells[i + 1].hydrogen);
+
+    let coefficient = diffusion_coefficient(species, temperature, density, hydrogen);
+    let net_mass = species.mass_number() - 0.5 * species.charge();
+    let settling_velocity = -coefficient * net_mass * ATOMIC_MASS_UNIT * face_gravity[i + 1]
+        / (BOLTZMANN_CONSTANT * temperature.max(1.0));
+    (coefficient, settling_velocity, density)
+}
+
+// Largest explicit step allowed by the diffusion and advection limits
+fn stable_time_step(profile: &CompositionProfile, structure: &[ShellStructure], face_gravity: &[f64]) -> f64 {
+    let dr = profile.shell_width();
+    let mut dt = f64::INFINITY;
+    for species in [Species::Helium, Species::Metals] {
+        for i in 0..profile.shells.len() - 1 {
+            let (coefficient, velocity, _) = face_transport(species, profile, structure, face_gravity, i);
+            if coefficient > 0.0 {
+                dt = dt.min(dr * dr / (2.0 * coefficient));
+            }
+            if velocity != 0.0 {
+                dt = dt.min(dr / velocity.abs());
+            }
+        }
+    }
+    COURANT_FACTOR * dt
+}
+
+// One conservative finite-volume update of a species' mass fraction
+fn advance_species(
+    species: Species,
+    profile: &mut CompositionProfile,
+    structure: &[ShellStructure],
+    face_radius: &[f64],
+    face_gravity: &[f64],
+    shell_mass: &[f64],
+    dt: f64,
+) {
+    let num_shells = profile.shells.len();
+    let dr = profile.shell_width();
+
+    // Mass flux (kg/s) through each interface; zero at the centre and the surface
+    let mut face_flux = vec![0.0; num_shells + 1];
+    for i in 0..num_shells - 1 {
+        let (coefficient, velocity, density) = face_transport(species, profile, structure, face_gravity, i);
+        let inner = species.fraction(&profile.shells[i]);
+        let outer = species.fraction(&profile.shells[i + 1]);
+        // Upwind the settling term, centre the diffusion term
+        let upwind = if velocity < 0.0 { outer } else { inner };
+        let flux_density = density * (velocity * upwind - coefficient * (outer - inner) / dr);
+        face_flux[i + 1] = 4.0 * std::f64::consts::PI * face_radius[i + 1].powi(2) * flux_density;
+    }
+
+    // Scale down the fluxes out of any shell that would lose more of the
+    // species than it holds; a massless shell passes nothing on
+    let mut limiter = vec![1.0; num_shells];
+    for i in 0..num_shells {
+        let outgoing = dt * (face_flux[i + 1].max(0.0) + (-face_flux[i]).max(0.0));
+        let available = species.fraction(&profile.shells[i]) * shell_mass[i];
+        if shell_mass[i] <= 0.0 {
+            limiter[i] = 0.0;
+        } else if outgoing > available {
+            limiter[i] = available / outgoing;
+        }
+    }
+    for i in 0..num_shells - 1 {
+        let donor = if face_flux[i + 1] > 0.0 { i } else { i + 1 };
+        let massless = shell_mass[i] <= 0.0 || shell_mass[i + 1] <= 0.0;
+        face_flux[i + 1] *= if massless { 0.0 } else { limiter[donor] };
+    }
+
+    // Each face flux leaves one shell and enters its neighbour unchanged
+    for i in 0..num_shells {
+        if shell_mass[i] <= 0.0 {
+            continue;
+        }
+        let fraction = species.fraction(&profile.shells[i]);
+        let updated = fraction - dt * (face_flux[i + 1] - face_flux[i]) / shell_mass[i];
+        species.set_fraction(&mut profile.shells[i], updated);
+    }
+}
+
+// Homogenize the composition of every shell above the convective boundary
+fn mix_convective_zone(profile: &mut CompositionProfile, shell_mass: &[f64], convective_base: f64) {
+    let first = (0..profile.shells.len())
+        .find(|&i| profile.shell_radius(i) >= convective_base)
+        .unwrap_or(profile.shells.len());
+    let mass: f64 = shell_mass[first..].iter().sum();
+    if mass <= 0.0 {
+        return;
+    }
+
+    let helium = (first..profile.shells.len()).map(|i| profile.shells[i].helium * shell_mass[i]).sum::<f64>() / mass;
+    let metals = (first..profile.shells.len()).map(|i| profile.shells[i].metals * shell_mass[i]).sum::<f64>() / mass;
+    for shell in &mut profile.shells[first..] {
+        *shell = Composition::new(helium, metals);
+    }
+}
@@ -1,25 +1,197 @@
+pub(crate) mod coronal_lines;
+pub(crate) mod dem_inversion;
+pub(crate) mod euv_response;
+pub(crate) mod thermal_structure;
+pub(crate) mod xray_spectrum;
+
+use coronal_lines::CoronalLine;
+use dem_inversion::{DemProblem, EuvObservation};
+use euv_response::{Passband, TemperatureResponse};
+use radiation::atomic_data::{self, AbundanceSet};
+use radiation::ionization::{ElementIons, IonizationData};
+use radiation::non_equilibrium_ionization::PlasmaSample;
+use radiation::numerics::trapezoid;
+use radiation::planck::{self, BOLTZMANN_CONSTANT};
+use radiation::radiative_loss::{LossComponents, MetalCooling, RadiativeLossFunction};
+use radiation::spectral_grid::{SpectralGrid, SpectralUnit};
+use thermal_structure::{CoronalRegion, ThermalComponent, ThermalStructure};
+use xray_spectrum::XraySpectrum;
+
+// Helium nuclei per hydrogen nucleus
+const HELIUM_ABUNDANCE: f64 = 0.085;
+// Electrons per hydrogen nucleus with hydrogen and helium fully ionized
+const ELECTRONS_PER_HYDROGEN: f64 = 1.0 + 2.0 * HELIUM_ABUNDANCE;
+const GRAVITATIONAL_PARAMETER: f64 = 1.32712e20; // m^3 s^-2, G M of the Sun
+const SOLAR_RADIUS: f64 = 6.957e8; // m
+const PROTON_MASS: f64 = 1.67262192e-27; // kg
+
 // Define a struct for the Corona
-struct Corona {
-    radius: f64,
-    temperature: f64,
-    density: f64,
-    energy_generation: f64,
-    magnetic_field_strength: f64, // Magnetic field strength in Tesla
+pub(crate) struct Corona {
+    pub(crate) radius: f64,
+    pub(crate) temperature: f64,
+    pub(crate) density: f64,
+    pub(crate) energy_generation: f64,
+    pub(crate) magnetic_field_strength: f64, // Magnetic field strength in Tesla
+    // Multi-thermal plasma the outputs integrate over; None for an isothermal
+    // corona at `temperature` and `density`
+    pub(crate) thermal_structure: Option<ThermalStructure>,
 }
 
 impl Corona {
-    // Method to calculate optical depth
-    fn calculate_optical_depth(&self, chromosphere_radius: f64, wavelength: f64) -> f64 {
+    // Method to calculate optical depth, tau = kappa * rho * ds, with the
+    // volume-averaged opacity and density of the components
+    pub(crate) fn calculate_optical_depth(&self, chromosphere_radius: f64, wavelength: f64) -> f64 {
         let kappa = self.calculate_absorption_coefficient(wavelength);
+        let density: f64 = self.components().iter().map(|c| c.density() * c.filling_factor).sum();
+        let corona_thickness = self.radius - chromosphere_radius;
+        kappa * density * corona_thickness
+    }
+
+    // Source function of the corona, which is in LTE for free-free emission:
+    // the Planck functions of the components weighted by their absorption
+    pub(crate) fn source_function(&self, wavelength: f64) -> f64 {
+        let (weighted, total) = self.components().iter().fold((0.0, 0.0), |(weighted, total), c| {
+            let absorption = c.filling_factor
+                * c.density()
+                * AbsorptionCoefficientModule::calculate_absorption_coefficient(c.temperature, c.density(), wavelength);
+            (
+                weighted + absorption * planck::spectral_radiance_wavelength(wavelength, c.temperature),
+                total + absorption,
+            )
+        });
+        if total > 0.0 {
+            weighted / total
+        } else {
+            planck::spectral_radiance_wavelength(wavelength, self.temperature)
+        }
+    }
+
+    // Isothermal components of the corona: the thermal structure, or the
+    // uniform corona filling the shell
+    pub(crate) fn components(&self) -> Vec<ThermalComponent> {
+        match &self.thermal_structure {
+            Some(structure) => structure.components.clone(),
+            None => vec![ThermalComponent {
+                temperature: self.temperature,
+                electron_density: ELECTRONS_PER_HYDROGEN * atomic_data::hydrogen_density(self.density),
+                filling_factor: 1.0,
+            }],
+        }
+    }
+
+    // Hydrogen nuclei and free electrons per m^3 of the fully ionized corona,
+    // averaged over the volume
+    pub(crate) fn hydrogen_density(&self) -> f64 {
+        self.components()
+            .iter()
+            .map(|c| c.hydrogen_density() * c.filling_factor)
+            .sum()
+    }
+
+    pub(crate) fn electron_density(&self) -> f64 {
+        ELECTRONS_PER_HYDROGEN * self.hydrogen_density()
+    }
+
+    // Optically thin radiative losses n_e n_H Lambda(T) of the components,
+    // averaged over the volume, in W/m^3
+    pub(crate) fn radiative_loss(&self, loss: &RadiativeLossFunction) -> f64 {
+        self.components()
+            .iter()
+            .map(|c| c.filling_factor * loss.volumetric_loss(c.temperature, c.electron_density, c.hydrogen_density()))
+            .sum()
+    }
+
+    // Energy balance: heating less radiative losses, in W/m^3
+    pub(crate) fn net_heating(&self, loss: &RadiativeLossFunction) -> f64 {
+        self.energy_generation - self.radiative_loss(loss)
+    }
+
+    // Time (s) for the losses to radiate away the thermal energy without heating
+    pub(crate) fn radiative_cooling_time(&self, loss: &RadiativeLossFunction) -> f64 {
+        let thermal_energy: f64 = self
+            .components()
+            .iter()
+            .map(|c| 1.5 * c.pressure() * c.filling_factor)
+            .sum();
+        thermal_energy / self.radiative_loss(loss)
+    }
+
+    // (temperature in K, emission measure n_e n_H ds in m^-5) of each
+    // component along a radial column through the corona above the
+    // chromosphere
+    pub(crate) fn emission_measures(&self, chromosphere_radius: f64) -> Vec<(f64, f64)> {
         let corona_thickness = self.radius - chromosphere_radius;
-        kappa * corona_thickness
+        self.components()
+            .iter()
+            .map(|c| (c.temperature, c.emission_measure(corona_thickness)))
+            .collect()
+    }
+
+    // Emission measure n_e n_H ds (m^-5) of a radial column through the
+    // corona above the chromosphere
+    pub(crate) fn emission_measure(&self, chromosphere_radius: f64) -> f64 {
+        self.emission_measures(chromosphere_radius)
+            .iter()
+            .map(|&(_, em)| em)
+            .sum()
     }
 
-    // Method to get emission spectrum
-    fn get_emission_spectrum(&self) -> Vec<(f64, f64)> {
-        let temperature = self.temperature;
-        // Calculate emission spectrum using advanced model
-        EmissionSpectrumModule::calculate_emission_spectrum_advanced(temperature, &[])
+    // Volume emission measure n_e n_H V (m^-3) of each component in the
+    // coronal shell above the chromosphere
+    pub(crate) fn volume_emission_measures(&self, chromosphere_radius: f64) -> Vec<(f64, f64)> {
+        let volume = 4.0 / 3.0 * std::f64::consts::PI * (self.radius.powi(3) - chromosphere_radius.powi(3));
+        self.components()
+            .iter()
+            .map(|c| (c.temperature, c.emission_measure(1.0) * volume))
+            .collect()
+    }
+
+    pub(crate) fn volume_emission_measure(&self, chromosphere_radius: f64) -> f64 {
+        self.volume_emission_measures(chromosphere_radius)
+            .iter()
+            .map(|&(_, em)| em)
+            .sum()
+    }
+
+    // Synthetic EUV count rates (DN/s/pixel) of a radial column through the
+    // corona, one per channel response
+    pub(crate) fn euv_counts(&self, chromosphere_radius: f64, responses: &[TemperatureResponse]) -> Vec<f64> {
+        let emission_measures = self.emission_measures(chromosphere_radius);
+        responses
+            .iter()
+            .map(|response| emission_measures.iter().map(|&(t, em)| response.counts(t, em)).sum())
+            .collect()
+    }
+
+    // Thermal X-ray spectrum of the coronal shell at 1 AU: free-free and
+    // free-bound continuum plus the X-ray lines
+    fn get_xray_spectrum(&self, chromosphere_radius: f64, lines: &[CoronalLine], grid: &SpectralGrid) -> XraySpectrum {
+        XraySpectrum::multi_thermal(
+            &self.volume_emission_measures(chromosphere_radius),
+            &AbundanceSet::coronal(),
+            &IonizationData::bundled(),
+            lines,
+            grid,
+        )
+    }
+
+    // Method to get emission spectrum: the optically thin lines of a radial
+    // column, as spectral radiance in W/m^2/sr/m, summed over the components
+    fn get_emission_spectrum(&self, chromosphere_radius: f64, grid: &SpectralGrid) -> Vec<(f64, f64)> {
+        let mut spectrum: Vec<(f64, f64)> = Vec::new();
+        for (temperature, emission_measure) in self.emission_measures(chromosphere_radius) {
+            // Calculate emission spectrum using advanced model
+            let component =
+                EmissionSpectrumModule::calculate_emission_spectrum_advanced(temperature, emission_measure, &[], grid);
+            if spectrum.is_empty() {
+                spectrum = component;
+            } else {
+                for (total, (_, radiance)) in spectrum.iter_mut().zip(component) {
+                    total.1 += radiance;
+                }
+            }
+        }
+        spectrum
     }
 
     // Method to model solar wind based on coronal properties
@@ -31,17 +203,25 @@ impl Corona {
         )
     }
 
-    // Method to calculate absorption coefficient
+    // Method to calculate absorption coefficient: the mass-weighted mean of
+    // the components, or that of the uniform corona if they fill nothing
     fn calculate_absorption_coefficient(&self, wavelength: f64) -> f64 {
-        AbsorptionCoefficientModule::calculate_absorption_coefficient(
-            self.temperature,
-            self.density,
-            wavelength,
-        )
+        let (absorption, mass) = self.components().iter().fold((0.0, 0.0), |(absorption, mass), c| {
+            let kappa =
+                AbsorptionCoefficientModule::calculate_absorption_coefficient(c.temperature, c.density(), wavelength);
+            let filled_density = c.density() * c.filling_factor;
+            (absorption + kappa * filled_density, mass + filled_density)
+        });
+        if mass > 0.0 {
+            absorption / mass
+        } else {
+            AbsorptionCoefficientModule::calculate_absorption_coefficient(self.temperature, self.density, wavelength)
+        }
     }
 }
 
 // Define a module for the Absorption Coefficient Module
+#[allow(non_snake_case)]
 mod AbsorptionCoefficientModule {
     /// Calculates the absorption coefficient based on temperature, density, and wavelength.
     ///
@@ -59,13 +239,21 @@ mod AbsorptionCoefficientModule {
         const BOLTZMANN_CONSTANT: f64 = 1.380649e-23; // Boltzmann constant in m^2 kg s^-2 K^-1
         const PLANCK_CONSTANT: f64 = 6.62607015e-34; // Planck constant in m^2 kg s^-1
         const SPEED_OF_LIGHT: f64 = 299792458.0; // Speed of light in m/s
+        const ATOMIC_MASS_UNIT: f64 = 1.66053907e-27; // kg
+        const MASS_PER_ELECTRON: f64 = 1.17; // amu per free electron, fully ionized solar mix
+        // Free-free absorption coefficient prefactor with unit Gaunt factor
+        // (Rybicki & Lightman 5.18b), SI units with n in m^-3 and nu in Hz
+        const FREE_FREE_CONSTANT: f64 = 3.7e-2;
 
-        // Calculate the absorption coefficient using the Beer-Lambert law
-        // Adjust the model based on specific properties and models of the corona
-        let absorption_coefficient = (1.0 / (temperature * density))
-            * ((PLANCK_CONSTANT * SPEED_OF_LIGHT) / (4.0 * BOLTZMANN_CONSTANT * wavelength));
+        // Thermal bremsstrahlung absorption in a fully ionized plasma, with
+        // sum(Z^2 n_i) ~ n_e and the correction for stimulated emission
+        let electron_density = density / (MASS_PER_ELECTRON * ATOMIC_MASS_UNIT);
+        let frequency = SPEED_OF_LIGHT / wavelength;
+        let stimulated = -(-PLANCK_CONSTANT * frequency / (BOLTZMANN_CONSTANT * temperature)).exp_m1();
+        let absorption_coefficient = FREE_FREE_CONSTANT * electron_density * electron_density * stimulated
+            / (temperature.sqrt() * frequency.powi(3));
 
-        absorption_coefficient
+        absorption_coefficient / density
     }
 }
 
@@ -76,32 +264,32 @@ impl EmissionSpectrumModule {
     // Function to calculate the emission spectrum considering additional factors
     fn calculate_emission_spectrum_advanced(
         temperature: f64,
-        additional_factors: &[AdditionalFactor],
+        emission_measure: f64,
+        _additional_factors: &[AdditionalFactor],
+        grid: &SpectralGrid,
     ) -> Vec<(f64, f64)> {
-        // Placeholder implementation for advanced emission spectrum calculation
-        // Here you can incorporate mechanisms like magnetic reconnection, wave heating, or particle acceleration
-        // For now, let's return the emission spectrum using a blackbody radiation model
-        Self::calculate_emission_spectrum_blackbody(temperature)
-    }
-
-    // Function to calculate the emission spectrum based on temperature (blackbody radiation model)
-    fn calculate_emission_spectrum_blackbody(temperature: f64) -> Vec<(f64, f64)> {
-        const MIN_WAVELENGTH: f64 = 200.0e-9;
-        const MAX_WAVELENGTH: f64 = 700.0e-9;
-        const STEP_SIZE: f64 = 1.0e-9;
-
-        let mut spectrum = Vec::new();
-        let mut wavelength = MIN_WAVELENGTH;
-        while wavelength <= MAX_WAVELENGTH {
-            let intensity = blackbody_intensity(wavelength, temperature);
-            spectrum.push((wavelength, intensity));
-            wavelength += STEP_SIZE;
-        }
-        spectrum
+        // Mechanisms like magnetic reconnection, wave heating, or particle acceleration
+        // would add non-thermal emission; only the thermal lines are modelled so far
+        Self::calculate_line_spectrum(temperature, emission_measure, grid)
+    }
+
+    // Function to calculate the optically thin line spectrum of isothermal
+    // plasma in ionization equilibrium with coronal abundances
+    fn calculate_line_spectrum(temperature: f64, emission_measure: f64, grid: &SpectralGrid) -> Vec<(f64, f64)> {
+        let ions = IonizationData::bundled();
+        let abundances = AbundanceSet::coronal();
+        let lines = CoronalLine::bundled();
+        let intensities: Vec<(&CoronalLine, f64)> = lines
+            .iter()
+            .map(|line| (line, line.emissivity(temperature, &ions, &abundances) * emission_measure))
+            .collect();
+        coronal_lines::line_spectrum(&intensities, temperature, grid)
     }
 }
 
-// Define an enum to represent additional factors affecting emission spectrum
+// Define an enum to represent additional factors affecting emission spectrum;
+// none of them is modelled yet
+#[allow(dead_code)]
 enum AdditionalFactor {
     MagneticReconnection,
     WaveHeating,
@@ -115,7 +303,7 @@ impl SolarWindModule {
     // Function to model solar wind dynamics based on coronal properties and magnetic field strength
     fn model_solar_wind(
         coronal_temperature: f64,
-        magnetic_field_strength: f64,
+        _magnetic_field_strength: f64,
         distance_from_sun: f64,
     ) -> (f64, f64, f64) {
         // Placeholder implementation for solar wind modeling using empirical data
@@ -130,6 +318,296 @@ impl SolarWindModule {
     }
 }
 
+impl SolarWindModule {
+    // Plasma history of a parcel carried out along a radial streamline from
+    // the coronal base (r = 1 R_sun) to `outer_radius` (R_sun), sampled at
+    // `samples` logarithmically spaced radii. The speed is Parker's
+    // isothermal solution at the base temperature,
+    //   w - ln w = 4 ln x + 4 / x - 3,  w = v^2 / c^2,  x = r / r_c,
+    // with c^2 = 2 k T / m_p and r_c = G M / (2 c^2); the density follows
+    // from n v r^2 = const and the electrons cool as T ~ r^-TEMPERATURE_INDEX.
+    // Returns the radius (R_sun) and the plasma at each sample.
+    fn model_streamline(
+        base_temperature: f64,
+        base_electron_density: f64,
+        outer_radius: f64,
+        samples: usize,
+    ) -> Vec<(f64, PlasmaSample)> {
+        let sound_speed_squared = 2.0 * BOLTZMANN_CONSTANT * base_temperature / PROTON_MASS;
+        let critical_radius = GRAVITATIONAL_PARAMETER / (2.0 * sound_speed_squared);
+        let speed = |radius: f64| -> f64 {
+            let x = radius * SOLAR_RADIUS / critical_radius;
+            let target = 4.0 * x.ln() + 4.0 / x - 3.0;
+            // w - ln w falls below w = 1 and rises above it: bisect in ln w on
+            // the subsonic branch inside r_c and the supersonic one outside
+            let (mut low, mut high) = if x < 1.0 { (-700.0, 0.0) } else { (0.0, 700f64.ln()) };
+            for _ in 0..100 {
+                let mid = 0.5 * (low + high);
+                let too_fast = (mid.exp() - mid > target) != (x < 1.0);
+                if too_fast {
+                    high = mid;
+                } else {
+                    low = mid;
+                }
+            }
+            (sound_speed_squared * (0.5 * (low + high)).exp()).sqrt()
+        };
+        let base_speed = speed(1.0);
+        let mut streamline = Vec::with_capacity(samples);
+        let mut time = 0.0;
+        let mut previous: Option<(f64, f64)> = None;
+        for i in 0..samples {
+            let radius = outer_radius.powf(i as f64 / (samples - 1).max(1) as f64);
+            let velocity = speed(radius);
+            // Travel time by the trapezoid rule in 1/v
+            if let Some((last_radius, last_velocity)) = previous {
+                time += 0.5 * (1.0 / last_velocity + 1.0 / velocity) * (radius - last_radius) * SOLAR_RADIUS;
+            }
+            previous = Some((radius, velocity));
+            let sample = PlasmaSample {
+                time,
+                temperature: base_temperature * radius.powf(-TEMPERATURE_INDEX),
+                electron_density: base_electron_density * base_speed / (velocity * radius * radius),
+            };
+            streamline.push((radius, sample));
+        }
+        streamline
+    }
+}
+
+// Electron density (m^-3) at the base of the wind streamlines, and the
+// power law of the electron temperature along them
+const WIND_BASE_ELECTRON_DENSITY: f64 = 1.0e14;
+const TEMPERATURE_INDEX: f64 = 0.5;
+// Base temperatures (K) of the wind streamlines: coronal hole and streamer
+const WIND_BASE_TEMPERATURES: [f64; 2] = [1.0e6, 1.6e6];
+// Freeze-in ratios reported along the wind: (element, upper charge, lower charge)
+const FREEZE_IN_RATIOS: [(&str, usize, usize); 2] = [("O", 7, 6), ("C", 6, 5)];
+
+// Plasma history of impulsive flare heating: the temperature rises
+// exponentially from `base_temperature` to `peak_temperature` over
+// `rise_time` (s), then cools exponentially back with e-folding `decay_time`,
+// at constant electron density; `samples` equally spaced samples.
+fn impulsive_heating_history(
+    base_temperature: f64,
+    peak_temperature: f64,
+    electron_density: f64,
+    rise_time: f64,
+    decay_time: f64,
+    samples: usize,
+) -> Vec<PlasmaSample> {
+    let duration = rise_time + 3.0 * decay_time;
+    (0..samples)
+        .map(|i| {
+            let time = duration * i as f64 / (samples - 1).max(1) as f64;
+            let temperature = if time <= rise_time {
+                base_temperature * (peak_temperature / base_temperature).powf(time / rise_time)
+            } else {
+                base_temperature + (peak_temperature - base_temperature) * (-(time - rise_time) / decay_time).exp()
+            };
+            PlasmaSample {
+                time,
+                temperature,
+                electron_density,
+            }
+        })
+        .collect()
+}
+
+// Prints the iron charge states and Fe XXI-XXIV line emissivities through
+// impulsive heating, out of and in ionization equilibrium
+fn report_flare_ionization(
+    iron: &ElementIons,
+    base_temperature: f64,
+    lines: &[CoronalLine],
+    abundances: &AbundanceSet,
+) {
+    let history = impulsive_heating_history(base_temperature, 1.5e7, 1.0e16, 60.0, 300.0, 61);
+    let evolution = iron.evolve(&iron.equilibrium(base_temperature), &history);
+    let hot_lines: Vec<&CoronalLine> = lines
+        .iter()
+        .filter(|line| line.element.symbol == "Fe" && line.charge >= 20)
+        .collect();
+    for (sample, fractions) in evolution.samples.iter().zip(&evolution.fractions).step_by(6) {
+        let equilibrium = iron.equilibrium(sample.temperature);
+        let emissivities: Vec<String> = hot_lines
+            .iter()
+            .map(|line| {
+                let charge = line.charge as usize;
+                format!(
+                    "{} {:.2e} ({:.2e})",
+                    line.label(),
+                    line.emissivity_with_fraction(sample.temperature, fractions[charge], abundances),
+                    line.emissivity_with_fraction(sample.temperature, equilibrium[charge], abundances)
+                )
+            })
+            .collect();
+        println!(
+            "Flare t = {:5.0} s, T = {:.2e} K: iron mean charge {:.2} (equilibrium {:.2}), G(T) {}",
+            sample.time,
+            sample.temperature,
+            mean_charge(fractions),
+            mean_charge(&equilibrium),
+            emissivities.join(", ")
+        );
+    }
+}
+
+// Temperatures (K) at which the EUV channel responses are reported
+const RESPONSE_TEMPERATURES: [f64; 5] = [3.0e5, 1.0e6, 2.0e6, 5.0e6, 1.0e7];
+// Exposure (s) of synthetic EUV observations
+const EUV_EXPOSURE: f64 = 2.0;
+// Temperature bins of the DEM inversions, in log10 T
+const DEM_LOG_START: f64 = 5.5;
+const DEM_LOG_STEP: f64 = 0.1;
+const DEM_BINS: usize = 18;
+
+// Inverts an EUV observation for its DEM by both methods and prints the
+// solutions, in cm^-5 K^-1
+pub(crate) fn report_dem_inversion(observation: &EuvObservation, responses: &[TemperatureResponse], log_edges: &[f64]) {
+    let problem = match DemProblem::new(responses, observation, log_edges) {
+        Ok(problem) => problem,
+        Err(error) => {
+            println!("Could not invert {}: {}", observation.name, error);
+            return;
+        }
+    };
+    for solution in [problem.regularized(), Ok(problem.sparse_basis())] {
+        let solution = match solution {
+            Ok(solution) => solution,
+            Err(error) => {
+                println!("Could not invert {}: {}", observation.name, error);
+                continue;
+            }
+        };
+        let bins: Vec<String> = solution
+            .log_temperatures
+            .iter()
+            .zip(solution.dem.iter().zip(&solution.uncertainty))
+            .map(|(log_t, (dem, error))| format!("{:.2}: {:.2e} +- {:.1e}", log_t, dem * 1.0e-10, error * 1.0e-10))
+            .collect();
+        let ratios: Vec<String> = solution
+            .model_intensities
+            .iter()
+            .zip(&observation.intensities)
+            .map(|(model, observed)| format!("{:.2}", model / observed))
+            .collect();
+        println!(
+            "DEM of {} ({}): EM {:.3e} cm^-5, mean T {:.3e} K, chi^2 {:.2} over {} channels (model/observed {}); {}",
+            observation.name,
+            solution.method,
+            solution.emission_measure(log_edges) * 1.0e-10,
+            solution.mean_temperature(log_edges),
+            solution.chi_squared,
+            observation.channels.len(),
+            ratios.join(" "),
+            bins.join(", ")
+        );
+    }
+}
+
+// Temperature (K) and volume emission measure (m^-3) of example flare plasmas
+const FLARE_PLASMAS: [(f64, f64); 3] = [(8.0e6, 1.0e53), (1.5e7, 1.0e54), (2.0e7, 1.0e55)];
+// Photon energies (keV) at which X-ray spectra are reported
+const XRAY_ENERGIES: [f64; 5] = [1.0, 3.0, 6.0, 10.0, 30.0];
+
+// Prints the continuum at a few energies, the line flux and the GOES
+// fluxes of an X-ray spectrum
+fn report_xray_spectrum(name: &str, spectrum: &XraySpectrum) {
+    let samples: Vec<String> = XRAY_ENERGIES
+        .iter()
+        .filter_map(|&energy| {
+            let index = spectrum.energies.iter().position(|&e| e >= energy)?;
+            Some(format!(
+                "{:.0} keV {:.2e} (ff {:.2e}, fb {:.2e})",
+                spectrum.energies[index],
+                spectrum.free_free[index] + spectrum.free_bound[index],
+                spectrum.free_free[index],
+                spectrum.free_bound[index]
+            ))
+        })
+        .collect();
+    let (short, long) = spectrum.goes_fluxes();
+    println!(
+        "{} X-ray spectrum, W m^-2 keV^-1 at 1 AU: {}; lines {:.2e} W m^-2; GOES 0.5-4 A {:.2e} W m^-2, 1-8 A {:.2e} W m^-2",
+        name,
+        samples.join(", "),
+        spectrum.lines.iter().map(|&(_, flux)| flux).sum::<f64>(),
+        short,
+        long
+    );
+}
+
+// Wavelengths (m) at which the free-free optical depth of the regions is
+// reported: visible and decimetric radio
+const REGION_WAVELENGTHS: [f64; 2] = [500.0e-9, 0.1];
+
+// Prints the outputs of a multi-thermal corona: optical depths, EUV
+// radiance, EUV count rates, X-ray flux and energy balance
+fn report_coronal_region(
+    region: &Corona,
+    chromosphere_radius: f64,
+    lines: &[CoronalLine],
+    responses: &[TemperatureResponse],
+    loss: &RadiativeLossFunction,
+    euv_grid: &SpectralGrid,
+    xray_grid: &SpectralGrid,
+) {
+    let Some(structure) = &region.thermal_structure else {
+        return;
+    };
+    let optical_depths: Vec<String> = REGION_WAVELENGTHS
+        .iter()
+        .map(|&wavelength| {
+            let tau = region.calculate_optical_depth(chromosphere_radius, wavelength);
+            let intensity = region.source_function(wavelength) * -(-tau).exp_m1();
+            format!(
+                "{:.3e} at {:.1e} m (intensity {:.3e} W/m^2/sr/m)",
+                tau, wavelength, intensity
+            )
+        })
+        .collect();
+    let counts: Vec<String> = responses
+        .iter()
+        .zip(region.euv_counts(chromosphere_radius, responses))
+        .map(|(response, rate)| format!("{} {:.3e}", response.name, rate))
+        .collect();
+    let (_, long) = region
+        .get_xray_spectrum(chromosphere_radius, lines, xray_grid)
+        .goes_fluxes();
+    println!(
+        "Corona of {}: {} components, mean T {:.3e} K, filling factor {:.2}, mean n_e {:.3e} m^-3, EM {:.3e} cm^-5",
+        structure.name,
+        structure.components.len(),
+        structure.mean_temperature(),
+        structure.filling_factor(),
+        region.electron_density(),
+        region.emission_measure(chromosphere_radius) * 1.0e-10
+    );
+    println!(
+        "  optical depth {}; EUV {:.4e} W/m^2/sr over 9-34 nm; {} DN s^-1 pixel^-1",
+        optical_depths.join(", "),
+        trapezoid(&region.get_emission_spectrum(chromosphere_radius, euv_grid)),
+        counts.join(", ")
+    );
+    println!(
+        "  shell of it: EM {:.3e} m^-3, GOES 1-8 A {:.3e} W m^-2; radiative loss {:.3e} W/m^3 ({}), cooling time {:.3e} s",
+        region.volume_emission_measure(chromosphere_radius),
+        long,
+        region.radiative_loss(loss),
+        loss.name,
+        region.radiative_cooling_time(loss)
+    );
+}
+
+// Mean charge of fractions over the charges 0..=Z
+fn mean_charge(fractions: &[f64]) -> f64 {
+    fractions.iter().enumerate().map(|(charge, f)| charge as f64 * f).sum()
+}
+
+// Temperatures (K) at which the radiative loss function is reported
+const LOSS_TEMPERATURES: [f64; 7] = [2.0e4, 1.0e5, 2.5e5, 1.0e6, 3.0e6, 1.0e7, 3.0e7];
+
 // Function to calculate solar wind velocity based on distance from the Sun (empirical model)
 fn calculate_solar_wind_velocity(distance_from_sun: f64) -> f64 {
     // Empirical model based on spacecraft observations
@@ -151,17 +629,6 @@ fn calculate_temperature_scaling_factor(distance_from_sun: f64) -> f64 {
     1.0 / distance_from_sun.powf(0.5) // Example formula
 }
 
-// Dummy function for blackbody intensity
-fn blackbody_intensity(wavelength: f64, temperature: f64) -> f64 {
-    const PLANCK_CONSTANT: f64 = 6.62607015e-34; // Planck constant in J*s
-    const SPEED_OF_LIGHT: f64 = 299792458.0; // Speed of light in m/s
-    const BOLTZMANN_CONSTANT: f64 = 1.380649e-23; // Boltzmann constant in J/K
-
-    let numerator = 2.0 * PLANCK_CONSTANT * SPEED_OF_LIGHT.powi(2);
-    let denominator = wavelength.powi(5) * (std::f64::consts::E.powf((PLANCK_CONSTANT * SPEED_OF_LIGHT) / (wavelength * BOLTZMANN_CONSTANT * temperature)) - 1.0);
-    numerator / denominator
-}
-
 fn main() {
     // Create an instance of the Corona struct
     let corona = Corona {
@@ -170,6 +637,7 @@ fn main() {
         density: 1.0e-6,
         energy_generation: 1.0e24,
         magnetic_field_strength: 1.0e-3,
+        thermal_structure: None,
     };
 
     // Example usage of methods for Corona
@@ -177,9 +645,226 @@ fn main() {
     let wavelength = 500.0e-9; // Example wavelength
     let corona_optical_depth = corona.calculate_optical_depth(chromosphere_radius, wavelength);
     println!("Corona Optical Depth: {}", corona_optical_depth);
+    println!(
+        "Corona Radial Intensity: {:.4e} W/m^2/sr/m",
+        corona.source_function(wavelength) * -(-corona_optical_depth).exp_m1()
+    );
 
-    let corona_emission_spectrum = corona.get_emission_spectrum();
-    println!("Corona Emission Spectrum: {:?}", corona_emission_spectrum);
+    // Radiative loss functions of the optically thin corona and the bundled
+    // lines, plus any line tables or loss functions given on the command line
+    let mut losses = vec![
+        RadiativeLossFunction::computed(&AbundanceSet::coronal(), &MetalCooling::bundled()),
+        RadiativeLossFunction::computed(&AbundanceSet::photospheric(), &MetalCooling::bundled()),
+        RadiativeLossFunction::rosner_1978(),
+    ];
+    let mut lines = CoronalLine::bundled();
+    let mut passbands = Passband::bundled();
+    let mut observations = vec![EuvObservation::bundled()];
+    let mut structures: Vec<ThermalStructure> = CoronalRegion::ALL
+        .iter()
+        .map(|&region| ThermalStructure::bundled(region))
+        .collect();
+    for path in std::env::args().skip(1) {
+        if let Ok(passband) = euv_response::load_passband(&path) {
+            passbands.push(passband);
+            continue;
+        }
+        if let Ok(extra) = coronal_lines::load_coronal_lines(&path) {
+            lines.extend(extra);
+            continue;
+        }
+        if let Ok(loss) = radiation::radiative_loss::load_radiative_loss(&path) {
+            losses.push(loss);
+            continue;
+        }
+        if let Ok(structure) = thermal_structure::load_thermal_structure(&path) {
+            structures.push(structure);
+            continue;
+        }
+        match dem_inversion::load_observation(&path) {
+            Ok(observation) => observations.push(observation),
+            Err(error) => println!(
+                "Could not load passband, line table, loss function, DEM or observation {}: {}",
+                path, error
+            ),
+        }
+    }
+
+    // EUV band of the coronal imagers, 9 to 34 nm
+    let euv_grid = SpectralGrid::with_step(SpectralUnit::Wavelength, 9.0e-9, 34.0e-9, 0.01e-9);
+    let corona_emission_spectrum = corona.get_emission_spectrum(chromosphere_radius, &euv_grid);
+    let (peak_wavelength, peak_radiance) = corona_emission_spectrum
+        .iter()
+        .fold((0.0, 0.0), |best, &point| if point.1 > best.1 { point } else { best });
+    println!(
+        "Corona EUV Spectrum: {:.4e} W/m^2/sr over 9-34 nm, peak {:.4e} W/m^2/sr/m at {:.2} nm",
+        trapezoid(&corona_emission_spectrum),
+        peak_radiance,
+        peak_wavelength * 1.0e9
+    );
+
+    // Thermal X-ray spectrum, 1 to 100 keV; the shell is far denser than the
+    // real corona, so only the flares, isothermal plasmas of given
+    // temperature and emission measure, are given a GOES class
+    let xray_grid = SpectralGrid::logarithmic(SpectralUnit::Energy, 1.0e3, 1.0e5, 400);
+    let xray_spectrum = corona.get_xray_spectrum(chromosphere_radius, &lines, &xray_grid);
+    report_xray_spectrum("Corona", &xray_spectrum);
+    for (temperature, emission_measure) in FLARE_PLASMAS {
+        let flare = XraySpectrum::thermal(
+            temperature,
+            emission_measure,
+            &AbundanceSet::coronal(),
+            &IonizationData::bundled(),
+            &lines,
+            &xray_grid,
+        );
+        let (_, long) = flare.goes_fluxes();
+        report_xray_spectrum(
+            &format!(
+                "Flare at {:.1e} K, EM {:.1e} m^-3 (class {})",
+                temperature,
+                emission_measure,
+                xray_spectrum::flare_class(long)
+            ),
+            &flare,
+        );
+    }
+
+    // Charge states of iron and the lines they emit
+    let ions = IonizationData::bundled();
+    if let Some(iron) = atomic_data::element("Fe").and_then(|fe| ions.element(fe)) {
+        let fractions: Vec<String> = iron
+            .equilibrium(corona.temperature)
+            .iter()
+            .enumerate()
+            .filter(|&(_, &fraction)| fraction > 0.01)
+            .map(|(charge, fraction)| format!("{} {:.3}", iron.ions[charge].name(), fraction))
+            .collect();
+        println!(
+            "Iron at {:.2e} K: mean charge {:.2}, {}",
+            corona.temperature,
+            iron.mean_charge(corona.temperature),
+            fractions.join(", ")
+        );
+    }
+    let abundances = AbundanceSet::coronal();
+    let emission_measure = corona.emission_measure(chromosphere_radius);
+    for line in &lines {
+        let emissivity = line.emissivity(corona.temperature, &ions, &abundances);
+        println!(
+            "  {}: formed at {:.2e} K, G(T) {:.3e} W m^3 sr^-1, intensity {:.3e} W m^-2 sr^-1",
+            line.label(),
+            ions.peak_temperature(line.element, line.charge).unwrap_or(0.0),
+            emissivity,
+            emissivity * emission_measure
+        );
+    }
+    // Temperature responses of the EUV channels and the count rates they
+    // give for the corona
+    let responses = euv_response::responses(&passbands, &lines);
+    let counts = corona.euv_counts(chromosphere_radius, &responses);
+    for (response, rate) in responses.iter().zip(&counts) {
+        // m^5 to cm^5
+        let samples: Vec<String> = RESPONSE_TEMPERATURES
+            .iter()
+            .map(|&t| format!("{:.2e}", response.evaluate(t) * 1.0e10))
+            .collect();
+        println!(
+            "{}: response peaks at {:.2e} K; {} DN cm^5 s^-1 pixel^-1 at {:?} K; corona {:.3e} DN s^-1 pixel^-1",
+            response.name,
+            response.peak_temperature(),
+            samples.join(" "),
+            RESPONSE_TEMPERATURES,
+            rate
+        );
+    }
+    // DEMs of the observations and of the corona's own synthetic counts
+    observations.push(EuvObservation::synthetic("corona", &passbands, &counts, EUV_EXPOSURE));
+    let log_edges: Vec<f64> = (0..=DEM_BINS).map(|i| DEM_LOG_START + DEM_LOG_STEP * i as f64).collect();
+    for observation in &observations {
+        report_dem_inversion(observation, &responses, &log_edges);
+    }
+
+    // Multi-thermal coronae of the bundled regions, each as deep as the line
+    // of sight its DEM was observed along
+    for structure in &structures {
+        let region = Corona {
+            radius: chromosphere_radius + structure.depth,
+            temperature: structure.mean_temperature(),
+            thermal_structure: Some(structure.clone()),
+            ..corona
+        };
+        report_coronal_region(
+            &region,
+            chromosphere_radius,
+            &lines,
+            &responses,
+            &losses[0],
+            &euv_grid,
+            &xray_grid,
+        );
+    }
+
+    for temperature in LOSS_TEMPERATURES {
+        let values: Vec<String> = losses
+            .iter()
+            .map(|loss| format!("{:.3e}", loss.evaluate(temperature)))
+            .collect();
+        let parts = LossComponents::at(temperature, &AbundanceSet::coronal(), &MetalCooling::bundled());
+        println!(
+            "Lambda({:.1e} K): {} W m^3 (coronal: H/He lines {:.2e}, recombination {:.2e}, bremsstrahlung {:.2e}, metal lines {:.2e})",
+            temperature,
+            values.join(" "),
+            parts.hydrogen_helium_lines,
+            parts.recombination,
+            parts.bremsstrahlung,
+            parts.metal_lines
+        );
+    }
+    for loss in &losses {
+        println!(
+            "Corona energy balance ({}): radiative loss {:.3e} W/m^3, net heating {:.3e} W/m^3, cooling time {:.3e} s",
+            loss.name,
+            corona.radiative_loss(loss),
+            corona.net_heating(loss),
+            corona.radiative_cooling_time(loss)
+        );
+    }
+
+    // Charge states frozen into the solar wind along streamlines from the base
+    for base_temperature in WIND_BASE_TEMPERATURES {
+        let streamline = SolarWindModule::model_streamline(base_temperature, WIND_BASE_ELECTRON_DENSITY, 20.0, 400);
+        let history: Vec<PlasmaSample> = streamline.iter().map(|&(_, sample)| sample).collect();
+        for (symbol, upper, lower) in FREEZE_IN_RATIOS {
+            let Some(element) = atomic_data::element(symbol).and_then(|e| ions.element(e)) else {
+                continue;
+            };
+            let evolution = element.evolve(&element.equilibrium(base_temperature), &history);
+            let ratio = evolution.ratio(upper, lower);
+            let frozen = ratio[ratio.len() - 1];
+            let freeze_in_radius = evolution
+                .freeze_in_index(upper, lower, 0.01)
+                .map(|i| streamline[i].0)
+                .unwrap_or(f64::NAN);
+            println!(
+                "Wind from {:.1e} K base: {}{}+/{}{}+ frozen at {:.3e} (equilibrium at base {:.3e}) by {:.2} R_sun, freeze-in temperature {:.3e} K",
+                base_temperature,
+                symbol,
+                upper,
+                symbol,
+                lower,
+                frozen,
+                ratio[0],
+                freeze_in_radius,
+                element.freeze_in_temperature(upper, lower, frozen).unwrap_or(f64::NAN)
+            );
+        }
+    }
+
+    // Iron lagging behind impulsive flare heating to 1.5e7 K
+    if let Some(iron) = atomic_data::element("Fe").and_then(|fe| ions.element(fe)) {
+        report_flare_ionization(iron, corona.temperature, &lines, &abundances);
+    }
 
     let distance_from_sun = 1.0; // AU (example value)
     let (solar_wind_velocity, solar_wind_density, solar_wind_temperature) =
@@ -0,0 +1,203 @@
+// Collisionally excited EUV and X-ray lines of the corona, read from
+// plain-text tables (format documented in data/corona/euv_lines.dat), with
+// ion fractions from the collisional ionization equilibrium.
+
+use radiation::atomic_data::{self, AbundanceSet, Element};
+use radiation::ionization::{self, IonizationData};
+use radiation::numerics::{error_function, exponential_integral};
+use radiation::planck::{BOLTZMANN_CONSTANT, PLANCK_CONSTANT, SPEED_OF_LIGHT};
+use radiation::spectral_grid::SpectralGrid;
+
+use std::error::Error;
+use std::f64::consts::PI;
+use std::fs;
+
+// Lines shipped with the repository
+const BUNDLED_LINES: &str = include_str!("../../data/corona/euv_lines.dat");
+
+// Collisional excitation rate coefficient 8.629e-6 Omega / (g sqrt(T)) cm^3 s^-1, in m^3 s^-1
+const COLLISION_RATE: f64 = 8.629e-12;
+// Smallest effective Gaunt factor of the Van Regemorter formula for positive ions
+const MINIMUM_GAUNT_FACTOR: f64 = 0.2;
+const RYDBERG_ENERGY: f64 = 13.605693; // eV
+const ATOMIC_MASS_UNIT: f64 = 1.66053907e-27; // kg
+
+#[derive(Clone, Debug)]
+pub(crate) struct CoronalLine {
+    pub element: &'static Element,
+    pub charge: u32,              // charge of the emitting ion
+    pub wavelength: f64,          // m, vacuum
+    pub lower_weight: f64,        // g of the ground level
+    pub weighted_oscillator: f64, // g_l f of the transition
+    pub branching_ratio: f64,     // fraction of the excitations decaying through the line
+}
+
+impl CoronalLine {
+    /// The Fe, O, Ne, Mg, Si and C lines bundled with the repository.
+    pub fn bundled() -> Vec<CoronalLine> {
+        parse_coronal_lines("bundled coronal lines", BUNDLED_LINES).expect("bundled coronal line table is well formed")
+    }
+
+    /// Label such as "Fe IX 17.11 nm".
+    pub fn label(&self) -> String {
+        format!(
+            "{} {} {:.2} nm",
+            self.element.symbol,
+            ionization::roman_numeral(self.charge + 1),
+            self.wavelength * 1.0e9
+        )
+    }
+
+    /// Photon energy in J.
+    pub fn photon_energy(&self) -> f64 {
+        PLANCK_CONSTANT * SPEED_OF_LIGHT / self.wavelength
+    }
+
+    /// Effective collision strength at `temperature` (K), Van Regemorter approximation.
+    pub fn collision_strength(&self, temperature: f64) -> f64 {
+        let x = self.photon_energy() / (BOLTZMANN_CONSTANT * temperature);
+        // e^x E1(x) underflows in its factors for large x, where g = 0.2 anyway
+        let gaunt = if x < 50.0 {
+            (3f64.sqrt() / (2.0 * PI) * x.exp() * exponential_integral(x)).max(MINIMUM_GAUNT_FACTOR)
+        } else {
+            MINIMUM_GAUNT_FACTOR
+        };
+        let rydbergs = self.photon_energy() / atomic_data::ELECTRON_VOLT / RYDBERG_ENERGY;
+        8.0 * PI / 3f64.sqrt() * self.weighted_oscillator * gaunt / rydbergs
+    }
+
+    /// Emissivity G(T) in W m^3 sr^-1: the power radiated per steradian and
+    /// per unit n_e n_H.
+    ///
+    /// # Arguments
+    ///
+    /// * `temperature` - The electron temperature in Kelvin.
+    /// * `ions` - Ionization data giving the equilibrium ion fraction.
+    /// * `abundances` - The element abundances of the plasma.
+    pub fn emissivity(&self, temperature: f64, ions: &IonizationData, abundances: &AbundanceSet) -> f64 {
+        let fraction = ions.ion_fraction(self.element, self.charge, temperature);
+        self.emissivity_with_fraction(temperature, fraction, abundances)
+    }
+
+    /// Emissivity G(T) in W m^3 sr^-1 for a given fraction of the element in
+    /// the emitting ion, e.g. out of ionization equilibrium.
+    pub fn emissivity_with_fraction(&self, temperature: f64, fraction: f64, abundances: &AbundanceSet) -> f64 {
+        let photon_energy = self.photon_energy();
+        let excitation = COLLISION_RATE * self.collision_strength(temperature)
+            / (self.lower_weight * temperature.sqrt())
+            * (-photon_energy / (BOLTZMANN_CONSTANT * temperature)).exp();
+        abundances.abundance(self.element) * fraction * excitation * self.branching_ratio * photon_energy / (4.0 * PI)
+    }
+
+    /// Thermal Doppler width (m, 1/e half width) at `temperature` (K).
+    pub fn doppler_width(&self, temperature: f64) -> f64 {
+        let ion_mass = self.element.mass_number * ATOMIC_MASS_UNIT;
+        self.wavelength / SPEED_OF_LIGHT * (2.0 * BOLTZMANN_CONSTANT * temperature / ion_mass).sqrt()
+    }
+}
+
+/// Spectral radiance (W m^-2 sr^-1 m^-1) of optically thin lines on a grid.
+///
+/// Each line is a thermal Gaussian; its intensity is shared between the
+/// grid cells, bounded halfway between neighbouring points, in proportion
+/// to the part of the profile each cell covers, so the integrated intensity
+/// is kept on grids coarser than the lines.
+///
+/// # Arguments
+///
+/// * `lines` - Pairs of a line and its intensity in W m^-2 sr^-1.
+/// * `temperature` - The temperature setting the Doppler widths, in Kelvin.
+/// * `grid` - The spectral sampling.
+pub(crate) fn line_spectrum(lines: &[(&CoronalLine, f64)], temperature: f64, grid: &SpectralGrid) -> Vec<(f64, f64)> {
+    let wavelengths = grid.wavelengths();
+    let n = wavelengths.len();
+    let edges: Vec<(f64, f64)> = (0..n)
+        .map(|i| {
+            let below = if i > 0 {
+                0.5 * (wavelengths[i - 1] + wavelengths[i])
+            } else {
+                wavelengths[i]
+            };
+            let above = if i + 1 < n {
+                0.5 * (wavelengths[i] + wavelengths[i + 1])
+            } else {
+                wavelengths[i]
+            };
+            (below.min(above), below.max(above))
+        })
+        .collect();
+    wavelengths
+        .iter()
+        .zip(&edges)
+        .map(|(&wavelength, &(low, high))| {
+            if high <= low {
+                return (wavelength, 0.0);
+            }
+            let radiance = lines
+                .iter()
+                .map(|&(line, intensity)| {
+                    let width = line.doppler_width(temperature);
+                    let covered = 0.5
+                        * (error_function((high - line.wavelength) / width)
+                            - error_function((low - line.wavelength) / width));
+                    intensity * covered
+                })
+                .sum::<f64>();
+            (wavelength, radiance / (high - low))
+        })
+        .collect()
+}
+
+/// Parses a line table (format documented in data/corona/euv_lines.dat).
+pub(crate) fn parse_coronal_lines(name: &str, text: &str) -> Result<Vec<CoronalLine>, Box<dyn Error>> {
+    let mut lines = Vec::new();
+    for (number, raw) in text.lines().enumerate() {
+        let content = raw.split('#').next().unwrap_or("");
+        let fields: Vec<&str> = content.split_whitespace().collect();
+        if fields.is_empty() {
+            continue;
+        }
+        if fields.len() < 6 {
+            return Err(format!(
+                "{} line {}: expected 6 columns, found {}",
+                name,
+                number + 1,
+                fields.len()
+            )
+            .into());
+        }
+        let value = |i: usize| -> Result<f64, Box<dyn Error>> {
+            fields[i]
+                .parse::<f64>()
+                .map_err(|e| format!("{} line {}, column {}: {}", name, number + 1, i + 1, e).into())
+        };
+        let element = atomic_data::element(fields[0])
+            .ok_or_else(|| format!("{} line {}: unknown element {}", name, number + 1, fields[0]))?;
+        let stage = ionization::parse_roman_numeral(fields[1])
+            .filter(|&stage| stage <= element.atomic_number)
+            .ok_or_else(|| format!("{} line {}: bad ionization stage {}", name, number + 1, fields[1]))?;
+        let line = CoronalLine {
+            element,
+            charge: stage - 1,
+            wavelength: value(2)? * 1.0e-9,
+            lower_weight: value(3)?,
+            weighted_oscillator: value(4)?,
+            branching_ratio: value(5)?,
+        };
+        if line.wavelength <= 0.0 || line.lower_weight <= 0.0 || line.weighted_oscillator <= 0.0 {
+            return Err(format!(
+                "{} line {}: wavelength, weight and oscillator strength must be positive",
+                name,
+                number + 1
+            )
+            .into());
+        }
+        lines.push(line);
+    }
+    Ok(lines)
+}
+
+/// Loads a line table from a local file.
+pub(crate) fn load_coronal_lines(path: &str) -> Result<Vec<CoronalLine>, Box<dyn Error>> {
+    parse_coronal_lines(path, &fs::read_to_string(path)?)
+}
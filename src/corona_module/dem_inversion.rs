@@ -0,0 +1,494 @@
+// Differential emission measure (DEM) inversion of multi-channel EUV
+// intensities.
+//
+// Each channel sees I_i = sum_j R_i(T_j) DEM(T_j) dT_j over temperature
+// bins, a badly conditioned system with far fewer channels than bins. Two
+// solutions are offered:
+//
+// * Regularized least squares (zeroth-order Tikhonov, as in Hannah & Kontar
+//   2012): minimize chi^2 + lambda |DEM|^2 with lambda set so that chi^2
+//   equals the number of channels. Bins that come out negative are fixed at
+//   zero and the rest solved again, until none is. The uncertainties follow
+//   linearly from those of the intensities.
+// * A sparse basis method (Cheung et al. 2015): the DEM is a non-negative
+//   sum of Gaussians in log T of several widths, the one of least total
+//   emission measure that fits every channel to within a tolerance, found
+//   by linear programming. Bins the channels hardly respond to would need a
+//   large emission measure to matter and stay empty. The uncertainties come
+//   from refitting intensities perturbed by their errors.
+//
+// Observation files are documented in data/euv/active_region.dat.
+
+use super::euv_response::{Passband, TemperatureResponse};
+use radiation::numerics::{linear_program, non_negative_least_squares, solve_linear_system};
+
+use std::error::Error;
+use std::fs;
+
+// Observation shipped with the repository
+const BUNDLED_OBSERVATION: &str = include_str!("../../data/euv/active_region.dat");
+// Fractional calibration uncertainty added to the photon noise of
+// synthetic intensities
+const CALIBRATION_UNCERTAINTY: f64 = 0.1;
+// Widths (dex) of the Gaussian basis functions in log T; zero is a single bin
+const BASIS_WIDTHS: [f64; 3] = [0.0, 0.1, 0.2];
+// Misfit (in uncertainties) the sparse basis solution may leave in each
+// channel, as in Cheung et al. (2015)
+const SPARSITY_TOLERANCE: f64 = 1.4;
+// Noise realizations for the uncertainties of the basis method
+const MONTE_CARLO_REALIZATIONS: usize = 200;
+const MONTE_CARLO_SEED: u64 = 0x2545_f491_4f6c_dd1d;
+
+// Intensities of the channels of one pixel or region
+#[derive(Clone, Debug)]
+pub(crate) struct EuvObservation {
+    pub name: String,
+    pub channels: Vec<String>,
+    pub intensities: Vec<f64>,   // DN s^-1 pixel^-1
+    pub uncertainties: Vec<f64>, // DN s^-1 pixel^-1, 1 sigma
+}
+
+// Inversion of one observation on a grid of temperature bins
+#[derive(Clone, Debug)]
+pub(crate) struct DemProblem {
+    pub log_edges: Vec<f64>,   // bin edges in log10 T, increasing
+    pub kernel: Vec<Vec<f64>>, // [channel][bin], R_i(T_j) dT_j in DN s^-1 pixel^-1 per m^-5 K^-1
+    pub observation: EuvObservation,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct DemSolution {
+    pub method: &'static str,
+    pub log_temperatures: Vec<f64>,  // bin centres
+    pub dem: Vec<f64>,               // m^-5 K^-1
+    pub uncertainty: Vec<f64>,       // m^-5 K^-1, 1 sigma
+    pub model_intensities: Vec<f64>, // DN s^-1 pixel^-1
+    pub chi_squared: f64,
+}
+
+impl EuvObservation {
+    /// The active region observation bundled with the repository.
+    pub fn bundled() -> Self {
+        parse_observation("active region", BUNDLED_OBSERVATION).expect("bundled observation is well formed")
+    }
+
+    /// Synthetic observation of count rates `counts` (DN s^-1 pixel^-1) in
+    /// `passbands`, with the photon noise of an `exposure` (s) and a
+    /// calibration uncertainty.
+    pub fn synthetic(name: &str, passbands: &[Passband], counts: &[f64], exposure: f64) -> Self {
+        let uncertainties = passbands
+            .iter()
+            .zip(counts)
+            .map(|(passband, &rate)| {
+                let photon_noise = rate.max(0.0) * passband.dn_per_photon() / exposure;
+                (photon_noise + (CALIBRATION_UNCERTAINTY * rate).powi(2)).sqrt()
+            })
+            .collect();
+        EuvObservation {
+            name: name.to_string(),
+            channels: passbands.iter().map(|passband| passband.name.clone()).collect(),
+            intensities: counts.to_vec(),
+            uncertainties,
+        }
+    }
+}
+
+impl DemProblem {
+    /// Sets up the inversion of `observation` with the channel responses.
+    ///
+    /// # Arguments
+    ///
+    /// * `responses` - Temperature responses; every observed channel needs
+    ///   one of the same name.
+    /// * `observation` - The intensities and their uncertainties.
+    /// * `log_edges` - Temperature bin edges in log10 T, increasing.
+    pub fn new(
+        responses: &[TemperatureResponse],
+        observation: &EuvObservation,
+        log_edges: &[f64],
+    ) -> Result<Self, Box<dyn Error>> {
+        let kernel = observation
+            .channels
+            .iter()
+            .map(|channel| {
+                let response = responses
+                    .iter()
+                    .find(|response| &response.name == channel)
+                    .ok_or_else(|| format!("{}: no response for channel {}", observation.name, channel))?;
+                Ok(log_edges
+                    .windows(2)
+                    .map(|edges| {
+                        let centre = 10f64.powf(0.5 * (edges[0] + edges[1]));
+                        response.evaluate(centre) * (10f64.powf(edges[1]) - 10f64.powf(edges[0]))
+                    })
+                    .collect())
+            })
+            .collect::<Result<Vec<Vec<f64>>, Box<dyn Error>>>()?;
+        Ok(DemProblem {
+            log_edges: log_edges.to_vec(),
+            kernel,
+            observation: observation.clone(),
+        })
+    }
+
+    /// Zeroth-order regularized least squares with chi^2 equal to the number
+    /// of channels, or the least-regularized solution if the data cannot be
+    /// fitted that well. Bins fixed at zero to keep the DEM positive have
+    /// zero uncertainty; a singular system is an error.
+    pub fn regularized(&self) -> Result<DemSolution, Box<dyn Error>> {
+        let (design, data, scale) = self.weighted_system();
+        let bins = self.bins();
+        let mut solution = vec![0.0; bins];
+        let mut uncertainty = vec![0.0; bins];
+        let mut free = vec![true; bins];
+        while free.iter().any(|&f| f) {
+            let columns: Vec<usize> = (0..bins).filter(|&j| free[j]).collect();
+            let reduced: Vec<Vec<f64>> = design
+                .iter()
+                .map(|row| columns.iter().map(|&j| row[j]).collect())
+                .collect();
+            let (values, errors) = tikhonov(&reduced, &data)
+                .ok_or_else(|| format!("{}: singular regularized system", self.observation.name))?;
+            let negative: Vec<usize> = columns
+                .iter()
+                .zip(&values)
+                .filter(|&(_, &value)| value < 0.0)
+                .map(|(&j, _)| j)
+                .collect();
+            if negative.is_empty() {
+                for ((&j, value), error) in columns.iter().zip(values).zip(errors) {
+                    solution[j] = value;
+                    uncertainty[j] = error / scale;
+                }
+                break;
+            }
+            for j in negative {
+                free[j] = false;
+            }
+        }
+        Ok(self.solution("regularized", &solution, uncertainty, scale))
+    }
+
+    /// Non-negative sum of Gaussian basis functions in log T of least total
+    /// emission measure that fits each channel to within the tolerance, or
+    /// to within the misfit of the best non-negative fit where that is
+    /// larger; uncertainties from Monte Carlo refits of perturbed
+    /// intensities.
+    pub fn sparse_basis(&self) -> DemSolution {
+        let (design, data, scale) = self.weighted_system();
+        let bins = self.bins();
+        let centres: Vec<f64> = self.log_edges.windows(2).map(|e| 0.5 * (e[0] + e[1])).collect();
+        let mut basis: Vec<Vec<f64>> = Vec::new();
+        for width in BASIS_WIDTHS {
+            for &centre in &centres {
+                let function = centres
+                    .iter()
+                    .map(|&log_t| {
+                        if width == 0.0 {
+                            if (log_t - centre).abs() < 1.0e-9 {
+                                1.0
+                            } else {
+                                0.0
+                            }
+                        } else {
+                            (-0.5 * ((log_t - centre) / width).powi(2)).exp()
+                        }
+                    })
+                    .collect();
+                basis.push(function);
+            }
+        }
+        let basis_design: Vec<Vec<f64>> = design
+            .iter()
+            .map(|row| {
+                basis
+                    .iter()
+                    .map(|function| row.iter().zip(function).map(|(k, b)| k * b).sum())
+                    .collect()
+            })
+            .collect();
+        // Emission measure of each basis function, relative to the largest
+        let widths: Vec<f64> = self
+            .log_edges
+            .windows(2)
+            .map(|e| 10f64.powf(e[1]) - 10f64.powf(e[0]))
+            .collect();
+        let cost: Vec<f64> = basis
+            .iter()
+            .map(|function| function.iter().zip(&widths).map(|(b, w)| b * w).sum())
+            .collect();
+        let largest = cost.iter().cloned().fold(f64::MIN_POSITIVE, f64::max);
+        let cost: Vec<f64> = cost.iter().map(|c| c / largest).collect();
+        let fit = |values: &[f64]| -> Vec<f64> {
+            let best = non_negative_least_squares(&basis_design, values);
+            let misfit = basis_design
+                .iter()
+                .zip(values)
+                .map(|(row, y)| (row.iter().zip(&best).map(|(k, x)| k * x).sum::<f64>() - y).abs())
+                .fold(SPARSITY_TOLERANCE, f64::max);
+            // |K x - y| <= tolerance as K x <= y + tolerance and -K x <= tolerance - y
+            let tolerance = misfit * (1.0 + 1.0e-6);
+            let constraints: Vec<Vec<f64>> = basis_design
+                .iter()
+                .cloned()
+                .chain(basis_design.iter().map(|row| row.iter().map(|k| -k).collect()))
+                .collect();
+            let bounds: Vec<f64> = values
+                .iter()
+                .map(|y| y + tolerance)
+                .chain(values.iter().map(|y| tolerance - y))
+                .collect();
+            let weights = linear_program(&cost, &constraints, &bounds).unwrap_or(best);
+            (0..bins)
+                .map(|j| basis.iter().zip(&weights).map(|(function, w)| function[j] * w).sum())
+                .collect()
+        };
+        let solution = fit(&data);
+        // Unit-variance noise on the weighted data
+        let mut random = NormalDeviates::new(MONTE_CARLO_SEED);
+        let mut sum_squares = vec![0.0; bins];
+        for _ in 0..MONTE_CARLO_REALIZATIONS {
+            let perturbed: Vec<f64> = data.iter().map(|y| y + random.next()).collect();
+            for ((s, x), x0) in sum_squares.iter_mut().zip(fit(&perturbed)).zip(&solution) {
+                *s += (x - x0) * (x - x0);
+            }
+        }
+        let uncertainty = sum_squares
+            .iter()
+            .map(|s| (s / MONTE_CARLO_REALIZATIONS as f64).sqrt() / scale)
+            .collect();
+        self.solution("sparse basis", &solution, uncertainty, scale)
+    }
+
+    fn bins(&self) -> usize {
+        self.log_edges.len().saturating_sub(1)
+    }
+
+    // Kernel and data divided by the uncertainties, and the kernel scaled
+    // to order unity; unknowns are the DEM times the returned scale
+    fn weighted_system(&self) -> (Vec<Vec<f64>>, Vec<f64>, f64) {
+        let observation = &self.observation;
+        let weighted: Vec<Vec<f64>> = self
+            .kernel
+            .iter()
+            .zip(&observation.uncertainties)
+            .map(|(row, sigma)| row.iter().map(|k| k / sigma).collect())
+            .collect();
+        let scale = weighted
+            .iter()
+            .flatten()
+            .cloned()
+            .fold(0.0, f64::max)
+            .max(f64::MIN_POSITIVE);
+        let design = weighted
+            .iter()
+            .map(|row| row.iter().map(|k| k / scale).collect())
+            .collect();
+        let data = observation
+            .intensities
+            .iter()
+            .zip(&observation.uncertainties)
+            .map(|(intensity, sigma)| intensity / sigma)
+            .collect();
+        (design, data, scale)
+    }
+
+    fn solution(&self, method: &'static str, scaled: &[f64], uncertainty: Vec<f64>, scale: f64) -> DemSolution {
+        let dem: Vec<f64> = scaled.iter().map(|x| x / scale).collect();
+        let model_intensities: Vec<f64> = self
+            .kernel
+            .iter()
+            .map(|row| row.iter().zip(&dem).map(|(k, d)| k * d).sum())
+            .collect();
+        let chi_squared = model_intensities
+            .iter()
+            .zip(&self.observation.intensities)
+            .zip(&self.observation.uncertainties)
+            .map(|((model, observed), sigma)| ((model - observed) / sigma).powi(2))
+            .sum();
+        DemSolution {
+            method,
+            log_temperatures: self.log_edges.windows(2).map(|e| 0.5 * (e[0] + e[1])).collect(),
+            dem,
+            uncertainty,
+            model_intensities,
+            chi_squared,
+        }
+    }
+}
+
+impl DemSolution {
+    /// Total emission measure (m^-5) over the temperature bins.
+    pub fn emission_measure(&self, log_edges: &[f64]) -> f64 {
+        log_edges
+            .windows(2)
+            .zip(&self.dem)
+            .map(|(edges, dem)| dem * (10f64.powf(edges[1]) - 10f64.powf(edges[0])))
+            .sum()
+    }
+
+    /// Emission-measure weighted mean temperature (K).
+    pub fn mean_temperature(&self, log_edges: &[f64]) -> f64 {
+        let (weighted, total) =
+            log_edges
+                .windows(2)
+                .zip(&self.dem)
+                .fold((0.0, 0.0), |(weighted, total), (edges, dem)| {
+                    let measure = dem * (10f64.powf(edges[1]) - 10f64.powf(edges[0]));
+                    (
+                        weighted + measure * 10f64.powf(0.5 * (edges[0] + edges[1])),
+                        total + measure,
+                    )
+                });
+        weighted / total
+    }
+}
+
+// Zeroth-order regularized solution of the weighted system with chi^2
+// equal to the number of rows where the data allow, and its uncertainties;
+// `None` if the regularized normal matrix is singular
+fn tikhonov(design: &[Vec<f64>], data: &[f64]) -> Option<(Vec<f64>, Vec<f64>)> {
+    let bins = design.first().map_or(0, |row| row.len());
+    let channels = data.len();
+    // Normal matrix K^T K and the solution for a given lambda
+    let normal: Vec<Vec<f64>> = (0..bins)
+        .map(|a| {
+            (0..bins)
+                .map(|b| design.iter().map(|row| row[a] * row[b]).sum())
+                .collect()
+        })
+        .collect();
+    let trace = (0..bins).map(|a| normal[a][a]).sum::<f64>() / bins as f64;
+    let solve = |lambda: f64, rhs: Vec<f64>| -> Option<Vec<f64>> {
+        let mut matrix = normal.clone();
+        for (a, row) in matrix.iter_mut().enumerate() {
+            row[a] += lambda;
+        }
+        solve_linear_system(matrix, rhs)
+    };
+    let project = |values: &[f64]| -> Vec<f64> {
+        (0..bins)
+            .map(|a| design.iter().zip(values).map(|(row, v)| row[a] * v).sum())
+            .collect()
+    };
+    let chi_squared_at = |log_lambda: f64| -> Option<f64> {
+        Some(chi_squared(
+            design,
+            data,
+            &solve(trace * 10f64.powf(log_lambda), project(data))?,
+        ))
+    };
+    // chi^2 grows with lambda: bisect for chi^2 = number of channels
+    let (mut low, mut high) = (-14.0, 4.0);
+    let log_lambda = if chi_squared_at(low)? >= channels as f64 {
+        low
+    } else {
+        for _ in 0..60 {
+            let mid = 0.5 * (low + high);
+            if chi_squared_at(mid)? < channels as f64 {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+        0.5 * (low + high)
+    };
+    let lambda = trace * 10f64.powf(log_lambda);
+    let solution = solve(lambda, project(data))?;
+    // The solution is linear in the unit-variance data: x = M y, with
+    // column i of M the response to channel i alone
+    let mut variance = vec![0.0; bins];
+    for i in 0..channels {
+        let mut unit = vec![0.0; channels];
+        unit[i] = 1.0;
+        for (v, m) in variance.iter_mut().zip(solve(lambda, project(&unit))?) {
+            *v += m * m;
+        }
+    }
+    Some((solution, variance.iter().map(|v| v.sqrt()).collect()))
+}
+
+// Sum of squared residuals of the weighted system
+fn chi_squared(design: &[Vec<f64>], data: &[f64], solution: &[f64]) -> f64 {
+    design
+        .iter()
+        .zip(data)
+        .map(|(row, y)| (row.iter().zip(solution).map(|(k, x)| k * x).sum::<f64>() - y).powi(2))
+        .sum()
+}
+
+// Standard normal deviates from a xorshift64* generator (Box-Muller)
+struct NormalDeviates {
+    state: u64,
+}
+
+impl NormalDeviates {
+    fn new(seed: u64) -> Self {
+        NormalDeviates { state: seed.max(1) }
+    }
+
+    fn uniform(&mut self) -> f64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        let bits = self.state.wrapping_mul(0x2545_f491_4f6c_dd1d) >> 11;
+        (bits as f64 + 0.5) / (1u64 << 53) as f64
+    }
+
+    fn next(&mut self) -> f64 {
+        let (u, v) = (self.uniform(), self.uniform());
+        (-2.0 * u.ln()).sqrt() * (2.0 * std::f64::consts::PI * v).cos()
+    }
+}
+
+/// Parses an observation file: '#' comments, then a channel name (which
+/// may contain spaces), the intensity and its uncertainty (DN s^-1
+/// pixel^-1) per line.
+pub(crate) fn parse_observation(name: &str, text: &str) -> Result<EuvObservation, Box<dyn Error>> {
+    let mut observation = EuvObservation {
+        name: name.to_string(),
+        channels: Vec::new(),
+        intensities: Vec::new(),
+        uncertainties: Vec::new(),
+    };
+    for (number, raw) in text.lines().enumerate() {
+        let content = raw.split('#').next().unwrap_or("");
+        let fields: Vec<&str> = content.split_whitespace().collect();
+        if fields.is_empty() {
+            continue;
+        }
+        if fields.len() < 3 {
+            return Err(format!(
+                "{} line {}: expected a channel, an intensity and an uncertainty",
+                name,
+                number + 1
+            )
+            .into());
+        }
+        let value = |i: usize| -> Result<f64, Box<dyn Error>> {
+            fields[i]
+                .parse::<f64>()
+                .map_err(|e| format!("{} line {}, column {}: {}", name, number + 1, i + 1, e).into())
+        };
+        let uncertainty = value(fields.len() - 1)?;
+        if uncertainty <= 0.0 {
+            return Err(format!("{} line {}: uncertainty must be positive", name, number + 1).into());
+        }
+        observation.intensities.push(value(fields.len() - 2)?);
+        observation.uncertainties.push(uncertainty);
+        observation.channels.push(fields[..fields.len() - 2].join(" "));
+    }
+    if observation.channels.is_empty() {
+        return Err(format!("{}: no channels", name).into());
+    }
+    Ok(observation)
+}
+
+/// Loads an observation from a local file; the file stem names it.
+pub(crate) fn load_observation(path: &str) -> Result<EuvObservation, Box<dyn Error>> {
+    let name = std::path::Path::new(path)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or(path);
+    parse_observation(name, &fs::read_to_string(path)?)
+}
@@ -0,0 +1,234 @@
+// EUV imager passbands (SDO/AIA-like) and their temperature responses:
+// the count rate per pixel from unit emission measure of an isothermal
+// plasma, from the coronal line emissivities and the thermal continuum.
+//
+// A photon of energy E frees E / 3.65 eV electrons in the CCD, read out at
+// `gain` electrons per DN, so the counts depend on the energy reaching the
+// detector alone:
+//
+//   R(T) = Omega_pix / (3.65 eV gain) [sum_lines A(lambda) G(T) + int A(lambda) eps_lambda(T) dlambda]
+//
+// in DN s^-1 pixel^-1 per unit column emission measure n_e n_H ds (m^-5).
+// Passband files are documented in data/euv/aia_171.dat.
+
+use super::coronal_lines::CoronalLine;
+use radiation::atomic_data::{AbundanceSet, ELECTRON_VOLT};
+use radiation::ionization::IonizationData;
+use radiation::numerics::trapezoid;
+use radiation::planck::{PLANCK_CONSTANT, SPEED_OF_LIGHT};
+use radiation::xray_continuum::ThermalContinuum;
+
+use std::error::Error;
+use std::f64::consts::PI;
+use std::fs;
+
+// Passband files shipped with the repository (data/euv)
+const BUNDLED_PASSBANDS: [(&str, &str); 6] = [
+    ("AIA 94", include_str!("../../data/euv/aia_94.dat")),
+    ("AIA 131", include_str!("../../data/euv/aia_131.dat")),
+    ("AIA 171", include_str!("../../data/euv/aia_171.dat")),
+    ("AIA 193", include_str!("../../data/euv/aia_193.dat")),
+    ("AIA 211", include_str!("../../data/euv/aia_211.dat")),
+    ("AIA 335", include_str!("../../data/euv/aia_335.dat")),
+];
+// Temperature grid of the tabulated responses, log10 T
+const RESPONSE_LOG_START: f64 = 4.5;
+const RESPONSE_LOG_STEP: f64 = 0.05;
+const RESPONSE_POINTS: usize = 71;
+const ARCSEC: f64 = PI / (180.0 * 3600.0); // rad
+const ELECTRON_HOLE_PAIR_ENERGY: f64 = 3.65 * ELECTRON_VOLT; // J, silicon
+
+#[derive(Clone, Debug)]
+pub(crate) struct Passband {
+    pub name: String,
+    pub effective_area: Vec<(f64, f64)>, // (wavelength in m, area in m^2)
+    pub pixel_size: f64,                 // arcsec
+    pub gain: f64,                       // electrons per DN
+}
+
+// Response of a passband tabulated in temperature
+#[derive(Clone, Debug)]
+pub(crate) struct TemperatureResponse {
+    pub name: String,
+    pub log_temperatures: Vec<f64>,
+    pub responses: Vec<f64>, // DN s^-1 pixel^-1 per m^-5
+}
+
+impl Passband {
+    /// The six AIA EUV channels bundled with the repository.
+    pub fn bundled() -> Vec<Passband> {
+        BUNDLED_PASSBANDS
+            .iter()
+            .map(|(name, text)| parse_passband(name, text).expect("bundled passband is well formed"))
+            .collect()
+    }
+
+    /// Effective area (m^2) at `wavelength` (m), linearly interpolated and
+    /// zero outside the table.
+    pub fn area_at(&self, wavelength: f64) -> f64 {
+        let table = &self.effective_area;
+        let index = table.partition_point(|&(w, _)| w < wavelength);
+        if index == 0 || index == table.len() {
+            return 0.0;
+        }
+        let ((w0, a0), (w1, a1)) = (table[index - 1], table[index]);
+        a0 + (a1 - a0) * (wavelength - w0) / (w1 - w0)
+    }
+
+    /// Solid angle (sr) of one pixel.
+    pub fn pixel_solid_angle(&self) -> f64 {
+        (self.pixel_size * ARCSEC).powi(2)
+    }
+
+    /// Count rate (DN s^-1 pixel^-1) of a radiance `energy_radiance`
+    /// (W m^-2 sr^-1) collected through an area `area` (m^2).
+    pub fn count_rate(&self, energy_radiance: f64, area: f64) -> f64 {
+        energy_radiance * area * self.pixel_solid_angle() / (ELECTRON_HOLE_PAIR_ENERGY * self.gain)
+    }
+
+    /// DN recorded per photon at the area-weighted mean wavelength of the
+    /// channel.
+    pub fn dn_per_photon(&self) -> f64 {
+        let weighted = trapezoid(&self.effective_area.iter().map(|&(w, a)| (w, w * a)).collect::<Vec<_>>());
+        let wavelength = weighted / trapezoid(&self.effective_area);
+        PLANCK_CONSTANT * SPEED_OF_LIGHT / wavelength / (ELECTRON_HOLE_PAIR_ENERGY * self.gain)
+    }
+
+    /// Response (DN s^-1 pixel^-1 per m^-5) of an isothermal plasma in
+    /// ionization equilibrium at `temperature` (K).
+    ///
+    /// # Arguments
+    ///
+    /// * `lines` - The coronal lines; those inside the passband count.
+    /// * `ions` - Ionization data for the ion fractions.
+    /// * `abundances` - The element abundances of the plasma.
+    pub fn response(
+        &self,
+        temperature: f64,
+        lines: &[CoronalLine],
+        ions: &IonizationData,
+        abundances: &AbundanceSet,
+    ) -> f64 {
+        let line_radiance: f64 = lines
+            .iter()
+            .map(|line| self.area_at(line.wavelength) * line.emissivity(temperature, ions, abundances))
+            .sum();
+        // Continuum per unit photon energy into 4 pi, to per sr and per m
+        let continuum = ThermalContinuum::new(temperature, abundances, ions);
+        let weighted: Vec<(f64, f64)> = self
+            .effective_area
+            .iter()
+            .map(|&(wavelength, area)| {
+                let energy = PLANCK_CONSTANT * SPEED_OF_LIGHT / wavelength;
+                let per_wavelength = continuum.emissivity(energy) * energy / wavelength / (4.0 * PI);
+                (wavelength, area * per_wavelength)
+            })
+            .collect();
+        self.count_rate(line_radiance + trapezoid(&weighted), 1.0)
+    }
+
+    /// The response tabulated from 10^4.5 to 10^8 K in steps of 0.05 dex.
+    pub fn temperature_response(
+        &self,
+        lines: &[CoronalLine],
+        ions: &IonizationData,
+        abundances: &AbundanceSet,
+    ) -> TemperatureResponse {
+        let log_temperatures: Vec<f64> = (0..RESPONSE_POINTS)
+            .map(|i| RESPONSE_LOG_START + RESPONSE_LOG_STEP * i as f64)
+            .collect();
+        let responses = log_temperatures
+            .iter()
+            .map(|&log_t| self.response(10f64.powf(log_t), lines, ions, abundances))
+            .collect();
+        TemperatureResponse {
+            name: self.name.clone(),
+            log_temperatures,
+            responses,
+        }
+    }
+}
+
+impl TemperatureResponse {
+    /// Response (DN s^-1 pixel^-1 per m^-5) at `temperature` (K),
+    /// interpolated in log T and zero outside the table.
+    pub fn evaluate(&self, temperature: f64) -> f64 {
+        let log_t = temperature.log10();
+        let index = self.log_temperatures.partition_point(|&l| l < log_t);
+        if index == 0 || index == self.log_temperatures.len() {
+            return 0.0;
+        }
+        let (l0, l1) = (self.log_temperatures[index - 1], self.log_temperatures[index]);
+        let (r0, r1) = (self.responses[index - 1], self.responses[index]);
+        r0 + (r1 - r0) * (log_t - l0) / (l1 - l0)
+    }
+
+    /// Count rate (DN s^-1 pixel^-1) of an isothermal column of emission
+    /// measure `emission_measure` (m^-5) at `temperature` (K).
+    pub fn counts(&self, temperature: f64, emission_measure: f64) -> f64 {
+        self.evaluate(temperature) * emission_measure
+    }
+
+    /// Temperature (K) of the largest response.
+    pub fn peak_temperature(&self) -> f64 {
+        let (index, _) = self
+            .responses
+            .iter()
+            .enumerate()
+            .fold((0, f64::MIN), |best, (i, &r)| if r > best.1 { (i, r) } else { best });
+        10f64.powf(self.log_temperatures[index])
+    }
+}
+
+/// Temperature responses of `passbands` for `lines`, with the bundled
+/// ionization data at coronal abundances.
+pub(crate) fn responses(passbands: &[Passband], lines: &[CoronalLine]) -> Vec<TemperatureResponse> {
+    let ions = IonizationData::bundled();
+    let abundances = AbundanceSet::coronal();
+    passbands
+        .iter()
+        .map(|passband| passband.temperature_response(lines, &ions, &abundances))
+        .collect()
+}
+
+/// Parses a passband file: '#' comments, `pixel <arcsec>` and
+/// `gain <electrons per DN>` lines, then wavelength (nm) and effective area
+/// (cm^2) columns.
+pub(crate) fn parse_passband(name: &str, text: &str) -> Result<Passband, Box<dyn Error>> {
+    let (mut pixel_size, mut gain) = (None, None);
+    let mut effective_area = Vec::new();
+    for (number, raw) in text.lines().enumerate() {
+        let content = raw.split('#').next().unwrap_or("");
+        let fields: Vec<&str> = content.split_whitespace().collect();
+        match fields.as_slice() {
+            [] => continue,
+            ["pixel", value] => pixel_size = Some(value.parse::<f64>()?),
+            ["gain", value] => gain = Some(value.parse::<f64>()?),
+            [wavelength, area] => {
+                effective_area.push((wavelength.parse::<f64>()? * 1.0e-9, area.parse::<f64>()? * 1.0e-4))
+            }
+            _ => return Err(format!("passband {} line {}: cannot parse '{}'", name, number + 1, raw.trim()).into()),
+        }
+    }
+    let pixel_size = pixel_size.ok_or_else(|| format!("passband {}: missing 'pixel' line", name))?;
+    let gain = gain.ok_or_else(|| format!("passband {}: missing 'gain' line", name))?;
+    if effective_area.len() < 2 {
+        return Err(format!("passband {}: needs at least two effective area points", name).into());
+    }
+    effective_area.sort_by(|a, b| a.0.total_cmp(&b.0));
+    Ok(Passband {
+        name: name.to_string(),
+        effective_area,
+        pixel_size,
+        gain,
+    })
+}
+
+/// Loads a passband from a local file; the file stem names the channel.
+pub(crate) fn load_passband(path: &str) -> Result<Passband, Box<dyn Error>> {
+    let name = std::path::Path::new(path)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or(path);
+    parse_passband(name, &fs::read_to_string(path)?)
+}
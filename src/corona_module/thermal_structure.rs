@@ -0,0 +1,209 @@
+// Multi-thermal structure of the corona: a set of isothermal components, each
+// filling part of the volume at its own temperature and density.
+//
+// A component of electron density n_e and filling factor f contributes the
+// column emission measure n_e n_H f L to a path of length L, and the free-free
+// optical depth alpha(T, n_e) f L. Components are built from a differential
+// emission measure (DEM) at one pressure: the DEM at each tabulated
+// temperature, times the width of its temperature bin, gives the emission
+// measure of the component along the line of sight through which the DEM was
+// observed; the pressure gives the density, and the two together the filling
+// factor over the depth of that line of sight. The bundled regions are in
+// data/corona.
+
+use super::{ELECTRONS_PER_HYDROGEN, HELIUM_ABUNDANCE};
+use radiation::atomic_data;
+use radiation::planck::BOLTZMANN_CONSTANT;
+
+use std::error::Error;
+use std::fs;
+
+// Regions shipped with the repository
+const ACTIVE_REGION_DEM: &str = include_str!("../../data/corona/active_region_dem.dat");
+const QUIET_SUN_DEM: &str = include_str!("../../data/corona/quiet_sun_dem.dat");
+const CORONAL_HOLE_DEM: &str = include_str!("../../data/corona/coronal_hole_dem.dat");
+const MEGAMETRE: f64 = 1.0e6; // m
+
+// Bundled regions
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum CoronalRegion {
+    ActiveRegion, // active region core, peaked near 3 MK
+    QuietSun,     // quiet corona at disk centre, near 1.3 MK
+    CoronalHole,  // polar coronal hole, near 0.9 MK
+}
+
+impl CoronalRegion {
+    pub const ALL: [CoronalRegion; 3] = [
+        CoronalRegion::ActiveRegion,
+        CoronalRegion::QuietSun,
+        CoronalRegion::CoronalHole,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            CoronalRegion::ActiveRegion => "active region",
+            CoronalRegion::QuietSun => "quiet Sun",
+            CoronalRegion::CoronalHole => "coronal hole",
+        }
+    }
+
+    fn table(self) -> &'static str {
+        match self {
+            CoronalRegion::ActiveRegion => ACTIVE_REGION_DEM,
+            CoronalRegion::QuietSun => QUIET_SUN_DEM,
+            CoronalRegion::CoronalHole => CORONAL_HOLE_DEM,
+        }
+    }
+}
+
+// Fully ionized plasma at one temperature
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ThermalComponent {
+    pub temperature: f64,      // K
+    pub electron_density: f64, // m^-3
+    pub filling_factor: f64,   // fraction of the volume
+}
+
+impl ThermalComponent {
+    pub fn hydrogen_density(&self) -> f64 {
+        self.electron_density / ELECTRONS_PER_HYDROGEN
+    }
+
+    /// Mass density in kg/m^3.
+    pub fn density(&self) -> f64 {
+        self.hydrogen_density() * atomic_data::mass_per_hydrogen()
+    }
+
+    /// Gas pressure (Pa) of hydrogen, helium and electrons.
+    pub fn pressure(&self) -> f64 {
+        (self.hydrogen_density() * (1.0 + HELIUM_ABUNDANCE) + self.electron_density)
+            * BOLTZMANN_CONSTANT
+            * self.temperature
+    }
+
+    /// Column emission measure n_e n_H f L (m^-5) over a path `length` (m).
+    pub fn emission_measure(&self, length: f64) -> f64 {
+        self.electron_density * self.hydrogen_density() * self.filling_factor * length
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct ThermalStructure {
+    pub name: String,
+    pub components: Vec<ThermalComponent>, // increasing temperature
+    pub depth: f64,                        // m, path the filling factors were derived over
+}
+
+impl ThermalStructure {
+    /// One of the regions bundled with the repository.
+    pub fn bundled(region: CoronalRegion) -> Self {
+        parse_thermal_structure(region.name(), region.table()).expect("bundled coronal region is well formed")
+    }
+
+    /// Components at one pressure from a differential emission measure.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the structure.
+    /// * `dem` - (log10 T, DEM in m^-5 K^-1) in increasing temperature, at
+    ///   least two points; each point stands for the bin reaching halfway to
+    ///   its neighbours. Points of zero or negative DEM are left out.
+    /// * `pressure` - The gas pressure in Pa.
+    /// * `depth` - The length (m) of the line of sight the DEM refers to.
+    pub fn from_dem(name: &str, dem: &[(f64, f64)], pressure: f64, depth: f64) -> Result<Self, Box<dyn Error>> {
+        if dem.len() < 2 {
+            return Err(format!("{}: needs at least two DEM points", name).into());
+        }
+        // Electrons per unit of n_e + n_H (1 + He), the particles carrying the pressure
+        let electron_share = 1.0 / (1.0 + (1.0 + HELIUM_ABUNDANCE) / ELECTRONS_PER_HYDROGEN);
+        let n = dem.len();
+        let mut components = Vec::with_capacity(n);
+        for i in 0..n {
+            let (log_t, value) = dem[i];
+            if value <= 0.0 {
+                continue;
+            }
+            let below = if i > 0 { dem[i - 1].0 } else { 2.0 * log_t - dem[1].0 };
+            let above = if i + 1 < n {
+                dem[i + 1].0
+            } else {
+                2.0 * log_t - dem[n - 2].0
+            };
+            let width = 10f64.powf(0.5 * (log_t + above)) - 10f64.powf(0.5 * (below + log_t));
+            let temperature = 10f64.powf(log_t);
+            let electron_density = electron_share * pressure / (BOLTZMANN_CONSTANT * temperature);
+            let hydrogen_density = electron_density / ELECTRONS_PER_HYDROGEN;
+            components.push(ThermalComponent {
+                temperature,
+                electron_density,
+                filling_factor: value * width / (electron_density * hydrogen_density * depth),
+            });
+        }
+        if components.is_empty() {
+            return Err(format!("{}: no positive DEM points", name).into());
+        }
+        let structure = ThermalStructure {
+            name: name.to_string(),
+            components,
+            depth,
+        };
+        if structure.filling_factor() > 1.0 {
+            return Err(format!(
+                "{}: filling factors add up to {:.2}; raise the pressure or the depth",
+                name,
+                structure.filling_factor()
+            )
+            .into());
+        }
+        Ok(structure)
+    }
+
+    /// Fraction of the volume filled by all components.
+    pub fn filling_factor(&self) -> f64 {
+        self.components.iter().map(|c| c.filling_factor).sum()
+    }
+
+    /// Emission-measure weighted mean temperature (K).
+    pub fn mean_temperature(&self) -> f64 {
+        let (weighted, total) = self.components.iter().fold((0.0, 0.0), |(weighted, total), c| {
+            let emission_measure = c.emission_measure(1.0);
+            (weighted + c.temperature * emission_measure, total + emission_measure)
+        });
+        weighted / total
+    }
+}
+
+/// Parses a DEM file: '#' comments, `pressure <Pa>` and `depth <Mm>` lines,
+/// then log10 T and DEM (cm^-5 K^-1) columns.
+pub(crate) fn parse_thermal_structure(name: &str, text: &str) -> Result<ThermalStructure, Box<dyn Error>> {
+    let (mut pressure, mut depth) = (None, None);
+    let mut dem = Vec::new();
+    for (number, raw) in text.lines().enumerate() {
+        let content = raw.split('#').next().unwrap_or("");
+        let fields: Vec<&str> = content.split_whitespace().collect();
+        match fields.as_slice() {
+            [] => continue,
+            ["pressure", value] => pressure = Some(value.parse::<f64>()?),
+            ["depth", value] => depth = Some(value.parse::<f64>()? * MEGAMETRE),
+            // cm^-5 K^-1 to m^-5 K^-1
+            [log_t, value] => dem.push((log_t.parse::<f64>()?, value.parse::<f64>()? * 1.0e10)),
+            _ => return Err(format!("{} line {}: cannot parse '{}'", name, number + 1, raw.trim()).into()),
+        }
+    }
+    let pressure = pressure.ok_or_else(|| format!("{}: missing 'pressure' line", name))?;
+    let depth = depth.ok_or_else(|| format!("{}: missing 'depth' line", name))?;
+    if pressure <= 0.0 || depth <= 0.0 {
+        return Err(format!("{}: pressure and depth must be positive", name).into());
+    }
+    dem.sort_by(|a: &(f64, f64), b| a.0.total_cmp(&b.0));
+    ThermalStructure::from_dem(name, &dem, pressure, depth)
+}
+
+/// Loads a DEM file from a local path; the file stem names the region.
+pub(crate) fn load_thermal_structure(path: &str) -> Result<ThermalStructure, Box<dyn Error>> {
+    let name = std::path::Path::new(path)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or(path);
+    parse_thermal_structure(name, &fs::read_to_string(path)?)
+}
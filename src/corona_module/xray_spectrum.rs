@@ -0,0 +1,204 @@
+// Thermal X-ray spectrum of a coronal plasma as seen from the Earth, and the
+// GOES soft X-ray fluxes and flare class derived from it.
+//
+// The spectrum is the free-free and free-bound continuum of an isothermal
+// plasma of a given volume emission measure, optionally with the X-ray
+// lines of the coronal line table; a multi-thermal plasma is the sum of its
+// isothermal components. The GOES fluxes are the energy fluxes in
+// the 0.5-4 and 1-8 Angstrom bands; the instrument responses are taken as
+// flat across each band.
+
+use super::coronal_lines::CoronalLine;
+use radiation::atomic_data::{AbundanceSet, ELECTRON_VOLT};
+use radiation::ionization::IonizationData;
+use radiation::numerics::trapezoid;
+use radiation::planck::{PLANCK_CONSTANT, SPEED_OF_LIGHT};
+use radiation::spectral_grid::SpectralGrid;
+use radiation::xray_continuum::ThermalContinuum;
+
+// GOES XRS bands (m): short 0.5-4 Angstrom, long 1-8 Angstrom
+const GOES_SHORT_BAND: (f64, f64) = (0.05e-9, 0.4e-9);
+const GOES_LONG_BAND: (f64, f64) = (0.1e-9, 0.8e-9);
+// 1-8 Angstrom flux (W m^-2) at the bottom of each flare class
+const FLARE_CLASSES: [(char, f64); 5] = [
+    ('X', 1.0e-4),
+    ('M', 1.0e-5),
+    ('C', 1.0e-6),
+    ('B', 1.0e-7),
+    ('A', 1.0e-8),
+];
+const ASTRONOMICAL_UNIT: f64 = 1.495978707e11; // m
+const KILO_ELECTRON_VOLT: f64 = 1.0e3 * ELECTRON_VOLT; // J
+
+#[derive(Clone, Debug)]
+pub(crate) struct XraySpectrum {
+    pub energies: Vec<f64>,     // keV, increasing
+    pub free_free: Vec<f64>,    // W m^-2 keV^-1 at 1 AU
+    pub free_bound: Vec<f64>,   // W m^-2 keV^-1 at 1 AU
+    pub lines: Vec<(f64, f64)>, // (keV, W m^-2 at 1 AU)
+}
+
+impl XraySpectrum {
+    /// Spectrum at 1 AU of an isothermal plasma in ionization equilibrium.
+    ///
+    /// # Arguments
+    ///
+    /// * `temperature` - The electron temperature in Kelvin.
+    /// * `emission_measure` - The volume emission measure n_e n_H V in m^-3.
+    /// * `abundances` - The element abundances of the plasma.
+    /// * `ions` - Ionization data for the charge states.
+    /// * `lines` - Lines to add to the continuum; an empty slice for the
+    ///   continuum alone.
+    /// * `grid` - The spectral sampling, e.g. 1-100 keV; an empty grid gives
+    ///   an empty spectrum.
+    pub fn thermal(
+        temperature: f64,
+        emission_measure: f64,
+        abundances: &AbundanceSet,
+        ions: &IonizationData,
+        lines: &[CoronalLine],
+        grid: &SpectralGrid,
+    ) -> Self {
+        let energies = grid_energies(grid);
+        let (Some(&low), Some(&high)) = (energies.first(), energies.last()) else {
+            return Self::zero(grid);
+        };
+        let continuum = ThermalContinuum::new(temperature, abundances, ions);
+        // Emission per J into all directions, to W m^-2 keV^-1 at 1 AU
+        let dilution = emission_measure / (4.0 * std::f64::consts::PI * ASTRONOMICAL_UNIT * ASTRONOMICAL_UNIT)
+            * KILO_ELECTRON_VOLT;
+        let free_free = energies
+            .iter()
+            .map(|&energy| continuum.free_free(energy * KILO_ELECTRON_VOLT) * dilution)
+            .collect();
+        let free_bound = energies
+            .iter()
+            .map(|&energy| continuum.free_bound(energy * KILO_ELECTRON_VOLT) * dilution)
+            .collect();
+        // G(T) is per steradian: the flux at distance d is G EM / d^2
+        let lines = lines
+            .iter()
+            .map(|line| (line.photon_energy() / KILO_ELECTRON_VOLT, line))
+            .filter(|&(energy, _)| energy >= low && energy <= high)
+            .map(|(energy, line)| {
+                let flux = line.emissivity(temperature, ions, abundances) * emission_measure
+                    / (ASTRONOMICAL_UNIT * ASTRONOMICAL_UNIT);
+                (energy, flux)
+            })
+            .collect();
+        XraySpectrum {
+            energies,
+            free_free,
+            free_bound,
+            lines,
+        }
+    }
+
+    /// Spectrum with no emission on the grid, and no lines.
+    pub fn zero(grid: &SpectralGrid) -> Self {
+        let energies = grid_energies(grid);
+        XraySpectrum {
+            free_free: vec![0.0; energies.len()],
+            free_bound: vec![0.0; energies.len()],
+            energies,
+            lines: Vec::new(),
+        }
+    }
+
+    /// Spectrum at 1 AU of a multi-thermal plasma: the sum of the isothermal
+    /// spectra of (temperature in K, volume emission measure in m^-3)
+    /// components, otherwise as `thermal`.
+    pub fn multi_thermal(
+        emission_measures: &[(f64, f64)],
+        abundances: &AbundanceSet,
+        ions: &IonizationData,
+        lines: &[CoronalLine],
+        grid: &SpectralGrid,
+    ) -> Self {
+        let mut spectra = emission_measures.iter().map(|&(temperature, emission_measure)| {
+            Self::thermal(temperature, emission_measure, abundances, ions, lines, grid)
+        });
+        let Some(mut total) = spectra.next() else {
+            return Self::zero(grid);
+        };
+        // Same grid and lines throughout: add point by point
+        for spectrum in spectra {
+            for (sum, value) in total.free_free.iter_mut().zip(&spectrum.free_free) {
+                *sum += value;
+            }
+            for (sum, value) in total.free_bound.iter_mut().zip(&spectrum.free_bound) {
+                *sum += value;
+            }
+            for (sum, &(_, flux)) in total.lines.iter_mut().zip(&spectrum.lines) {
+                sum.1 += flux;
+            }
+        }
+        total
+    }
+
+    /// Total continuum (W m^-2 keV^-1) at each energy (keV).
+    pub fn continuum(&self) -> Vec<(f64, f64)> {
+        self.energies
+            .iter()
+            .zip(self.free_free.iter().zip(&self.free_bound))
+            .map(|(&energy, (ff, fb))| (energy, ff + fb))
+            .collect()
+    }
+
+    /// Energy flux (W m^-2) at 1 AU between two wavelengths (m), continuum
+    /// and lines; the grid must cover the band. A grid of fewer than two
+    /// points has no continuum to integrate and gives the lines alone.
+    pub fn band_flux(&self, min_wavelength: f64, max_wavelength: f64) -> f64 {
+        let to_kev = |wavelength: f64| PLANCK_CONSTANT * SPEED_OF_LIGHT / wavelength / KILO_ELECTRON_VOLT;
+        let (low, high) = (to_kev(max_wavelength), to_kev(min_wavelength));
+        let lines: f64 = self
+            .lines
+            .iter()
+            .filter(|&&(energy, _)| energy >= low && energy <= high)
+            .map(|&(_, flux)| flux)
+            .sum();
+        let continuum = self.continuum();
+        if continuum.len() < 2 {
+            return lines;
+        }
+        let interpolate = |energy: f64| -> f64 {
+            let index = continuum
+                .partition_point(|&(e, _)| e < energy)
+                .clamp(1, continuum.len() - 1);
+            let ((e0, f0), (e1, f1)) = (continuum[index - 1], continuum[index]);
+            f0 + (f1 - f0) * (energy - e0) / (e1 - e0)
+        };
+        let mut points = vec![(low, interpolate(low))];
+        points.extend(continuum.iter().filter(|&&(e, _)| e > low && e < high));
+        points.push((high, interpolate(high)));
+        trapezoid(&points) + lines
+    }
+
+    /// GOES fluxes (W m^-2) in the short 0.5-4 and long 1-8 Angstrom bands.
+    pub fn goes_fluxes(&self) -> (f64, f64) {
+        (
+            self.band_flux(GOES_SHORT_BAND.0, GOES_SHORT_BAND.1),
+            self.band_flux(GOES_LONG_BAND.0, GOES_LONG_BAND.1),
+        )
+    }
+}
+
+// Photon energies (keV) of the grid points, increasing
+fn grid_energies(grid: &SpectralGrid) -> Vec<f64> {
+    let mut energies: Vec<f64> = grid
+        .wavelengths()
+        .iter()
+        .map(|wavelength| PLANCK_CONSTANT * SPEED_OF_LIGHT / wavelength / KILO_ELECTRON_VOLT)
+        .collect();
+    energies.sort_by(f64::total_cmp);
+    energies
+}
+
+/// Flare class of a GOES 1-8 Angstrom flux (W m^-2), e.g. "M2.3".
+pub(crate) fn flare_class(long_flux: f64) -> String {
+    let &(letter, base) = FLARE_CLASSES
+        .iter()
+        .find(|&&(_, base)| long_flux >= base)
+        .unwrap_or(&FLARE_CLASSES[FLARE_CLASSES.len() - 1]);
+    format!("{}{:.1}", letter, long_flux / base)
+}
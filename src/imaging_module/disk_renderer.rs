@@ -0,0 +1,281 @@
+// Full-disk images of the Sun at any wavelength, written as PNG files.
+//
+// Every pixel is a ray toward the observer. Rays that hit the disk start
+// from the limb-darkened photospheric intensity, optionally with sunspots,
+// faculae and granulation, and then cross the chromosphere and corona; rays
+// beyond the limb see only the emission of those two shells. The image is
+// sampled on a sky grid with a given pixel scale and rotated so that solar
+// north lies at the requested position angle.
+
+// Only part of each included layer is used here; the layers' own binaries
+// check the rest for dead code
+#[path = "../photosphere_module/granulation.rs"]
+#[allow(dead_code)]
+mod granulation;
+#[path = "../chromosphere_module/chromosphere.rs"]
+#[allow(dead_code)]
+mod chromosphere;
+#[path = "../corona_module/corona.rs"]
+#[allow(dead_code)]
+mod corona;
+
+use chromosphere::Chromosphere;
+use corona::thermal_structure::{CoronalRegion, ThermalStructure};
+use corona::Corona;
+use granulation::photosphere::{Facula, Photosphere, Sunspot};
+use granulation::{GranulationFrame, GranulationModel};
+use plotters::prelude::*;
+use radiation::irradiance::{self, SphericalShell, ASTRONOMICAL_UNIT};
+
+const ARCSEC: f64 = std::f64::consts::PI / (180.0 * 3600.0); // radians
+
+// What to draw and how to sample it
+#[derive(Clone, Debug)]
+struct RenderSettings {
+    wavelength: f64,     // m
+    pixels: u32,         // image width and height
+    pixel_scale: f64,    // arcsec per pixel
+    position_angle: f64, // radians, solar north counterclockwise from image up
+    distance: f64,       // m, observer to Sun centre
+    sunspots: bool,
+    granulation: bool,
+    off_limb: bool,
+}
+
+impl RenderSettings {
+    // Full-disk view from 1 AU with north up
+    fn full_disk(wavelength: f64) -> Self {
+        RenderSettings {
+            wavelength,
+            pixels: 512,
+            pixel_scale: 4.8,
+            position_angle: 0.0,
+            distance: ASTRONOMICAL_UNIT,
+            sunspots: true,
+            granulation: false,
+            off_limb: true,
+        }
+    }
+}
+
+struct DiskRenderer {
+    photosphere: Photosphere,
+    chromosphere: Chromosphere,
+    corona: Corona,
+    granulation: Option<(GranulationFrame, f64)>, // frame and the side of its patch in m
+}
+
+impl DiskRenderer {
+    // Intensity image in W m^-2 sr^-1 m^-1, indexed [row][column] from the top left
+    fn render(&self, settings: &RenderSettings) -> Vec<Vec<f64>> {
+        let wavelength = settings.wavelength;
+        let radius = self.photosphere.radius;
+        let angular_radius = (radius / settings.distance).asin();
+        let disk = self.photosphere.disk_intensity(wavelength);
+        let shells = if settings.off_limb { self.shells(wavelength) } else { Vec::new() };
+        let (sin_pa, cos_pa) = settings.position_angle.sin_cos();
+        let centre = 0.5 * settings.pixels as f64;
+
+        (0..settings.pixels)
+            .map(|row| {
+                (0..settings.pixels)
+                    .map(|column| {
+                        // Sky offsets in solar radii, east to the left and up positive
+                        let sky_x = (column as f64 + 0.5 - centre) * settings.pixel_scale * ARCSEC / angular_radius;
+                        let sky_y = (centre - row as f64 - 0.5) * settings.pixel_scale * ARCSEC / angular_radius;
+                        // Rotate into solar coordinates (x west, y north)
+                        let x = sky_x * cos_pa + sky_y * sin_pa;
+                        let y = -sky_x * sin_pa + sky_y * cos_pa;
+
+                        let impact_parameter = (x * x + y * y).sqrt() * radius;
+                        let surface = match disk.intensity(x, y) {
+                            Some(intensity) if settings.sunspots => intensity,
+                            Some(_) => disk.quiet_intensity((1.0 - x * x - y * y).sqrt()),
+                            None => 0.0,
+                        };
+                        let surface = if settings.granulation { surface * self.granulation_factor(x, y) } else { surface };
+                        irradiance::ray_intensity(radius, &|_mu: f64| surface, &shells, impact_parameter)
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    // Chromosphere and corona as spherical shells at one wavelength
+    fn shells(&self, wavelength: f64) -> Vec<SphericalShell> {
+        vec![
+            SphericalShell {
+                inner_radius: self.photosphere.radius,
+                outer_radius: self.chromosphere.radius,
+                optical_depth: self.chromosphere.calculate_optical_depth(self.photosphere.radius, wavelength),
+                source_function: self.chromosphere.source_function(wavelength),
+            },
+            SphericalShell {
+                inner_radius: self.chromosphere.radius,
+                outer_radius: self.corona.radius,
+                optical_depth: self.corona.calculate_optical_depth(self.chromosphere.radius, wavelength),
+                source_function: self.corona.source_function(wavelength),
+            },
+        ]
+    }
+
+    // Relative granular intensity at a disk position, wrapping the periodic
+    // granulation patch over the heliographic surface; the patch is made as
+    // large as the close-up so that it does not repeat within one image
+    fn granulation_factor(&self, x: f64, y: f64) -> f64 {
+        let Some((frame, field_size)) = &self.granulation else {
+            return 1.0;
+        };
+        let r2 = x * x + y * y;
+        if r2 >= 1.0 {
+            return 1.0;
+        }
+        let mu = (1.0 - r2).sqrt();
+        let latitude = y.asin();
+        let longitude = x.atan2(mu);
+        let pixels = frame.intensity.len();
+        let cell = |distance: f64| {
+            let fraction = (distance / field_size).rem_euclid(1.0);
+            ((fraction * pixels as f64) as usize).min(pixels - 1)
+        };
+        let row = cell(self.photosphere.radius * latitude);
+        let column = cell(self.photosphere.radius * longitude * latitude.cos());
+        frame.intensity[row][column]
+    }
+}
+
+// Mapping of intensities to display levels in [0, 1]
+#[derive(Clone, Copy, Debug)]
+enum Stretch {
+    // asinh of the intensity relative to the image peak, so that faint
+    // emission beyond the limb stays visible next to the disk
+    Asinh,
+    // Linear between the faintest and brightest pixel, for close-ups where
+    // the contrast is only a few percent
+    Linear,
+}
+
+/// Writes an intensity image as a PNG with a heat color map.
+fn write_png(path: &str, image: &[Vec<f64>], stretch: Stretch) -> Result<(), Box<dyn std::error::Error>> {
+    let size = image.len() as u32;
+    let root = BitMapBackend::new(path, (size, size)).into_drawing_area();
+    root.fill(&BLACK)?;
+    let peak = image.iter().flatten().fold(f64::MIN_POSITIVE, |peak, &value| peak.max(value));
+    let floor = image.iter().flatten().fold(peak, |floor, &value| floor.min(value));
+    let level = |value: f64| match stretch {
+        Stretch::Asinh => (value / peak / STRETCH_SOFTENING).asinh() / (1.0 / STRETCH_SOFTENING).asinh(),
+        Stretch::Linear if peak > floor => (value - floor) / (peak - floor),
+        Stretch::Linear => 1.0,
+    };
+    for (row, line) in image.iter().enumerate() {
+        for (column, &value) in line.iter().enumerate() {
+            let level = level(value.max(0.0)).clamp(0.0, 1.0);
+            root.draw_pixel((column as i32, row as i32), &heat_color(level))?;
+        }
+    }
+    root.present()?;
+    Ok(())
+}
+
+// Black -> red -> orange -> yellow -> white
+fn heat_color(level: f64) -> RGBColor {
+    let channel = |start: f64| (((level - start) * 3.0).clamp(0.0, 1.0) * 255.0) as u8;
+    RGBColor(channel(0.0), channel(1.0 / 3.0), channel(2.0 / 3.0))
+}
+
+// Quiet-Sun properties of the outer layers; the photosphere is the
+// photosphere program's own quiet-Sun model
+const CHROMOSPHERE_RADIUS: f64 = 6.977e8; // meters, 2000 km above the photosphere
+const CHROMOSPHERE_TEMPERATURE: f64 = 6000.0; // Kelvin
+const CHROMOSPHERE_DENSITY: f64 = 1.0e-7; // kg/m^3 at the base
+const CHROMOSPHERE_ENERGY_GENERATION: f64 = 1.0e25; // W/m^3
+const CORONA_RADIUS: f64 = 1.4e9; // meters, about 2 solar radii
+const CORONA_TEMPERATURE: f64 = 1.5e6; // Kelvin
+const CORONA_DENSITY: f64 = 1.0e-12; // kg/m^3
+const CORONA_ENERGY_GENERATION: f64 = 1.0e24; // W/m^3
+const CORONA_MAGNETIC_FIELD: f64 = 1.0e-4; // Tesla
+
+// Granulation patch covering the close-up, with margin for foreshortening
+const GRANULATION_MARGIN: f64 = 1.1;
+const GRANULATION_SEED: u64 = 2024;
+// Softening of the asinh display stretch, as a fraction of the peak intensity
+const STRETCH_SOFTENING: f64 = 0.02;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Optional arguments: wavelength (nm), pixel scale (arcsec), position angle (deg), size (px)
+    let arguments: Vec<f64> = std::env::args().skip(1).filter_map(|a| a.parse().ok()).collect();
+    let mut settings = RenderSettings::full_disk(arguments.first().copied().unwrap_or(500.0) * 1.0e-9);
+    if let Some(&scale) = arguments.get(1) {
+        settings.pixel_scale = scale;
+    }
+    if let Some(&angle) = arguments.get(2) {
+        settings.position_angle = angle.to_radians();
+    }
+    if let Some(&size) = arguments.get(3) {
+        settings.pixels = size as u32;
+    }
+
+    let mut photosphere = Photosphere::quiet_sun();
+    photosphere.add_sunspot(Sunspot::new(15.0, -25.0, 800.0));
+    photosphere.add_sunspot(Sunspot::new(18.0, -15.0, 300.0));
+    photosphere.add_sunspot(Sunspot::new(-12.0, 50.0, 500.0));
+    for (latitude, longitude) in [(16.0, -35.0), (20.0, -10.0), (-10.0, 62.0), (-15.0, 40.0), (8.0, -70.0)] {
+        photosphere.add_facula(Facula::new(latitude, longitude, 1500.0));
+    }
+    // A close-up with granulation at the same wavelength
+    let mut close_up = settings.clone();
+    close_up.pixel_scale = 0.25;
+    close_up.granulation = true;
+    close_up.off_limb = false;
+    let field_size =
+        GRANULATION_MARGIN * close_up.pixels as f64 * close_up.pixel_scale * ARCSEC * close_up.distance;
+    let granulation = GranulationModel::new(
+        &photosphere,
+        field_size,
+        (GRANULATION_MARGIN * close_up.pixels as f64).ceil() as usize,
+        settings.wavelength,
+        GRANULATION_SEED,
+    );
+    let renderer = DiskRenderer {
+        photosphere,
+        chromosphere: Chromosphere {
+            radius: CHROMOSPHERE_RADIUS,
+            temperature: CHROMOSPHERE_TEMPERATURE,
+            density: CHROMOSPHERE_DENSITY,
+            energy_generation: CHROMOSPHERE_ENERGY_GENERATION,
+            stratification: None,
+            jets: Vec::new(),
+        },
+        corona: Corona {
+            radius: CORONA_RADIUS,
+            temperature: CORONA_TEMPERATURE,
+            density: CORONA_DENSITY,
+            energy_generation: CORONA_ENERGY_GENERATION,
+            magnetic_field_strength: CORONA_MAGNETIC_FIELD,
+            // The quiet-Sun DEM, rather than the one temperature and density
+            // above, sets the coronal shell's optical depth and source function
+            thermal_structure: Some(ThermalStructure::bundled(CoronalRegion::QuietSun)),
+        },
+        granulation: Some((granulation.frame(), field_size)),
+    };
+
+    // The requested view, then the close-up
+    let nanometers = settings.wavelength * 1.0e9;
+    for (name, view, stretch) in [("sun", &settings, Stretch::Asinh), ("sun_closeup", &close_up, Stretch::Linear)] {
+        let image = renderer.render(view);
+        let reference = renderer.photosphere.disk_intensity(view.wavelength).quiet_intensity(1.0);
+        let path = format!("{}_{:.0}nm.png", name, nanometers);
+        write_png(&path, &image, stretch)?;
+        let peak = image.iter().flatten().fold(0.0_f64, |peak, &value| peak.max(value));
+        println!(
+            "Wrote {} ({} px at {:.2} arcsec/px, position angle {:.0} deg): peak {:.3e} W/m^2/sr/m, {:.3} of disk centre",
+            path,
+            view.pixels,
+            view.pixel_scale,
+            view.position_angle.to_degrees(),
+            peak,
+            peak / reference
+        );
+    }
+    Ok(())
+}
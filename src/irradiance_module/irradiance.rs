@@ -0,0 +1,208 @@
+// Total and spectral solar irradiance from the emergent radiation of the
+// photosphere, chromosphere and corona.
+//
+// The photosphere supplies the limb-darkened surface intensity; the
+// chromosphere and corona are treated as homogeneous spherical shells at each
+// wavelength, with their own optical thickness and source function, so both
+// their attenuation of the disk and their emission beyond the limb are
+// included.
+
+// Only part of each included layer is used here; the layers' own binaries
+// check the rest for dead code
+#[path = "../photosphere_module/photosphere.rs"]
+#[allow(dead_code)]
+mod photosphere;
+#[path = "../chromosphere_module/chromosphere.rs"]
+#[allow(dead_code)]
+mod chromosphere;
+#[path = "../corona_module/corona.rs"]
+#[allow(dead_code)]
+mod corona;
+
+use chromosphere::Chromosphere;
+use corona::thermal_structure::{CoronalRegion, ThermalStructure};
+use corona::Corona;
+use photosphere::{Facula, Photosphere, Sunspot};
+use radiation::irradiance::{self, SphericalShell, ASTRONOMICAL_UNIT};
+use radiation::photometry::{self, Filter};
+use radiation::spectral_grid::{SpectralGrid, SpectralUnit};
+
+// The Sun and its outer layers, seen from a given distance
+struct SolarIrradiance {
+    photosphere: Photosphere,
+    chromosphere: Chromosphere,
+    corona: Corona,
+}
+
+impl SolarIrradiance {
+    // Chromosphere and corona as spherical shells at one wavelength
+    fn shells(&self, wavelength: f64) -> [SphericalShell; 2] {
+        [
+            SphericalShell {
+                inner_radius: self.photosphere.radius,
+                outer_radius: self.chromosphere.radius,
+                optical_depth: self.chromosphere.calculate_optical_depth(self.photosphere.radius, wavelength),
+                source_function: self.chromosphere.source_function(wavelength),
+            },
+            SphericalShell {
+                inner_radius: self.chromosphere.radius,
+                outer_radius: self.corona.radius,
+                optical_depth: self.corona.calculate_optical_depth(self.chromosphere.radius, wavelength),
+                source_function: self.corona.source_function(wavelength),
+            },
+        ]
+    }
+
+    // Spectral irradiance (W m^-2 m^-1) at `distance` meters, all layers
+    // included; sunspots and faculae change the photospheric contribution
+    fn spectral_irradiance(&self, grid: &SpectralGrid, distance: f64) -> Vec<(f64, f64)> {
+        let dilution = (self.photosphere.radius / distance).powi(2);
+        grid.map_wavelengths(|wavelength| {
+            let surface = |mu: f64| self.photosphere.emergent_intensity(wavelength, mu);
+            let features = self.photosphere.disk_intensity(wavelength).flux_change();
+            irradiance::disk_integrated_irradiance(self.photosphere.radius, &surface, &self.shells(wavelength), distance)
+                + features * dilution
+        })
+    }
+
+    // Spectral irradiance of the bare photosphere, without the outer layers
+    fn photospheric_irradiance(&self, grid: &SpectralGrid, distance: f64) -> Vec<(f64, f64)> {
+        grid.map_wavelengths(|wavelength| {
+            let surface = |mu: f64| self.photosphere.emergent_intensity(wavelength, mu);
+            irradiance::disk_integrated_irradiance(self.photosphere.radius, &surface, &[], distance)
+        })
+    }
+}
+
+// Quiet-Sun properties of the outer layers; the photosphere is the
+// photosphere program's own quiet-Sun model
+const CHROMOSPHERE_RADIUS: f64 = 6.977e8; // meters, 2000 km above the photosphere
+const CHROMOSPHERE_TEMPERATURE: f64 = 6000.0; // Kelvin
+const CHROMOSPHERE_DENSITY: f64 = 1.0e-7; // kg/m^3 at the base, about 1000 km up in VAL C
+const CHROMOSPHERE_ENERGY_GENERATION: f64 = 1.0e25; // W/m^3
+const CORONA_RADIUS: f64 = 1.4e9; // meters, about 2 solar radii
+const CORONA_TEMPERATURE: f64 = 1.5e6; // Kelvin
+const CORONA_DENSITY: f64 = 1.0e-12; // kg/m^3, n_e ~ 5e14 m^-3
+const CORONA_ENERGY_GENERATION: f64 = 1.0e24; // W/m^3
+const CORONA_MAGNETIC_FIELD: f64 = 1.0e-4; // Tesla
+
+// Spectral range from the EUV to the far infrared
+const MIN_WAVELENGTH: f64 = 10.0e-9; // meters
+const MAX_WAVELENGTH: f64 = 100.0e-6; // meters
+const SPECTRAL_POINTS: usize = 600;
+
+fn main() {
+    let sun = SolarIrradiance {
+        photosphere: Photosphere::quiet_sun(),
+        chromosphere: Chromosphere {
+            radius: CHROMOSPHERE_RADIUS,
+            temperature: CHROMOSPHERE_TEMPERATURE,
+            density: CHROMOSPHERE_DENSITY,
+            energy_generation: CHROMOSPHERE_ENERGY_GENERATION,
+            stratification: None,
+            jets: Vec::new(),
+        },
+        corona: Corona {
+            radius: CORONA_RADIUS,
+            temperature: CORONA_TEMPERATURE,
+            density: CORONA_DENSITY,
+            energy_generation: CORONA_ENERGY_GENERATION,
+            magnetic_field_strength: CORONA_MAGNETIC_FIELD,
+            // The quiet-Sun DEM, rather than the one temperature and density
+            // above, sets the coronal shell's optical depth and source function
+            thermal_structure: Some(ThermalStructure::bundled(CoronalRegion::QuietSun)),
+        },
+    };
+
+    let grid = SpectralGrid::logarithmic(SpectralUnit::Wavelength, MIN_WAVELENGTH, MAX_WAVELENGTH, SPECTRAL_POINTS);
+    let spectrum = sun.spectral_irradiance(&grid, ASTRONOMICAL_UNIT);
+    let photosphere_only = sun.photospheric_irradiance(&grid, ASTRONOMICAL_UNIT);
+
+    let tsi = irradiance::total_irradiance(&spectrum);
+    println!("Total Solar Irradiance at 1 AU: {:.2} W/m^2", tsi);
+    println!(
+        "Photosphere Alone: {:.2} W/m^2 (chromosphere and corona add {:+.3} W/m^2)",
+        irradiance::total_irradiance(&photosphere_only),
+        tsi - irradiance::total_irradiance(&photosphere_only)
+    );
+
+    // Spectral irradiance in broad bands
+    let bands = [
+        ("EUV", 10.0e-9, 121.0e-9),
+        ("FUV", 121.0e-9, 200.0e-9),
+        ("MUV/NUV", 200.0e-9, 400.0e-9),
+        ("Visible", 400.0e-9, 700.0e-9),
+        ("Near IR", 700.0e-9, 5.0e-6),
+        ("Far IR", 5.0e-6, 100.0e-6),
+    ];
+    for (name, start, end) in bands {
+        let band = irradiance::band_irradiance(&spectrum, start, end);
+        println!(
+            "{:>8} ({:>7.1}-{:>8.1} nm): {:.4e} W/m^2 ({:.2}% of TSI)",
+            name,
+            start * 1.0e9,
+            end * 1.0e9,
+            band,
+            100.0 * band / tsi
+        );
+    }
+
+    // Spectral irradiance at a few reference wavelengths, in W m^-2 nm^-1
+    for wavelength in [30.4e-9, 121.6e-9, 250.0e-9, 500.0e-9, 1.0e-6, 10.0e-6] {
+        let (_, value) = spectrum
+            .iter()
+            .copied()
+            .min_by(|a, b| (a.0 - wavelength).abs().total_cmp(&(b.0 - wavelength).abs()))
+            .unwrap_or((wavelength, 0.0));
+        println!("SSI({:.1} nm): {:.4e} W/m^2/nm", wavelength * 1.0e9, value * 1.0e-9);
+    }
+
+    // Apparent magnitudes of the Sun through the bundled Johnson and
+    // Gaia-like bands, and through filter files given on the command line
+    let mut filters: Vec<Filter> = ["U", "B", "V", "G", "BP", "RP"]
+        .iter()
+        .filter_map(|band| photometry::filter(band))
+        .collect();
+    for path in std::env::args().skip(1) {
+        match photometry::load_filter(&path) {
+            Ok(filter) => filters.push(filter),
+            Err(error) => println!("Could not load filter {}: {}", path, error),
+        }
+    }
+    for filter in &filters {
+        let magnitude = filter.magnitude(&spectrum);
+        println!(
+            "Apparent Magnitude {}: {:.3} (chromosphere and corona add {:+.1e} mag)",
+            filter.name,
+            magnitude,
+            magnitude - filter.magnitude(&photosphere_only)
+        );
+    }
+
+    // Solar-cycle variability: the same Sun with an active region on the disk
+    let mut active = sun;
+    active.photosphere.add_sunspot(Sunspot::new(15.0, -10.0, 500.0));
+    active.photosphere.add_sunspot(Sunspot::new(-12.0, 20.0, 300.0));
+    for (latitude, longitude) in [(16.0, -20.0), (12.0, 5.0), (-14.0, 30.0), (-10.0, 10.0)] {
+        active.photosphere.add_facula(Facula::new(latitude, longitude, 1000.0));
+    }
+    let active_spectrum = active.spectral_irradiance(&grid, ASTRONOMICAL_UNIT);
+    let active_tsi = irradiance::total_irradiance(&active_spectrum);
+    println!(
+        "TSI with Active Region: {:.2} W/m^2 ({:+.3} W/m^2, {:+.0} ppm)",
+        active_tsi,
+        active_tsi - tsi,
+        1.0e6 * (active_tsi / tsi - 1.0)
+    );
+
+    // The same spectrum at other heliocentric distances
+    for (planet, distance) in [("Mercury", 0.387), ("Venus", 0.723), ("Mars", 1.524), ("Jupiter", 5.203)] {
+        let scaled = irradiance::scale_to_distance(&spectrum, ASTRONOMICAL_UNIT, distance * ASTRONOMICAL_UNIT);
+        println!(
+            "Total Irradiance at {} ({} AU): {:.2} W/m^2",
+            planet,
+            distance,
+            irradiance::total_irradiance(&scaled)
+        );
+    }
+}
@@ -0,0 +1,303 @@
+// Sunspots and faculae on the photosphere.
+//
+// Features sit at heliographic positions seen from an observer in the solar
+// equatorial plane, with longitude measured from the central meridian. A
+// sunspot is a dark umbra inside a penumbra, each radiating like a gray
+// atmosphere of lower effective temperature; the umbra is depressed by the
+// Wilson depression, which shifts it toward disk centre near the limb. A
+// facula is a small bright region whose contrast grows toward the limb, as
+// its hot walls come into view.
+
+use radiation::planck;
+
+const VACUUM_PERMEABILITY: f64 = 1.25663706212e-6; // N/A^2
+// Micro-hemispheres (MSH): area unit of one millionth of the visible hemisphere
+const MICRO_HEMISPHERE: f64 = 1.0e-6 * 2.0 * std::f64::consts::PI;
+// Number of mu points tabulating each center-to-limb profile
+const PROFILE_POINTS: usize = 41;
+
+#[derive(Clone, Debug)]
+pub(crate) struct Sunspot {
+    pub latitude: f64,             // radians
+    pub longitude: f64,            // radians from the central meridian
+    pub area: f64,                 // MSH, umbra plus penumbra
+    pub umbra_fraction: f64,       // umbral share of the area
+    pub umbra_temperature: f64,    // K, effective
+    pub penumbra_temperature: f64, // K, effective
+    pub field_strength: f64,       // T, at the umbral centre
+    pub wilson_depression: f64,    // m
+}
+
+impl Sunspot {
+    /// A sunspot with typical umbral and penumbral properties.
+    ///
+    /// # Arguments
+    ///
+    /// * `latitude` - Heliographic latitude in degrees.
+    /// * `longitude` - Longitude from the central meridian in degrees.
+    /// * `area` - Total area in millionths of the solar hemisphere.
+    pub fn new(latitude: f64, longitude: f64, area: f64) -> Self {
+        Sunspot {
+            latitude: latitude.to_radians(),
+            longitude: longitude.to_radians(),
+            area,
+            umbra_fraction: 0.2,
+            umbra_temperature: 4000.0,
+            penumbra_temperature: 5400.0,
+            field_strength: 0.3,
+            wilson_depression: 5.0e5,
+        }
+    }
+
+    // Angular radius (radians) of the whole spot and of its umbra
+    fn angular_radius(&self) -> f64 {
+        cap_angular_radius(self.area)
+    }
+
+    fn umbra_angular_radius(&self) -> f64 {
+        cap_angular_radius(self.area * self.umbra_fraction)
+    }
+
+    /// Magnetic flux (Wb) through the umbra.
+    pub fn umbral_flux(&self, radius: f64) -> f64 {
+        self.field_strength * self.area * self.umbra_fraction * MICRO_HEMISPHERE * radius * radius
+    }
+
+    /// Magnetic pressure B^2 / 2 mu0 (Pa) that balances the gas pressure deficit of the umbra.
+    pub fn magnetic_pressure(&self) -> f64 {
+        self.field_strength * self.field_strength / (2.0 * VACUUM_PERMEABILITY)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Facula {
+    pub latitude: f64,           // radians
+    pub longitude: f64,          // radians from the central meridian
+    pub area: f64,               // MSH
+    pub temperature_excess: f64, // K, seen at the extreme limb
+}
+
+impl Facula {
+    /// A facular region with typical limb contrast.
+    ///
+    /// # Arguments
+    ///
+    /// * `latitude` - Heliographic latitude in degrees.
+    /// * `longitude` - Longitude from the central meridian in degrees.
+    /// * `area` - Area in millionths of the solar hemisphere.
+    pub fn new(latitude: f64, longitude: f64, area: f64) -> Self {
+        Facula {
+            latitude: latitude.to_radians(),
+            longitude: longitude.to_radians(),
+            area,
+            temperature_excess: 100.0,
+        }
+    }
+
+    fn angular_radius(&self) -> f64 {
+        cap_angular_radius(self.area)
+    }
+}
+
+/// Position of a heliographic point on the disk: (x, y) in units of the
+/// solar radius, with x toward the west limb and y north, and mu.
+pub(crate) fn disk_position(latitude: f64, longitude: f64) -> (f64, f64, f64) {
+    let (sin_lat, cos_lat) = latitude.sin_cos();
+    let (sin_lon, cos_lon) = longitude.sin_cos();
+    (cos_lat * sin_lon, sin_lat, cos_lat * cos_lon)
+}
+
+// Heliographic latitude and longitude of a point on the visible disk
+fn heliographic_position(x: f64, y: f64) -> (f64, f64) {
+    let mu = (1.0 - x * x - y * y).max(0.0).sqrt();
+    (y.clamp(-1.0, 1.0).asin(), x.atan2(mu))
+}
+
+// Great-circle angle between two heliographic points
+fn angular_separation(first: (f64, f64), second: (f64, f64)) -> f64 {
+    let (lat1, lon1) = first;
+    let (lat2, lon2) = second;
+    let cosine = lat1.sin() * lat2.sin() + lat1.cos() * lat2.cos() * (lon1 - lon2).cos();
+    cosine.clamp(-1.0, 1.0).acos()
+}
+
+// Angular radius of a spherical cap covering `area` MSH: a cap of angular
+// radius theta covers 2 pi R^2 (1 - cos theta), and 1 MSH is 1e-6 of the
+// hemisphere's 2 pi R^2
+fn cap_angular_radius(area: f64) -> f64 {
+    (1.0 - area * 1.0e-6).clamp(-1.0, 1.0).acos()
+}
+
+// Center-to-limb intensity tabulated on a uniform mu grid
+#[derive(Clone, Debug)]
+pub(crate) struct LimbProfile {
+    intensity: Vec<f64>, // at mu = i / (PROFILE_POINTS - 1)
+}
+
+impl LimbProfile {
+    pub fn new<F: Fn(f64) -> f64>(intensity: F) -> Self {
+        LimbProfile {
+            intensity: (0..PROFILE_POINTS)
+                .map(|i| intensity((i as f64 / (PROFILE_POINTS - 1) as f64).max(1.0e-3)))
+                .collect(),
+        }
+    }
+
+    pub fn at(&self, mu: f64) -> f64 {
+        let position = mu.clamp(0.0, 1.0) * (PROFILE_POINTS - 1) as f64;
+        let index = (position.floor() as usize).min(PROFILE_POINTS - 2);
+        let fraction = position - index as f64;
+        self.intensity[index] * (1.0 - fraction) + self.intensity[index + 1] * fraction
+    }
+}
+
+// Surfaces with their own center-to-limb profile
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Surface {
+    Quiet,
+    Umbra(usize),    // of the sunspot with this index
+    Penumbra(usize), // of the sunspot with this index
+}
+
+// Intensity across the disk at one wavelength, quiet Sun plus features
+pub(crate) struct DiskIntensity {
+    pub wavelength: f64,
+    pub radius: f64, // m
+    quiet: LimbProfile,
+    umbra: Vec<LimbProfile>,
+    penumbra: Vec<LimbProfile>,
+    quiet_temperature: f64,
+    sunspots: Vec<Sunspot>,
+    faculae: Vec<Facula>,
+}
+
+impl DiskIntensity {
+    /// Collects the center-to-limb profiles of the quiet Sun and of each
+    /// sunspot's umbra and penumbra.
+    ///
+    /// # Arguments
+    ///
+    /// * `wavelength` - Wavelength in meters.
+    /// * `radius` - Photospheric radius in meters.
+    /// * `quiet_temperature` - Effective temperature of the quiet photosphere in K.
+    /// * `profile` - Intensity I(mu) of each surface at this wavelength.
+    pub fn new<P: Fn(Surface, f64) -> f64>(
+        wavelength: f64,
+        radius: f64,
+        quiet_temperature: f64,
+        sunspots: &[Sunspot],
+        faculae: &[Facula],
+        profile: P,
+    ) -> Self {
+        let limb_profile = |surface: Surface| LimbProfile::new(|mu| profile(surface, mu));
+        DiskIntensity {
+            wavelength,
+            radius,
+            quiet: limb_profile(Surface::Quiet),
+            umbra: (0..sunspots.len()).map(|i| limb_profile(Surface::Umbra(i))).collect(),
+            penumbra: (0..sunspots.len()).map(|i| limb_profile(Surface::Penumbra(i))).collect(),
+            quiet_temperature,
+            sunspots: sunspots.to_vec(),
+            faculae: faculae.to_vec(),
+        }
+    }
+
+    /// Quiet-Sun intensity at direction cosine `mu`.
+    pub fn quiet_intensity(&self, mu: f64) -> f64 {
+        self.quiet.at(mu)
+    }
+
+    /// Relative facular contrast at `mu`: the hot walls add a temperature
+    /// excess that grows from nothing at disk centre to the full excess at the limb.
+    pub fn facular_contrast(&self, facula: &Facula, mu: f64) -> f64 {
+        let temperature = self.quiet_temperature + facula.temperature_excess * (1.0 - mu);
+        planck::spectral_radiance_wavelength(self.wavelength, temperature)
+            / planck::spectral_radiance_wavelength(self.wavelength, self.quiet_temperature)
+            - 1.0
+    }
+
+    /// Intensity at disk position (x, y) in solar radii, or `None` off the disk.
+    pub fn intensity(&self, x: f64, y: f64) -> Option<f64> {
+        let r2 = x * x + y * y;
+        if r2 >= 1.0 {
+            return None;
+        }
+        let mu = (1.0 - r2).sqrt();
+        let point = heliographic_position(x, y);
+
+        for (i, spot) in self.sunspots.iter().enumerate() {
+            let centre = (spot.latitude, spot.longitude);
+            if angular_separation(point, centre) > spot.angular_radius() {
+                continue;
+            }
+            // The umbra lies a depth W below the surface, so at heliocentric
+            // angle theta it appears W sin(theta) closer to disk centre
+            let (sx, sy, _) = disk_position(spot.latitude, spot.longitude);
+            let scale = 1.0 - spot.wilson_depression / self.radius;
+            let umbra_centre = heliographic_position(sx * scale, sy * scale);
+            let profile = if angular_separation(point, umbra_centre) <= spot.umbra_angular_radius() {
+                &self.umbra[i]
+            } else {
+                &self.penumbra[i]
+            };
+            return Some(profile.at(mu));
+        }
+
+        let quiet = self.quiet.at(mu);
+        for facula in &self.faculae {
+            if angular_separation(point, (facula.latitude, facula.longitude)) <= facula.angular_radius() {
+                return Some(quiet * (1.0 + self.facular_contrast(facula, mu)));
+            }
+        }
+        Some(quiet)
+    }
+
+    /// Intensity image of the disk on a square grid of `pixels` covering
+    /// [-1, 1] solar radii; off-disk pixels are zero.
+    pub fn image(&self, pixels: usize) -> Vec<Vec<f64>> {
+        (0..pixels)
+            .map(|row| {
+                let y = 1.0 - 2.0 * (row as f64 + 0.5) / pixels as f64;
+                (0..pixels)
+                    .map(|column| {
+                        let x = -1.0 + 2.0 * (column as f64 + 0.5) / pixels as f64;
+                        self.intensity(x, y).unwrap_or(0.0)
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Change of the surface flux F_lambda (W m^-2 m^-1) caused by the
+    /// features: each adds (A / R^2) mu (I_feature - I_quiet) while on the
+    /// visible hemisphere.
+    pub fn flux_change(&self) -> f64 {
+        let spots: f64 = self
+            .sunspots
+            .iter()
+            .enumerate()
+            .map(|(i, spot)| {
+                let (_, _, mu) = disk_position(spot.latitude, spot.longitude);
+                if mu <= 0.0 {
+                    return 0.0;
+                }
+                let quiet = self.quiet.at(mu);
+                let umbra = spot.area * spot.umbra_fraction * (self.umbra[i].at(mu) - quiet);
+                let penumbra = spot.area * (1.0 - spot.umbra_fraction) * (self.penumbra[i].at(mu) - quiet);
+                MICRO_HEMISPHERE * mu * (umbra + penumbra)
+            })
+            .sum();
+        let faculae: f64 = self
+            .faculae
+            .iter()
+            .map(|facula| {
+                let (_, _, mu) = disk_position(facula.latitude, facula.longitude);
+                if mu <= 0.0 {
+                    return 0.0;
+                }
+                MICRO_HEMISPHERE * facula.area * mu * self.quiet.at(mu) * self.facular_contrast(facula, mu)
+            })
+            .sum();
+        spots + faculae
+    }
+}
@@ -0,0 +1,237 @@
+// Gray plane-parallel model atmosphere for the photosphere.
+//
+// The temperature follows the gray-atmosphere relation
+//
+//     T^4(tau) = 3/4 T_eff^4 (tau + q(tau))
+//
+// with either the Eddington approximation q = 2/3 or the Hopf function, and
+// the pressure follows hydrostatic equilibrium dP/dtau = g / kappa. The
+// opacity is the H- dominated mean opacity of the solar photosphere plus
+// electron scattering, so the pressure has to be integrated numerically.
+// The T^9 law of the H- opacity only holds up to about 6000 K; in the hotter
+// layers below tau = 1 it is held at that temperature and electron
+// scattering off the thermally ionized gas is added.
+
+use radiation::atomic_data;
+use radiation::planck;
+use radiation::transfer::{self, RadiatingLayer};
+
+const BOLTZMANN_CONSTANT: f64 = 1.380649e-23; // J/K
+const ATOMIC_MASS_UNIT: f64 = 1.66053907e-27; // kg
+
+// Mean molecular weight of the (mostly neutral) photospheric gas
+const MEAN_MOLECULAR_WEIGHT: f64 = 1.3;
+// Metal mass fraction scaling the H- opacity
+const METALLICITY: f64 = 0.02;
+const HYDROGEN_FRACTION: f64 = 0.7;
+// Electrons per hydrogen nucleus from the metals
+const IONIZATION_FRACTION: f64 = 1.0e-4;
+// Upper end of the temperature range of the H- power law (K)
+const H_MINUS_MAX_TEMPERATURE: f64 = 6000.0;
+const HYDROGEN_IONIZATION_ENERGY: f64 = 13.598; // eV
+
+// Optical depth range covered by the model, sampled uniformly in log(tau)
+const MIN_OPTICAL_DEPTH: f64 = 1.0e-6;
+const MAX_OPTICAL_DEPTH: f64 = 100.0;
+const NUM_LAYERS: usize = 161;
+
+// Relation used for the Hopf function q(tau)
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum TemperatureLaw {
+    Eddington,
+    Hopf,
+}
+
+impl TemperatureLaw {
+    fn hopf_function(self, optical_depth: f64) -> f64 {
+        match self {
+            TemperatureLaw::Eddington => 2.0 / 3.0,
+            // Fit that is exact at tau = 0 (1/sqrt(3)) and deep inside (0.7104)
+            TemperatureLaw::Hopf => 0.7104 - 0.1331 * (-3.4488 * optical_depth).exp(),
+        }
+    }
+}
+
+// One depth point of the atmosphere
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct AtmosphereLayer {
+    pub height: f64,        // meters above the tau = 1 level
+    pub optical_depth: f64, // gray (mean) optical depth
+    pub temperature: f64,   // K
+    pub pressure: f64,      // Pa
+    pub density: f64,       // kg/m^3
+}
+
+// Layers ordered from the top of the atmosphere (smallest tau) downward
+pub(crate) struct GrayAtmosphere {
+    pub effective_temperature: f64,
+    pub surface_gravity: f64,
+    pub law: TemperatureLaw,
+    pub layers: Vec<AtmosphereLayer>,
+}
+
+impl GrayAtmosphere {
+    pub fn new(effective_temperature: f64, surface_gravity: f64, law: TemperatureLaw) -> Self {
+        let mut atmosphere = GrayAtmosphere {
+            effective_temperature,
+            surface_gravity,
+            law,
+            layers: Vec::with_capacity(NUM_LAYERS),
+        };
+        atmosphere.integrate_hydrostatic_equilibrium();
+        atmosphere
+    }
+
+    pub fn temperature_at_optical_depth(&self, optical_depth: f64) -> f64 {
+        let q = self.law.hopf_function(optical_depth);
+        (0.75 * self.effective_temperature.powi(4) * (optical_depth + q)).powf(0.25)
+    }
+
+    // Integrate dP/d(ln tau) = tau g / kappa(P, T) with fourth-order Runge-Kutta
+    fn integrate_hydrostatic_equilibrium(&mut self) {
+        let log_step = (MAX_OPTICAL_DEPTH / MIN_OPTICAL_DEPTH).ln() / (NUM_LAYERS - 1) as f64;
+        let gravity = self.surface_gravity;
+        let derivative = |log_tau: f64, pressure: f64| {
+            let tau = log_tau.exp();
+            let temperature = self.temperature_at_optical_depth(tau);
+            tau * gravity / mean_opacity(pressure, temperature)
+        };
+
+        // Above the first layer the temperature is nearly constant, so the
+        // H- opacity kappa ~ P^(1/2) integrates to P^(3/2) ~ tau
+        let top_temperature = self.temperature_at_optical_depth(MIN_OPTICAL_DEPTH);
+        let opacity_per_root_pressure = h_minus_opacity(1.0, top_temperature);
+        let mut pressure = (1.5 * gravity * MIN_OPTICAL_DEPTH / opacity_per_root_pressure).powf(2.0 / 3.0);
+
+        let mut log_tau = MIN_OPTICAL_DEPTH.ln();
+        let mut pressures = Vec::with_capacity(NUM_LAYERS);
+        pressures.push(pressure);
+        for _ in 1..NUM_LAYERS {
+            let k1 = derivative(log_tau, pressure);
+            let k2 = derivative(log_tau + 0.5 * log_step, pressure + 0.5 * log_step * k1);
+            let k3 = derivative(log_tau + 0.5 * log_step, pressure + 0.5 * log_step * k2);
+            let k4 = derivative(log_tau + log_step, pressure + log_step * k3);
+            pressure += log_step * (k1 + 2.0 * k2 + 2.0 * k3 + k4) / 6.0;
+            log_tau += log_step;
+            pressures.push(pressure);
+        }
+
+        let mut layers: Vec<AtmosphereLayer> = pressures
+            .iter()
+            .enumerate()
+            .map(|(i, &pressure)| {
+                let optical_depth = MIN_OPTICAL_DEPTH * (i as f64 * log_step).exp();
+                let temperature = self.temperature_at_optical_depth(optical_depth);
+                AtmosphereLayer {
+                    height: 0.0,
+                    optical_depth,
+                    temperature,
+                    pressure,
+                    density: gas_density(pressure, temperature),
+                }
+            })
+            .collect();
+
+        // Geometric height from dh = -dtau / (kappa rho), zeroed at tau = 1
+        for i in 1..layers.len() {
+            let (upper, lower) = (layers[i - 1], layers[i]);
+            let extinction_upper = mean_opacity(upper.pressure, upper.temperature) * upper.density;
+            let extinction_lower = mean_opacity(lower.pressure, lower.temperature) * lower.density;
+            let dh = 2.0 * (lower.optical_depth - upper.optical_depth) / (extinction_upper + extinction_lower);
+            layers[i].height = upper.height - dh;
+        }
+        let reference = interpolate_height(&layers, 1.0);
+        for layer in &mut layers {
+            layer.height -= reference;
+        }
+        self.layers = layers;
+    }
+
+    /// Mixing-length convective velocity (m/s) at the tau = 1 level.
+    ///
+    /// The whole flux sigma T_eff^4 is carried by convection, F = rho c_p dT v,
+    /// with the buoyancy relation v^2 = g (dT / T) l / 8 and a mixing length
+    /// l = alpha * H_p (Bohm-Vitense 1958).
+    pub fn convective_velocity(&self, mixing_length_parameter: f64) -> f64 {
+        let layer = self
+            .layers
+            .iter()
+            .min_by(|a, b| a.height.abs().total_cmp(&b.height.abs()))
+            .copied()
+            .expect("atmosphere has layers");
+        let specific_heat = 2.5 * BOLTZMANN_CONSTANT / (MEAN_MOLECULAR_WEIGHT * ATOMIC_MASS_UNIT);
+        let pressure_scale_height = layer.pressure / (layer.density * self.surface_gravity);
+        let mixing_length = mixing_length_parameter * pressure_scale_height;
+        let flux = planck::STEFAN_BOLTZMANN_CONSTANT * self.effective_temperature.powi(4);
+        (flux * self.surface_gravity * mixing_length / (8.0 * layer.density * specific_heat * layer.temperature)).cbrt()
+    }
+
+    // Emergent specific intensity at direction cosine `mu` from the formal solver
+    pub fn emergent_intensity(&self, wavelength: f64, mu: f64) -> f64 {
+        transfer::emergent_intensity(self, wavelength, mu)
+    }
+}
+
+// The gray atmosphere in LTE: the opacity does not depend on wavelength and
+// the source function is the local Planck function
+impl RadiatingLayer for GrayAtmosphere {
+    fn heights(&self) -> Vec<f64> {
+        self.layers.iter().map(|l| l.height).collect()
+    }
+
+    fn densities(&self) -> Vec<f64> {
+        self.layers.iter().map(|l| l.density).collect()
+    }
+
+    fn opacities(&self, _wavelength: f64) -> Vec<f64> {
+        self.layers.iter().map(|l| mean_opacity(l.pressure, l.temperature)).collect()
+    }
+
+    fn source_function(&self, wavelength: f64) -> Vec<f64> {
+        self.layers
+            .iter()
+            .map(|l| planck::spectral_radiance_wavelength(wavelength, l.temperature))
+            .collect()
+    }
+}
+
+// Ideal-gas density from pressure and temperature
+fn gas_density(pressure: f64, temperature: f64) -> f64 {
+    pressure * MEAN_MOLECULAR_WEIGHT * ATOMIC_MASS_UNIT / (BOLTZMANN_CONSTANT * temperature)
+}
+
+// H- bound-free/free-free mean opacity, kappa = 2.5e-31 (Z/0.02) rho^(1/2) T^9 cm^2/g,
+// converted to m^2/kg; the temperature factor stops growing above
+// H_MINUS_MAX_TEMPERATURE, where hydrogen ionization ends the power law
+fn h_minus_opacity(pressure: f64, temperature: f64) -> f64 {
+    let density_cgs = gas_density(pressure, temperature) * 1.0e-3;
+    let temperature_factor = temperature.min(H_MINUS_MAX_TEMPERATURE).powi(9);
+    2.5e-31 * (METALLICITY / 0.02) * density_cgs.sqrt() * temperature_factor * 0.1
+}
+
+// Thomson scattering in m^2/kg: 0.02 (1 + X) for fully ionized gas, scaled
+// by the metal electrons plus the Saha ionization fraction of hydrogen
+fn electron_scattering_opacity(pressure: f64, temperature: f64) -> f64 {
+    let hydrogen_density = atomic_data::hydrogen_density(gas_density(pressure, temperature));
+    // x^2 / (1 - x) = n_e n_p / (n_0 n_H), the Saha ratio taken at n_e = n_H
+    let ratio = atomic_data::saha_ratio(2.0, 1.0, HYDROGEN_IONIZATION_ENERGY, temperature, hydrogen_density);
+    let hydrogen_ionization = 0.5 * (-ratio + (ratio * ratio + 4.0 * ratio).sqrt());
+    0.02 * (1.0 + HYDROGEN_FRACTION) * (IONIZATION_FRACTION + hydrogen_ionization)
+}
+
+// Total mean opacity in m^2/kg: H- plus Thomson scattering
+pub(crate) fn mean_opacity(pressure: f64, temperature: f64) -> f64 {
+    h_minus_opacity(pressure, temperature) + electron_scattering_opacity(pressure, temperature)
+}
+
+// Height at which the model reaches `optical_depth`, interpolating in log(tau)
+fn interpolate_height(layers: &[AtmosphereLayer], optical_depth: f64) -> f64 {
+    for pair in layers.windows(2) {
+        let (upper, lower) = (&pair[0], &pair[1]);
+        if optical_depth >= upper.optical_depth && optical_depth <= lower.optical_depth {
+            let fraction = (optical_depth / upper.optical_depth).ln() / (lower.optical_depth / upper.optical_depth).ln();
+            return upper.height + fraction * (lower.height - upper.height);
+        }
+    }
+    layers.last().map_or(0.0, |layer| layer.height)
+}
@@ -0,0 +1,441 @@
+// Stochastic cellular model of solar granulation.
+//
+// Granules are convective cells with a random size, lifetime and position.
+// Every pixel of a periodic patch of the surface belongs to the granule with
+// the smallest size-normalized distance (a power diagram), the gas rises in
+// the granule interiors and sinks in the lanes between them, and each
+// granule's flow grows and decays over its lifetime. Expired granules are
+// replaced by new ones at random positions, so the pattern evolves while its
+// statistics stay stationary. The flow amplitude is the mixing-length
+// convective velocity of the photosphere, and the temperature contrast that
+// goes with it sets the intensity pattern.
+
+extern crate rand;
+
+#[path = "photosphere.rs"]
+#[allow(dead_code)]
+pub(crate) mod photosphere;
+
+use photosphere::Photosphere;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+const PLANCK_CONSTANT: f64 = 6.62607015e-34; // J s
+const SPEED_OF_LIGHT: f64 = 299792458.0; // m/s
+const BOLTZMANN_CONSTANT: f64 = 1.380649e-23; // J/K
+
+// Granule diameters and lifetimes are log-normal (median, log-width)
+const MEDIAN_GRANULE_DIAMETER: f64 = 1.3e6; // m
+const GRANULE_DIAMETER_SPREAD: f64 = 0.35;
+const MEDIAN_GRANULE_LIFETIME: f64 = 480.0; // s
+const GRANULE_LIFETIME_SPREAD: f64 = 0.5;
+// Size-normalized boundary distance beyond which the gas sinks
+const LANE_THRESHOLD: f64 = 0.8;
+// Relative temperature excess of gas rising at the convective velocity,
+// at constant optical depth
+const TEMPERATURE_CONTRAST: f64 = 0.04;
+
+// One convective cell
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Granule {
+    pub x: f64,        // m
+    pub y: f64,        // m
+    pub radius: f64,   // m, fully developed
+    pub birth: f64,    // s
+    pub lifetime: f64, // s
+}
+
+impl Granule {
+    // Flow amplitude in [0, 1]: quick growth, a long mature phase, then decay
+    fn amplitude(&self, time: f64) -> f64 {
+        let age = (time - self.birth) / self.lifetime;
+        if (0.0..=1.0).contains(&age) {
+            (std::f64::consts::PI * age).sin().sqrt()
+        } else {
+            0.0
+        }
+    }
+}
+
+// Maps of one instant on a square pixel grid, indexed [row][column]
+pub(crate) struct GranulationFrame {
+    pub time: f64,                // s
+    pub temperature: Vec<Vec<f64>>, // K
+    pub intensity: Vec<Vec<f64>>, // relative to the mean intensity
+    pub velocity: Vec<Vec<f64>>,  // m/s, positive upward
+}
+
+pub(crate) struct GranulationModel {
+    pub field_size: f64, // m, side of the periodic patch
+    pub pixels: usize,
+    pub temperature: f64,          // K, mean photospheric temperature
+    pub convective_velocity: f64,  // m/s
+    pub wavelength: f64,           // m, wavelength of the intensity maps
+    pub granules: Vec<Granule>,
+    pub completed_lifetimes: Vec<f64>, // s, lifetimes of granules that have died
+    pub time: f64,
+    rng: StdRng,
+}
+
+impl GranulationModel {
+    /// Fills a periodic patch with granules of random age.
+    ///
+    /// # Arguments
+    ///
+    /// * `photosphere` - Supplies the temperature and convective velocity.
+    /// * `field_size` - Side of the square patch in meters.
+    /// * `pixels` - Number of pixels along each side of the maps.
+    /// * `wavelength` - Wavelength of the intensity maps in meters.
+    /// * `seed` - Seed of the random number generator.
+    pub fn new(photosphere: &Photosphere, field_size: f64, pixels: usize, wavelength: f64, seed: u64) -> Self {
+        let mut model = GranulationModel {
+            field_size,
+            pixels,
+            temperature: photosphere.temperature,
+            convective_velocity: photosphere.convective_velocity(),
+            wavelength,
+            granules: Vec::new(),
+            completed_lifetimes: Vec::new(),
+            time: 0.0,
+            rng: StdRng::seed_from_u64(seed),
+        };
+        let mean_area = std::f64::consts::PI * (0.5 * MEDIAN_GRANULE_DIAMETER).powi(2)
+            * (2.0 * GRANULE_DIAMETER_SPREAD * GRANULE_DIAMETER_SPREAD).exp();
+        let count = ((field_size * field_size / mean_area).round() as usize).max(1);
+        for _ in 0..count {
+            let mut granule = model.spawn_granule();
+            granule.birth = -model.rng.gen::<f64>() * granule.lifetime;
+            model.granules.push(granule);
+        }
+        model
+    }
+
+    fn spawn_granule(&mut self) -> Granule {
+        let diameter = log_normal(&mut self.rng, MEDIAN_GRANULE_DIAMETER, GRANULE_DIAMETER_SPREAD);
+        Granule {
+            x: self.rng.gen::<f64>() * self.field_size,
+            y: self.rng.gen::<f64>() * self.field_size,
+            radius: 0.5 * diameter,
+            birth: self.time,
+            lifetime: log_normal(&mut self.rng, MEDIAN_GRANULE_LIFETIME, GRANULE_LIFETIME_SPREAD),
+        }
+    }
+
+    pub fn pixel_size(&self) -> f64 {
+        self.field_size / self.pixels as f64
+    }
+
+    /// Advances the pattern by `dt` seconds, replacing granules that have died.
+    pub fn advance(&mut self, dt: f64) {
+        self.time += dt;
+        for i in 0..self.granules.len() {
+            let granule = self.granules[i];
+            if self.time > granule.birth + granule.lifetime {
+                self.completed_lifetimes.push(granule.lifetime);
+                self.granules[i] = self.spawn_granule();
+            }
+        }
+    }
+
+    /// Temperature, intensity and velocity maps at the current time.
+    pub fn frame(&self) -> GranulationFrame {
+        let n = self.pixels;
+        let pixel = self.pixel_size();
+        let index = self.index();
+        let mut velocity = vec![vec![0.0; n]; n];
+        for (row, line) in velocity.iter_mut().enumerate() {
+            for (column, value) in line.iter_mut().enumerate() {
+                let x = (column as f64 + 0.5) * pixel;
+                let y = (row as f64 + 0.5) * pixel;
+                *value = self.local_velocity(&index, x, y);
+            }
+        }
+
+        // No net mass flux through the surface
+        let mean = velocity.iter().flatten().sum::<f64>() / (n * n) as f64;
+        velocity.iter_mut().flatten().for_each(|v| *v -= mean);
+
+        let temperature: Vec<Vec<f64>> = velocity
+            .iter()
+            .map(|line| {
+                line.iter()
+                    .map(|v| self.temperature * (1.0 + TEMPERATURE_CONTRAST * v / self.convective_velocity))
+                    .collect()
+            })
+            .collect();
+        let radiance = |t: f64| {
+            let x = PLANCK_CONSTANT * SPEED_OF_LIGHT / (self.wavelength * BOLTZMANN_CONSTANT * t);
+            1.0 / x.exp_m1()
+        };
+        let mut intensity: Vec<Vec<f64>> = temperature
+            .iter()
+            .map(|line| line.iter().map(|&t| radiance(t)).collect())
+            .collect();
+        let mean_intensity = intensity.iter().flatten().sum::<f64>() / (n * n) as f64;
+        intensity.iter_mut().flatten().for_each(|i| *i /= mean_intensity);
+
+        GranulationFrame {
+            time: self.time,
+            temperature,
+            intensity,
+            velocity,
+        }
+    }
+
+    /// Frames at `steps` instants separated by `dt` seconds, starting now.
+    pub fn time_series(&mut self, steps: usize, dt: f64) -> Vec<GranulationFrame> {
+        let mut frames = Vec::with_capacity(steps);
+        for step in 0..steps {
+            if step > 0 {
+                self.advance(dt);
+            }
+            frames.push(self.frame());
+        }
+        frames
+    }
+
+    // Vertical velocity at a point: up in the granule interior, down in the lanes
+    fn local_velocity(&self, index: &GranuleIndex, x: f64, y: f64) -> f64 {
+        let n = index.cells.len();
+        let column = ((x / index.cell_size).floor() as isize).rem_euclid(n as isize);
+        let row = ((y / index.cell_size).floor() as isize).rem_euclid(n as isize);
+        // Cell offsets along a side, each cell of the periodic grid once
+        let (lowest, highest) = (-((n / 2) as isize), ((n - 1) / 2) as isize);
+        let mut nearest = (f64::INFINITY, 0.0);
+        let mut second = f64::INFINITY;
+        for ring in 0..=highest.max(-lowest) {
+            // Granules beyond this ring are at least ring - 1 cells away
+            if ring > 0 && (ring - 1) as f64 * index.cell_size / index.max_radius > second {
+                break;
+            }
+            for dy in (-ring).max(lowest)..=ring.min(highest) {
+                for dx in (-ring).max(lowest)..=ring.min(highest) {
+                    if dx.abs() != ring && dy.abs() != ring {
+                        continue;
+                    }
+                    let cell_row = (row + dy).rem_euclid(n as isize) as usize;
+                    let cell_column = (column + dx).rem_euclid(n as isize) as usize;
+                    for granule in &index.cells[cell_row][cell_column] {
+                        let dx = periodic_offset(x - granule.x, self.field_size);
+                        let dy = periodic_offset(y - granule.y, self.field_size);
+                        let distance = (dx * dx + dy * dy).sqrt() / granule.radius;
+                        if distance < nearest.0 {
+                            second = nearest.0;
+                            nearest = (distance, granule.amplitude);
+                        } else if distance < second {
+                            second = distance;
+                        }
+                    }
+                }
+            }
+        }
+        let (distance, amplitude) = nearest;
+        // Closeness to the boundary with the neighbouring cell, 1 on the boundary
+        let boundary = if second.is_finite() { distance / second } else { distance };
+        self.convective_velocity * amplitude * (1.0 - (boundary / LANE_THRESHOLD).powi(2))
+    }
+
+    // Granules of the current instant binned on a periodic grid of cells
+    fn index(&self) -> GranuleIndex {
+        let n = ((self.field_size / MEDIAN_GRANULE_DIAMETER) as usize).max(1);
+        let cell_size = self.field_size / n as f64;
+        let mut cells = vec![vec![Vec::new(); n]; n];
+        let mut max_radius = 0.0_f64;
+        for granule in &self.granules {
+            let amplitude = granule.amplitude(self.time);
+            // Young and dying granules occupy less of the surface
+            let radius = granule.radius * amplitude.max(0.2).sqrt();
+            max_radius = max_radius.max(radius);
+            let cell = |position: f64| ((position / cell_size).floor() as isize).rem_euclid(n as isize) as usize;
+            cells[cell(granule.y)][cell(granule.x)].push(IndexedGranule {
+                x: granule.x,
+                y: granule.y,
+                radius,
+                amplitude,
+            });
+        }
+        GranuleIndex {
+            cells,
+            cell_size,
+            max_radius,
+        }
+    }
+}
+
+// Granules binned by position, so that a pixel only visits the granules
+// around it
+struct GranuleIndex {
+    cells: Vec<Vec<Vec<IndexedGranule>>>, // [row][column]
+    cell_size: f64,                       // m
+    max_radius: f64,                      // m, largest current radius
+}
+
+// A granule as it is at the time of the index
+#[derive(Clone, Copy)]
+struct IndexedGranule {
+    x: f64,         // m
+    y: f64,         // m
+    radius: f64,    // m
+    amplitude: f64, // in [0, 1]
+}
+
+// Size and lifetime statistics of a time series
+#[derive(Debug)]
+pub(crate) struct GranulationStatistics {
+    pub granule_count: f64,      // mean number of granules per frame
+    pub mean_diameter: f64,      // m, area-equivalent diameter
+    pub diameter_spread: f64,    // m, standard deviation
+    pub mean_lifetime: f64,      // s, of granules that were born and died
+    pub correlation_time: f64,   // s, 1/e decay time of the intensity correlation
+    pub intensity_contrast: f64, // rms of I / <I>
+    pub velocity_rms: f64,       // m/s
+}
+
+/// Area-equivalent diameters (m) of the bright, rising granules in a frame.
+pub(crate) fn granule_diameters(frame: &GranulationFrame, pixel_size: f64) -> Vec<f64> {
+    let n = frame.velocity.len();
+    let mut visited = vec![vec![false; n]; n];
+    let mut diameters = Vec::new();
+    for row in 0..n {
+        for column in 0..n {
+            if visited[row][column] || frame.velocity[row][column] <= 0.0 {
+                continue;
+            }
+            // Flood fill one connected upflow region
+            let mut area = 0usize;
+            let mut stack = vec![(row, column)];
+            visited[row][column] = true;
+            while let Some((r, c)) = stack.pop() {
+                area += 1;
+                let neighbours = [((r + n - 1) % n, c), ((r + 1) % n, c), (r, (c + n - 1) % n), (r, (c + 1) % n)];
+                for (nr, nc) in neighbours {
+                    if !visited[nr][nc] && frame.velocity[nr][nc] > 0.0 {
+                        visited[nr][nc] = true;
+                        stack.push((nr, nc));
+                    }
+                }
+            }
+            // Single pixels are noise, not resolved granules
+            if area > 1 {
+                diameters.push(2.0 * (area as f64 / std::f64::consts::PI).sqrt() * pixel_size);
+            }
+        }
+    }
+    diameters
+}
+
+/// Pearson correlation of the intensity maps of two frames.
+pub(crate) fn intensity_correlation(first: &GranulationFrame, second: &GranulationFrame) -> f64 {
+    let a: Vec<f64> = first.intensity.iter().flatten().copied().collect();
+    let b: Vec<f64> = second.intensity.iter().flatten().copied().collect();
+    let mean_a = a.iter().sum::<f64>() / a.len() as f64;
+    let mean_b = b.iter().sum::<f64>() / b.len() as f64;
+    let covariance: f64 = a.iter().zip(&b).map(|(x, y)| (x - mean_a) * (y - mean_b)).sum();
+    let variance_a: f64 = a.iter().map(|x| (x - mean_a).powi(2)).sum();
+    let variance_b: f64 = b.iter().map(|y| (y - mean_b).powi(2)).sum();
+    covariance / (variance_a * variance_b).sqrt()
+}
+
+/// Vertical velocity history (s, m/s) of one pixel, e.g. to drive waves.
+pub(crate) fn velocity_history(frames: &[GranulationFrame], row: usize, column: usize) -> Vec<(f64, f64)> {
+    frames.iter().map(|frame| (frame.time, frame.velocity[row][column])).collect()
+}
+
+/// Statistics of a time series of frames from `model`.
+pub(crate) fn statistics(model: &GranulationModel, frames: &[GranulationFrame]) -> GranulationStatistics {
+    let diameters: Vec<f64> = frames
+        .iter()
+        .flat_map(|frame| granule_diameters(frame, model.pixel_size()))
+        .collect();
+    let mean_diameter = diameters.iter().sum::<f64>() / diameters.len().max(1) as f64;
+    let diameter_spread =
+        (diameters.iter().map(|d| (d - mean_diameter).powi(2)).sum::<f64>() / diameters.len().max(1) as f64).sqrt();
+
+    let lifetimes = &model.completed_lifetimes;
+    let mean_lifetime = lifetimes.iter().sum::<f64>() / lifetimes.len().max(1) as f64;
+
+    // First lag at which the correlation with the first frame drops below 1/e
+    let correlation_time = frames
+        .iter()
+        .skip(1)
+        .find(|frame| intensity_correlation(&frames[0], frame) < (-1.0f64).exp())
+        .map_or(f64::NAN, |frame| frame.time - frames[0].time);
+
+    let values = |map: fn(&GranulationFrame) -> &Vec<Vec<f64>>| -> Vec<f64> {
+        frames.iter().flat_map(|frame| map(frame).iter().flatten().copied()).collect()
+    };
+    let intensities = values(|frame| &frame.intensity);
+    let velocities = values(|frame| &frame.velocity);
+    let rms = |samples: &[f64], centre: f64| {
+        (samples.iter().map(|s| (s - centre).powi(2)).sum::<f64>() / samples.len() as f64).sqrt()
+    };
+
+    GranulationStatistics {
+        granule_count: diameters.len() as f64 / frames.len().max(1) as f64,
+        mean_diameter,
+        diameter_spread,
+        mean_lifetime,
+        correlation_time,
+        intensity_contrast: rms(&intensities, 1.0),
+        velocity_rms: rms(&velocities, 0.0),
+    }
+}
+
+// Shortest signed separation on a periodic interval of length `period`
+fn periodic_offset(offset: f64, period: f64) -> f64 {
+    offset - period * (offset / period).round()
+}
+
+// Log-normal sample with the given median and logarithmic width (Box-Muller)
+fn log_normal(rng: &mut StdRng, median: f64, spread: f64) -> f64 {
+    let u1: f64 = rng.gen::<f64>().max(f64::MIN_POSITIVE);
+    let u2: f64 = rng.gen();
+    let normal = (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos();
+    median * (spread * normal).exp()
+}
+
+// Constants for the granulation run
+const FIELD_SIZE: f64 = 12.0e6; // meters, side of the simulated patch
+const FIELD_PIXELS: usize = 128;
+const MAP_WAVELENGTH: f64 = 500.0e-9; // meters
+const CADENCE: f64 = 30.0; // seconds
+const SERIES_LENGTH: usize = 120; // one hour at the cadence above
+const RANDOM_SEED: u64 = 2024;
+
+fn main() {
+    let photosphere = Photosphere::quiet_sun();
+    let mut model = GranulationModel::new(&photosphere, FIELD_SIZE, FIELD_PIXELS, MAP_WAVELENGTH, RANDOM_SEED);
+    println!(
+        "Granulation Patch: {:.1} Mm, {} granules, convective velocity {:.0} m/s",
+        FIELD_SIZE / 1.0e6,
+        model.granules.len(),
+        model.convective_velocity
+    );
+
+    let frames = model.time_series(SERIES_LENGTH, CADENCE);
+    let stats = statistics(&model, &frames);
+    println!("Granules per Frame: {:.1}", stats.granule_count);
+    println!(
+        "Granule Diameter: {:.2} +/- {:.2} Mm",
+        stats.mean_diameter / 1.0e6,
+        stats.diameter_spread / 1.0e6
+    );
+    println!("Mean Granule Lifetime: {:.1} min", stats.mean_lifetime / 60.0);
+    println!("Intensity Correlation Time: {:.1} min", stats.correlation_time / 60.0);
+    println!("Intensity Contrast ({:.0} nm): {:.2}%", MAP_WAVELENGTH * 1.0e9, 100.0 * stats.intensity_contrast);
+    println!("RMS Vertical Velocity: {:.0} m/s", stats.velocity_rms);
+    let last = frames.last().expect("time series has frames");
+    let (coolest, hottest) = last
+        .temperature
+        .iter()
+        .flatten()
+        .fold((f64::INFINITY, 0.0_f64), |(low, high), &t| (low.min(t), high.max(t)));
+    println!("Temperature Range: {:.0} - {:.0} K", coolest, hottest);
+
+    // A velocity history at the patch centre, as a wave driver
+    let centre = FIELD_PIXELS / 2;
+    let history = velocity_history(&frames, centre, centre);
+    for (time, velocity) in history.iter().step_by(20) {
+        println!("t = {:>6.0} s: v_z(centre) = {:>7.1} m/s", time, velocity);
+    }
+}
@@ -0,0 +1,76 @@
+// Center-to-limb variation of the photospheric intensity and the standard
+// coefficient laws fitted to it.
+//
+// All laws are written as 1 - I(mu)/I(1) = sum of c_k * f_k(mu), which is
+// linear in the coefficients, so each fit is a linear least-squares problem.
+
+use radiation::numerics;
+use radiation::transfer::{self, RadiatingLayer};
+
+// Number of mu points used to sample the center-to-limb profile for fitting
+const FIT_POINTS: usize = 100;
+// Smallest mu sampled; the extreme limb is left out as usual for these fits
+const MIN_FIT_MU: f64 = 0.01;
+
+#[derive(Clone, Debug)]
+pub(crate) enum LimbDarkeningLaw {
+    // I/I(1) = 1 - u (1 - mu)
+    Linear { u: f64 },
+    // I/I(1) = 1 - a (1 - mu) - b (1 - mu)^2
+    Quadratic { a: f64, b: f64 },
+    // Claret (2000): I/I(1) = 1 - sum over k of c_k (1 - mu^(k/2)), k = 1..4
+    NonLinear { c: [f64; 4] },
+}
+
+impl LimbDarkeningLaw {
+    pub fn intensity_ratio(&self, mu: f64) -> f64 {
+        match self {
+            LimbDarkeningLaw::Linear { u } => 1.0 - u * (1.0 - mu),
+            LimbDarkeningLaw::Quadratic { a, b } => 1.0 - a * (1.0 - mu) - b * (1.0 - mu).powi(2),
+            LimbDarkeningLaw::NonLinear { c } => {
+                1.0 - (1..=4).map(|k| c[k - 1] * (1.0 - mu.powf(k as f64 / 2.0))).sum::<f64>()
+            }
+        }
+    }
+}
+
+/// Center-to-limb intensity variation I(mu)/I(1) of a layer at one wavelength.
+///
+/// # Returns
+///
+/// (mu, I(mu)/I(1)) pairs for every requested direction cosine.
+pub(crate) fn center_to_limb<L: RadiatingLayer>(layer: &L, wavelength: f64, mus: &[f64]) -> Vec<(f64, f64)> {
+    let disk_center = transfer::emergent_intensity(layer, wavelength, 1.0);
+    mus.iter()
+        .map(|&mu| (mu, transfer::emergent_intensity(layer, wavelength, mu) / disk_center))
+        .collect()
+}
+
+// Evenly spaced mu points used for the fits
+pub(crate) fn fit_mu_points() -> Vec<f64> {
+    (0..FIT_POINTS)
+        .map(|i| MIN_FIT_MU + (1.0 - MIN_FIT_MU) * i as f64 / (FIT_POINTS - 1) as f64)
+        .collect()
+}
+
+pub(crate) fn fit_linear(profile: &[(f64, f64)]) -> Option<LimbDarkeningLaw> {
+    let c = fit_basis(profile, |mu| vec![1.0 - mu])?;
+    Some(LimbDarkeningLaw::Linear { u: c[0] })
+}
+
+pub(crate) fn fit_quadratic(profile: &[(f64, f64)]) -> Option<LimbDarkeningLaw> {
+    let c = fit_basis(profile, |mu| vec![1.0 - mu, (1.0 - mu).powi(2)])?;
+    Some(LimbDarkeningLaw::Quadratic { a: c[0], b: c[1] })
+}
+
+pub(crate) fn fit_nonlinear(profile: &[(f64, f64)]) -> Option<LimbDarkeningLaw> {
+    let c = fit_basis(profile, |mu| (1..=4).map(|k| 1.0 - mu.powf(k as f64 / 2.0)).collect())?;
+    Some(LimbDarkeningLaw::NonLinear { c: [c[0], c[1], c[2], c[3]] })
+}
+
+// Least-squares fit of 1 - I(mu)/I(1) by the given basis functions
+fn fit_basis<F: Fn(f64) -> Vec<f64>>(profile: &[(f64, f64)], basis: F) -> Option<Vec<f64>> {
+    let design: Vec<Vec<f64>> = profile.iter().map(|&(mu, _)| basis(mu)).collect();
+    let target: Vec<f64> = profile.iter().map(|&(_, ratio)| 1.0 - ratio).collect();
+    numerics::least_squares(&design, &target)
+}
@@ -0,0 +1,198 @@
+// LTE spectral line synthesis on the photospheric model atmosphere.
+//
+// Level populations follow from the Saha and Boltzmann equations at the
+// local temperature and electron density, each line gets a Voigt profile
+// with thermal, microturbulent and pressure broadening, and the line
+// opacity is added to the continuum opacity before the formal solution.
+
+use super::atmosphere::{mean_opacity, GrayAtmosphere};
+use radiation::atomic_data;
+use radiation::line_list::SpectralLine;
+use radiation::line_profile;
+use radiation::planck::{self, BOLTZMANN_CONSTANT, PLANCK_CONSTANT, SPEED_OF_LIGHT};
+use radiation::spectral_grid::SpectralGrid;
+use radiation::transfer::{self, RadiatingLayer};
+
+// Classical line cross-section pi e^2 / (4 pi eps0 m_e c), in m^2 Hz
+const CLASSICAL_CROSS_SECTION: f64 = 2.654008e-6;
+// Lines further than this from the sampled wavelength are ignored
+const LINE_CUTOFF: f64 = 2.0e-9; // m
+// Hydrogen lines have far wider pressure-broadened wings
+const HYDROGEN_LINE_CUTOFF: f64 = 20.0e-9; // m
+
+// Per-layer quantities of one line that do not depend on wavelength
+struct LineLayers {
+    strength: Vec<f64>,      // frequency-integrated opacity, m^2 kg^-1 Hz
+    doppler_width: Vec<f64>, // Hz
+    damping_rate: Vec<f64>,  // s^-1
+}
+
+pub(crate) struct LineSynthesis<'a> {
+    atmosphere: &'a GrayAtmosphere,
+    lines: &'a [SpectralLine],
+    line_layers: Vec<LineLayers>,
+}
+
+impl<'a> LineSynthesis<'a> {
+    /// Prepares the LTE populations and broadening of every line.
+    ///
+    /// # Arguments
+    ///
+    /// * `atmosphere` - The model atmosphere the lines form in.
+    /// * `lines` - The atomic line list.
+    /// * `microturbulence` - The microturbulent velocity in m/s.
+    pub fn new(atmosphere: &'a GrayAtmosphere, lines: &'a [SpectralLine], microturbulence: f64) -> Self {
+        // Electron and neutral hydrogen densities at each depth
+        let gas: Vec<(f64, f64, f64)> = atmosphere
+            .layers
+            .iter()
+            .map(|layer| {
+                let hydrogen = atomic_data::hydrogen_density(layer.density);
+                let electrons = atomic_data::electron_density(layer.temperature, hydrogen);
+                let neutral_fraction = atomic_data::ELEMENTS[0].ionization_fractions(layer.temperature, electrons)[0];
+                (hydrogen, electrons, hydrogen * neutral_fraction)
+            })
+            .collect();
+
+        let line_layers = lines
+            .iter()
+            .map(|line| {
+                let frequency = line.frequency();
+                let mut layers = LineLayers {
+                    strength: Vec::with_capacity(gas.len()),
+                    doppler_width: Vec::with_capacity(gas.len()),
+                    damping_rate: Vec::with_capacity(gas.len()),
+                };
+                for (layer, &(hydrogen, electrons, neutral_hydrogen)) in atmosphere.layers.iter().zip(&gas) {
+                    let temperature = layer.temperature;
+                    let stage = line.ion_stage - 1;
+                    let stage_density = hydrogen
+                        * line.element.abundance()
+                        * line.element.ionization_fractions(temperature, electrons)[stage];
+                    // n_lower * g_lower * f, with g_lower * f = gf
+                    let weighted_lower = stage_density
+                        * atomic_data::boltzmann_fraction(
+                            10f64.powf(line.log_gf),
+                            line.excitation_energy,
+                            line.element.partition_function[stage],
+                            temperature,
+                        );
+                    let stimulated = 1.0 - (-PLANCK_CONSTANT * frequency / (BOLTZMANN_CONSTANT * temperature)).exp();
+                    layers.strength.push(CLASSICAL_CROSS_SECTION * weighted_lower * stimulated / layer.density);
+                    layers.doppler_width.push(line_profile::doppler_width(
+                        frequency,
+                        temperature,
+                        line.element.mass_number,
+                        microturbulence,
+                    ));
+                    layers.damping_rate.push(line.damping_rate(temperature, electrons, neutral_hydrogen));
+                }
+                layers
+            })
+            .collect();
+
+        LineSynthesis {
+            atmosphere,
+            lines,
+            line_layers,
+        }
+    }
+
+    /// Summed line opacity (m^2/kg) at each depth for one wavelength.
+    pub fn line_opacity(&self, wavelength: f64) -> Vec<f64> {
+        let frequency = SPEED_OF_LIGHT / wavelength;
+        let mut opacity = vec![0.0; self.atmosphere.layers.len()];
+        for (line, layers) in self.lines.iter().zip(&self.line_layers) {
+            let cutoff = if line.element.atomic_number == 1 { HYDROGEN_LINE_CUTOFF } else { LINE_CUTOFF };
+            if (wavelength - line.wavelength).abs() > cutoff {
+                continue;
+            }
+            let offset = frequency - line.frequency();
+            for (i, value) in opacity.iter_mut().enumerate() {
+                *value += layers.strength[i]
+                    * line_profile::voigt_profile(offset, layers.doppler_width[i], layers.damping_rate[i]);
+            }
+        }
+        opacity
+    }
+
+    /// Line-to-continuum opacity ratio at line centre, Doppler width (m) and
+    /// Voigt damping parameter of line `index` at the layer nearest to
+    /// `optical_depth`; these are the Milne-Eddington parameters of the line.
+    pub fn formation_parameters(&self, index: usize, optical_depth: f64) -> (f64, f64, f64) {
+        let layer_index = self
+            .atmosphere
+            .layers
+            .iter()
+            .enumerate()
+            .min_by(|a, b| {
+                let distance = |tau: f64| (tau / optical_depth).ln().abs();
+                distance(a.1.optical_depth).total_cmp(&distance(b.1.optical_depth))
+            })
+            .map_or(0, |(i, _)| i);
+        let layer = &self.atmosphere.layers[layer_index];
+        let line = &self.lines[index];
+        let layers = &self.line_layers[index];
+
+        let doppler_width = layers.doppler_width[layer_index];
+        let peak_opacity = layers.strength[layer_index]
+            * line_profile::voigt_profile(0.0, doppler_width, layers.damping_rate[layer_index]);
+        let line_strength = peak_opacity / mean_opacity(layer.pressure, layer.temperature);
+        let damping = layers.damping_rate[layer_index] / (4.0 * std::f64::consts::PI * doppler_width);
+        let doppler_width_wavelength = line.wavelength * doppler_width / line.frequency();
+        (line_strength, doppler_width_wavelength, damping)
+    }
+
+    // Emergent intensity I(lambda, mu) including the lines
+    pub fn spectrum(&self, grid: &SpectralGrid, mu: f64) -> Vec<(f64, f64)> {
+        transfer::emergent_spectrum(self, grid, mu)
+    }
+
+    // Emergent intensity divided by the line-free continuum intensity
+    pub fn normalized_spectrum(&self, grid: &SpectralGrid, mu: f64) -> Vec<(f64, f64)> {
+        self.spectrum(grid, mu)
+            .into_iter()
+            .map(|(wavelength, intensity)| {
+                (wavelength, intensity / self.atmosphere.emergent_intensity(wavelength, mu))
+            })
+            .collect()
+    }
+}
+
+// Lines and continuum in LTE: the source function is the Planck function
+impl RadiatingLayer for LineSynthesis<'_> {
+    fn heights(&self) -> Vec<f64> {
+        self.atmosphere.heights()
+    }
+
+    fn densities(&self) -> Vec<f64> {
+        self.atmosphere.densities()
+    }
+
+    fn opacities(&self, wavelength: f64) -> Vec<f64> {
+        let lines = self.line_opacity(wavelength);
+        self.atmosphere
+            .layers
+            .iter()
+            .zip(lines)
+            .map(|(layer, line)| mean_opacity(layer.pressure, layer.temperature) + line)
+            .collect()
+    }
+
+    fn source_function(&self, wavelength: f64) -> Vec<f64> {
+        self.atmosphere
+            .layers
+            .iter()
+            .map(|layer| planck::spectral_radiance_wavelength(wavelength, layer.temperature))
+            .collect()
+    }
+}
+
+// Equivalent width (m) of an absorption feature in a normalized spectrum
+pub(crate) fn equivalent_width(normalized: &[(f64, f64)]) -> f64 {
+    normalized
+        .windows(2)
+        .map(|pair| 0.5 * ((1.0 - pair[0].1) + (1.0 - pair[1].1)) * (pair[1].0 - pair[0].0).abs())
+        .sum()
+}
+
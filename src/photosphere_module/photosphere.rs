@@ -1,64 +1,380 @@
-struct Photosphere {
-  radius: f64,
-  temperature: f64,
+mod active_regions;
+mod atmosphere;
+mod limb_darkening;
+mod line_synthesis;
+
+use active_regions::{DiskIntensity, Surface};
+pub(crate) use active_regions::{Facula, Sunspot};
+use atmosphere::{GrayAtmosphere, TemperatureLaw};
+use limb_darkening::LimbDarkeningLaw;
+use line_synthesis::LineSynthesis;
+use radiation::line_list::{self, SpectralLine};
+use radiation::photometry::{self, Filter};
+use radiation::planck;
+use radiation::spectral_grid::{SpectralGrid, SpectralUnit};
+use radiation::stokes::{self, MilneEddington};
+use radiation::transfer;
+
+pub(crate) struct Photosphere {
+  pub(crate) radius: f64,
+  pub(crate) temperature: f64,
+  // Bulk properties kept from the original model; the gray atmosphere
+  // supplies the densities
+  #[allow(dead_code)]
   density: f64,
+  #[allow(dead_code)]
   energy_generation: f64,
+  atmosphere: GrayAtmosphere,
+  microturbulence: f64,
+  sunspots: Vec<Sunspot>,
+  faculae: Vec<Facula>,
+  spot_atmospheres: Vec<[GrayAtmosphere; 2]>, // umbra and penumbra of each sunspot
 }
 
 impl Photosphere {
-  fn calculate_optical_depth(&self, core_radius: f64, wavelength: f64) -> f64 {
-      let kappa = self.get_absorption_coefficient(wavelength);
-      let photosphere_thickness = self.radius - core_radius;
-      kappa * photosphere_thickness
-  }
-
-  fn get_emission_spectrum(&self) -> Vec<(f64, f64)> {
-      let mut spectrum = Vec::new();
-      let temperature = self.temperature;
-      // Iterate over wavelengths from 200nm to 700nm in steps of 1nm
-      for wavelength in (200..=700).map(|w| w as f64 * 1.0e-9) {
-          let intensity = blackbody_intensity(wavelength, temperature);
-          spectrum.push((wavelength, intensity));
+  // Photosphere whose gray Hopf atmosphere has the given effective temperature
+  pub(crate) fn new(radius: f64, temperature: f64, density: f64, energy_generation: f64) -> Self {
+      Photosphere {
+          radius,
+          temperature,
+          density,
+          energy_generation,
+          atmosphere: GrayAtmosphere::new(temperature, PHOTOSPHERE_SURFACE_GRAVITY, TemperatureLaw::Hopf),
+          microturbulence: PHOTOSPHERE_MICROTURBULENCE,
+          sunspots: Vec::new(),
+          faculae: Vec::new(),
+          spot_atmospheres: Vec::new(),
       }
-      spectrum
   }
 
-  // Function to calculate absorption coefficient
-  fn get_absorption_coefficient(&self, _wavelength: f64) -> f64 {
-      // Placeholder implementation for absorption coefficient calculation
-      // Here you can implement the actual logic to calculate the absorption coefficient
-      // For now, let's return a constant value as an example
-      0.5
+  // Attach a sunspot, modelling its umbra and penumbra as gray atmospheres
+  pub(crate) fn add_sunspot(&mut self, sunspot: Sunspot) {
+      let atmosphere = |temperature: f64| GrayAtmosphere::new(temperature, PHOTOSPHERE_SURFACE_GRAVITY, TemperatureLaw::Hopf);
+      self.spot_atmospheres.push([atmosphere(sunspot.umbra_temperature), atmosphere(sunspot.penumbra_temperature)]);
+      self.sunspots.push(sunspot);
+  }
+
+  pub(crate) fn add_facula(&mut self, facula: Facula) {
+      self.faculae.push(facula);
+  }
+
+  // Intensity across the disk at one wavelength, with sunspots and faculae
+  pub(crate) fn disk_intensity(&self, wavelength: f64) -> DiskIntensity {
+      DiskIntensity::new(wavelength, self.radius, self.temperature, &self.sunspots, &self.faculae, |surface, mu| {
+          let atmosphere = match surface {
+              Surface::Quiet => &self.atmosphere,
+              Surface::Umbra(i) => &self.spot_atmospheres[i][0],
+              Surface::Penumbra(i) => &self.spot_atmospheres[i][1],
+          };
+          atmosphere.emergent_intensity(wavelength, mu)
+      })
+  }
+
+  // Surface flux F_lambda (W m^-2 m^-1) including the sunspots and faculae
+  pub(crate) fn active_flux(&self, grid: &SpectralGrid) -> Vec<(f64, f64)> {
+      grid.map_wavelengths(|wavelength| {
+          transfer::emergent_flux(&self.atmosphere, wavelength) + self.disk_intensity(wavelength).flux_change()
+      })
+  }
+
+  // Quiet-Sun photosphere with the nominal solar radius and effective
+  // temperature; the other programs take the photosphere from here
+  pub(crate) fn quiet_sun() -> Self {
+      Photosphere::new(
+          PHOTOSPHERE_RADIUS,
+          PHOTOSPHERE_TEMPERATURE,
+          PHOTOSPHERE_DENSITY,
+          PHOTOSPHERE_ENERGY_GENERATION,
+      )
+  }
+
+  // Mixing-length convective velocity at the surface, m/s
+  pub(crate) fn convective_velocity(&self) -> f64 {
+      self.atmosphere.convective_velocity(MIXING_LENGTH_PARAMETER)
+  }
+
+  // Emergent intensity I(lambda, mu) at the top of the atmosphere
+  pub(crate) fn emergent_intensity(&self, wavelength: f64, mu: f64) -> f64 {
+      self.atmosphere.emergent_intensity(wavelength, mu)
+  }
+
+  // Vertical optical depth through the model atmosphere, integrating
+  // dtau = kappa * rho * ds over its depth structure
+  fn calculate_optical_depth(&self, wavelength: f64) -> f64 {
+      transfer::optical_depth(&self.atmosphere, wavelength)
+  }
+
+  // Disk-centre emergent intensity, formed over the depth-resolved atmosphere
+  fn get_emission_spectrum(&self, grid: &SpectralGrid) -> Vec<(f64, f64)> {
+      grid.map_wavelengths(|wavelength| self.emergent_intensity(wavelength, 1.0))
+  }
+
+  // Disk-centre spectrum with LTE absorption lines, normalized to the continuum
+  fn get_absorption_line_spectrum(&self, grid: &SpectralGrid, lines: &[SpectralLine]) -> Vec<(f64, f64)> {
+      LineSynthesis::new(&self.atmosphere, lines, self.microturbulence).normalized_spectrum(grid, 1.0)
+  }
+
+  // Milne-Eddington model of a magnetically split line, with the source
+  // function linearized over tau = 0..1 and the line parameters taken where
+  // the line forms (tau ~ 0.1); `None` if the line has no Zeeman data
+  fn milne_eddington(
+      &self,
+      lines: &[SpectralLine],
+      index: usize,
+      field_strength: f64,
+      inclination: f64,
+      azimuth: f64,
+  ) -> Option<MilneEddington> {
+      let line = &lines[index];
+      let levels = line.zeeman?;
+      let synthesis = LineSynthesis::new(&self.atmosphere, lines, self.microturbulence);
+      let (line_strength, doppler_width, damping) = synthesis.formation_parameters(index, LINE_FORMATION_DEPTH);
+      let surface = planck::spectral_radiance_wavelength(line.wavelength, self.atmosphere.temperature_at_optical_depth(0.0));
+      let unit_depth = planck::spectral_radiance_wavelength(line.wavelength, self.atmosphere.temperature_at_optical_depth(1.0));
+      Some(MilneEddington {
+          line_center: line.wavelength,
+          levels,
+          field_strength,
+          inclination,
+          azimuth,
+          doppler_width,
+          damping,
+          line_strength,
+          line_of_sight_velocity: 0.0,
+          source_constant: surface,
+          source_gradient: unit_depth - surface,
+      })
+  }
+
+  // Center-to-limb variation I(mu)/I(1) at one wavelength
+  fn limb_darkening_profile(&self, wavelength: f64, mus: &[f64]) -> Vec<(f64, f64)> {
+      limb_darkening::center_to_limb(&self.atmosphere, wavelength, mus)
+  }
+
+  // Linear, quadratic and four-parameter nonlinear laws fitted to the model
+  fn limb_darkening_laws(&self, wavelength: f64) -> Vec<LimbDarkeningLaw> {
+      let profile = self.limb_darkening_profile(wavelength, &limb_darkening::fit_mu_points());
+      [limb_darkening::fit_linear, limb_darkening::fit_quadratic, limb_darkening::fit_nonlinear]
+          .iter()
+          .filter_map(|fit| fit(&profile))
+          .collect()
+  }
+
+  // Disk-integrated (surface) flux F_lambda in W m^-2 m^-1
+  fn disk_integrated_flux(&self, grid: &SpectralGrid) -> Vec<(f64, f64)> {
+      grid.map_wavelengths(|wavelength| transfer::emergent_flux(&self.atmosphere, wavelength))
+  }
+
+  // Absolute magnitude in each band and the bolometric correction against
+  // it, from the disk-integrated flux sampled finely from the UV to the far IR
+  fn photometry(&self, filters: &[Filter]) -> Vec<(String, f64, f64)> {
+      let grid = SpectralGrid::logarithmic(SpectralUnit::Wavelength, PHOTOMETRY_MIN_WAVELENGTH, PHOTOMETRY_MAX_WAVELENGTH, PHOTOMETRY_POINTS);
+      let flux = self.disk_integrated_flux(&grid);
+      filters
+          .iter()
+          .map(|filter| {
+              (
+                  filter.name.clone(),
+                  filter.absolute_magnitude(&flux, self.radius),
+                  photometry::bolometric_correction(&flux, self.radius, filter),
+              )
+          })
+          .collect()
   }
 }
 
-// Constants for the photosphere properties
-const PHOTOSPHERE_RADIUS: f64 = 6.955e8; // meters
-const PHOTOSPHERE_TEMPERATURE: f64 = 5500.0; // Kelvin
+// Constants for the photosphere properties; radius and effective
+// temperature are the nominal solar values (IAU 2015 Resolution B3)
+const PHOTOSPHERE_RADIUS: f64 = 6.957e8; // meters
+const PHOTOSPHERE_TEMPERATURE: f64 = 5772.0; // Kelvin
 const PHOTOSPHERE_DENSITY: f64 = 1.0e-4; // kg/m^3
 const PHOTOSPHERE_ENERGY_GENERATION: f64 = 1.0e26; // W/m^3
-
-// Dummy function for blackbody intensity
-fn blackbody_intensity(_wavelength: f64, _temperature: f64) -> f64 {
-  // Placeholder implementation
-  1.0
-}
+const PHOTOSPHERE_SURFACE_GRAVITY: f64 = 274.0; // m/s^2
+const PHOTOSPHERE_MICROTURBULENCE: f64 = 1.0e3; // m/s
+const PHOTOMETRY_MIN_WAVELENGTH: f64 = 100.0e-9; // meters
+const PHOTOMETRY_MAX_WAVELENGTH: f64 = 100.0e-6; // meters
+const PHOTOMETRY_POINTS: usize = 3000;
+const MIXING_LENGTH_PARAMETER: f64 = 1.8; // mixing length in pressure scale heights
+const DISK_IMAGE_PIXELS: usize = 256;
+const LINE_FORMATION_DEPTH: f64 = 0.1; // typical continuum optical depth of line formation
 
 fn main() {
   // Create an instance of the Photosphere struct
-  let photosphere = Photosphere {
-      radius: PHOTOSPHERE_RADIUS,
-      temperature: PHOTOSPHERE_TEMPERATURE,
-      density: PHOTOSPHERE_DENSITY,
-      energy_generation: PHOTOSPHERE_ENERGY_GENERATION,
-  };
+  let photosphere = Photosphere::quiet_sun();
+
+  // Depth structure of the gray atmosphere
+  println!("{:>10} {:>10} {:>10} {:>12} {:>12}", "Height(km)", "Tau", "T(K)", "P(Pa)", "Rho(kg/m^3)");
+  for layer in photosphere.atmosphere.layers.iter().step_by(20) {
+      println!(
+          "{:>10.1} {:>10.2e} {:>10.1} {:>12.3e} {:>12.3e}",
+          layer.height / 1.0e3,
+          layer.optical_depth,
+          layer.temperature,
+          layer.pressure,
+          layer.density
+      );
+  }
+  let eddington = GrayAtmosphere::new(PHOTOSPHERE_TEMPERATURE, PHOTOSPHERE_SURFACE_GRAVITY, TemperatureLaw::Eddington);
+  println!(
+      "Boundary Temperature: {:.1} K (Hopf), {:.1} K (Eddington)",
+      photosphere.atmosphere.temperature_at_optical_depth(0.0),
+      eddington.temperature_at_optical_depth(0.0)
+  );
+
+  println!("Convective Velocity (mixing length): {:.0} m/s", photosphere.convective_velocity());
 
   // Example usage of methods
-  let core_radius = 6.96e8; // Example core radius
   let wavelength = 500.0e-9; // Example wavelength
-  let optical_depth = photosphere.calculate_optical_depth(core_radius, wavelength);
+  let optical_depth = photosphere.calculate_optical_depth(wavelength);
   println!("Optical Depth: {}", optical_depth);
 
-  let emission_spectrum = photosphere.get_emission_spectrum();
+  let emission_spectrum = photosphere.get_emission_spectrum(&SpectralGrid::visible());
   println!("Emission Spectrum: {:?}", emission_spectrum);
+
+  // Broadband spectrum from hard X-rays (10 keV) to radio (1 m)
+  let broadband_grid = SpectralGrid::logarithmic(SpectralUnit::Wavelength, 1.24e-10, 1.0, 400);
+  let (peak_wavelength, _) = photosphere
+      .get_emission_spectrum(&broadband_grid)
+      .into_iter()
+      .fold((0.0, 0.0), |peak, (w, i)| if i > peak.1 { (w, i) } else { peak });
+  println!("Peak Emission Wavelength: {:.1} nm", peak_wavelength * 1.0e9);
+
+  // Center-to-limb variation and fitted limb darkening laws at 500 nm
+  let mus = [1.0, 0.8, 0.6, 0.4, 0.2, 0.1];
+  for (mu, ratio) in photosphere.limb_darkening_profile(wavelength, &mus) {
+      println!("I(mu = {:.1}) / I(1) = {:.4}", mu, ratio);
+  }
+  for law in photosphere.limb_darkening_laws(wavelength) {
+      println!("Limb Darkening Law: {:?}, fitted I(0.4) / I(1) = {:.4}", law, law.intensity_ratio(0.4));
+  }
+
+  // Disk-integrated flux; its bolometric integral should recover sigma T_eff^4
+  let flux_grid = SpectralGrid::logarithmic(SpectralUnit::Wavelength, 50.0e-9, 100.0e-6, 400);
+  let flux_spectrum = photosphere.disk_integrated_flux(&flux_grid);
+  let bolometric_flux = radiation::numerics::trapezoid(&flux_spectrum);
+  println!(
+      "Bolometric Flux: {:.4e} W/m^2 (sigma T_eff^4 = {:.4e} W/m^2)",
+      bolometric_flux,
+      planck::STEFAN_BOLTZMANN_CONSTANT * photosphere.temperature.powi(4)
+  );
+
+  // Synthetic photometry through the bundled Johnson, SDSS and Gaia-like
+  // bands; filter files given on the command line join them
+  let mut filters: Vec<Filter> = ["U", "B", "V", "u", "g", "r", "i", "z", "G", "BP", "RP"]
+      .iter()
+      .filter_map(|band| photometry::filter(band))
+      .collect();
+  for path in std::env::args().skip(1) {
+      match photometry::load_filter(&path) {
+          Ok(filter) => filters.push(filter),
+          Err(error) => println!("Could not load filter {}: {}", path, error),
+      }
+  }
+  let magnitudes = photosphere.photometry(&filters);
+  for (band, magnitude, correction) in &magnitudes {
+      println!("Absolute Magnitude M_{}: {:.3} (BC = {:+.3})", band, magnitude, correction);
+  }
+  let magnitude = |band: &str| magnitudes.iter().find(|(b, _, _)| b == band).map_or(f64::NAN, |m| m.1);
+  println!(
+      "Colors: U-B = {:.3}, B-V = {:.3}, g-r = {:.3}, BP-RP = {:.3}",
+      magnitude("U") - magnitude("B"),
+      magnitude("B") - magnitude("V"),
+      magnitude("g") - magnitude("r"),
+      magnitude("BP") - magnitude("RP")
+  );
+  println!(
+      "Bolometric Magnitude: {:.3}",
+      photometry::bolometric_magnitude(&flux_spectrum, photosphere.radius)
+  );
+
+  // An active region: a sunspot group with surrounding faculae
+  let mut active = Photosphere::quiet_sun();
+  active.add_sunspot(Sunspot::new(15.0, -10.0, 500.0));
+  active.add_sunspot(Sunspot::new(12.0, 5.0, 200.0));
+  active.add_sunspot(Sunspot::new(-20.0, 55.0, 300.0));
+  for (latitude, longitude) in [(16.0, -20.0), (10.0, 12.0), (-18.0, 62.0), (-22.0, 48.0)] {
+      active.add_facula(Facula::new(latitude, longitude, 800.0));
+  }
+  for spot in &active.sunspots {
+      println!(
+          "Sunspot at ({:.0}, {:.0}) deg: {:.0} MSH, umbral flux {:.2e} Wb, magnetic pressure {:.2e} Pa, Wilson depression {:.0} km",
+          spot.latitude.to_degrees(),
+          spot.longitude.to_degrees(),
+          spot.area,
+          spot.umbral_flux(active.radius),
+          spot.magnetic_pressure(),
+          spot.wilson_depression / 1.0e3
+      );
+  }
+  let disk = active.disk_intensity(wavelength);
+  let image = disk.image(DISK_IMAGE_PIXELS);
+  let (darkest, brightest) = image
+      .iter()
+      .flatten()
+      .filter(|&&i| i > 0.0)
+      .fold((f64::INFINITY, 0.0_f64), |(low, high), &i| (low.min(i), high.max(i)));
+  println!(
+      "Disk Image ({0}x{0} px, 500 nm): darkest {1:.3}, brightest {2:.3} of disk-centre intensity",
+      DISK_IMAGE_PIXELS,
+      darkest / disk.quiet_intensity(1.0),
+      brightest / disk.quiet_intensity(1.0)
+  );
+  let quiet_visible = radiation::numerics::trapezoid(&photosphere.disk_integrated_flux(&SpectralGrid::visible()));
+  let active_visible = radiation::numerics::trapezoid(&active.active_flux(&SpectralGrid::visible()));
+  println!("Active Region Visible Flux Change: {:+.3}%", 100.0 * (active_visible / quiet_visible - 1.0));
+  let active_bolometric = radiation::numerics::trapezoid(&active.active_flux(&flux_grid));
+  println!(
+      "Active Region Bolometric Flux Change: {:+.1} ppm",
+      1.0e6 * (active_bolometric / bolometric_flux - 1.0)
+  );
+
+  // Fraunhofer lines from the bundled line list
+  let lines = line_list::default_line_list();
+  for line in &lines {
+      let line_grid = SpectralGrid::with_step(
+          SpectralUnit::Wavelength,
+          line.wavelength - 0.2e-9,
+          line.wavelength + 0.2e-9,
+          0.002e-9,
+      );
+      let profile = photosphere.get_absorption_line_spectrum(&line_grid, &lines);
+      let core_intensity = profile.iter().map(|&(_, i)| i).fold(f64::INFINITY, f64::min);
+      println!(
+          "{:<14} {:.4} nm: residual core intensity {:.3}, equivalent width {:.1} pm",
+          line.label,
+          line.wavelength * 1.0e9,
+          core_intensity,
+          line_synthesis::equivalent_width(&profile) * 1.0e12
+      );
+  }
+
+  // Full Stokes profiles of Fe I 630.25 nm (g_eff = 2.5) in a 0.1 T field
+  let fe_index = lines.iter().position(|l| l.label == "Fe I 630.25").expect("Fe I 630.25 in line list");
+  if let Some(model) = photosphere.milne_eddington(&lines, fe_index, 0.1, 30.0_f64.to_radians(), 0.0) {
+      let wavelengths: Vec<f64> = (-50..=50).map(|i| model.line_center + i as f64 * 1.0e-12).collect();
+      let profiles = model.stokes_profiles(&wavelengths, 1.0);
+      let continuum = profiles[0].1.i;
+      let peak = |f: &dyn Fn(&stokes::StokesVector) -> f64| profiles.iter().map(|(_, s)| f(s).abs()).fold(0.0, f64::max);
+      println!(
+          "Fe I 630.25 Stokes peaks (B = 0.1 T, inclination 30 deg): |Q|/Ic = {:.4}, |U|/Ic = {:.4}, |V|/Ic = {:.4}",
+          peak(&|s| s.q) / continuum,
+          peak(&|s| s.u) / continuum,
+          peak(&|s| s.v) / continuum
+      );
+
+      // Synthetic magnetogram from the same scan across the line
+      let field_map: Vec<Vec<(f64, f64)>> = [0.01, 0.03, 0.05]
+          .iter()
+          .map(|&b| [0.0_f64, 45.0, 90.0, 135.0].iter().map(|&g| (b, g.to_radians())).collect())
+          .collect();
+      let magnetogram = stokes::synthetic_magnetogram(&model, &field_map, &wavelengths);
+      for (fields, row) in field_map.iter().zip(&magnetogram) {
+          let recovered: Vec<String> = row.iter().map(|b| format!("{:+.4}", b)).collect();
+          let expected: Vec<String> = fields.iter().map(|(b, g)| format!("{:+.4}", b * g.cos())).collect();
+          println!("Magnetogram B_los (T): {} (true {})", recovered.join(" "), expected.join(" "));
+      }
+  }
+
+  let visible_flux = planck::band_flux(400.0e-9, 700.0e-9, photosphere.temperature);
+  println!("Visible (400-700 nm) Surface Flux: {:.3e} W/m^2", visible_flux);
 }
@@ -0,0 +1,183 @@
+// Elemental abundances, ionization energies and partition functions, with
+// the Saha-Boltzmann relations for LTE level populations.
+
+use super::planck::{BOLTZMANN_CONSTANT, PLANCK_CONSTANT};
+
+pub const ELECTRON_MASS: f64 = 9.1093837e-31; // kg
+pub const ELECTRON_VOLT: f64 = 1.602176634e-19; // J
+const ATOMIC_MASS_UNIT: f64 = 1.66053907e-27; // kg
+// Elements with a first ionization energy below this (eV) are ionized in the
+// chromosphere, where the first ionization potential (FIP) effect sorts them
+pub const LOW_FIP_LIMIT: f64 = 10.0;
+// Enhancement of the low-FIP elements in the closed-field corona (Feldman 1992)
+pub const CORONAL_FIP_BIAS: f64 = 4.0;
+
+#[derive(Debug)]
+pub struct Element {
+    pub symbol: &'static str,
+    pub atomic_number: u32,
+    pub mass_number: f64,        // atomic mass in amu
+    pub log_abundance: f64,      // log10(N/N_H) + 12 (Asplund et al. 2009)
+    pub ionization_energy: [f64; 2], // eV, neutral -> I and I -> II
+    pub partition_function: [f64; 3], // stages I, II, III near 5000-6000 K
+}
+
+pub const ELEMENTS: [Element; 15] = [
+    Element { symbol: "H", atomic_number: 1, mass_number: 1.008, log_abundance: 12.00, ionization_energy: [13.598, 0.0], partition_function: [2.0, 1.0, 1.0] },
+    Element { symbol: "He", atomic_number: 2, mass_number: 4.003, log_abundance: 10.93, ionization_energy: [24.587, 54.418], partition_function: [1.0, 2.0, 1.0] },
+    Element { symbol: "C", atomic_number: 6, mass_number: 12.011, log_abundance: 8.43, ionization_energy: [11.260, 24.383], partition_function: [9.3, 6.0, 1.0] },
+    Element { symbol: "N", atomic_number: 7, mass_number: 14.007, log_abundance: 7.83, ionization_energy: [14.534, 29.601], partition_function: [4.0, 8.9, 5.9] },
+    Element { symbol: "O", atomic_number: 8, mass_number: 15.999, log_abundance: 8.69, ionization_energy: [13.618, 35.121], partition_function: [8.7, 4.0, 9.0] },
+    Element { symbol: "Ne", atomic_number: 10, mass_number: 20.180, log_abundance: 7.93, ionization_energy: [21.565, 40.963], partition_function: [1.0, 5.4, 8.0] },
+    Element { symbol: "Na", atomic_number: 11, mass_number: 22.990, log_abundance: 6.24, ionization_energy: [5.139, 47.286], partition_function: [2.0, 1.0, 6.0] },
+    Element { symbol: "Mg", atomic_number: 12, mass_number: 24.305, log_abundance: 7.60, ionization_energy: [7.646, 15.035], partition_function: [1.0, 2.0, 1.0] },
+    Element { symbol: "Al", atomic_number: 13, mass_number: 26.982, log_abundance: 6.45, ionization_energy: [5.986, 18.829], partition_function: [5.9, 1.0, 2.0] },
+    Element { symbol: "Si", atomic_number: 14, mass_number: 28.086, log_abundance: 7.51, ionization_energy: [8.152, 16.346], partition_function: [9.5, 5.7, 1.0] },
+    Element { symbol: "S", atomic_number: 16, mass_number: 32.06, log_abundance: 7.12, ionization_energy: [10.360, 23.338], partition_function: [8.5, 4.0, 8.0] },
+    Element { symbol: "K", atomic_number: 19, mass_number: 39.098, log_abundance: 5.03, ionization_energy: [4.341, 31.63], partition_function: [2.2, 1.0, 6.0] },
+    Element { symbol: "Ca", atomic_number: 20, mass_number: 40.078, log_abundance: 6.34, ionization_energy: [6.113, 11.872], partition_function: [1.3, 2.2, 1.0] },
+    Element { symbol: "Fe", atomic_number: 26, mass_number: 55.845, log_abundance: 7.50, ionization_energy: [7.902, 16.199], partition_function: [30.0, 45.0, 25.0] },
+    Element { symbol: "Ni", atomic_number: 28, mass_number: 58.693, log_abundance: 6.22, ionization_energy: [7.640, 18.169], partition_function: [29.0, 10.0, 21.0] },
+];
+
+pub fn element(symbol: &str) -> Option<&'static Element> {
+    ELEMENTS.iter().find(|e| e.symbol.eq_ignore_ascii_case(symbol))
+}
+
+// Number abundances of the element table, e.g. photospheric or coronal
+#[derive(Clone, Debug)]
+pub struct AbundanceSet {
+    pub name: String,
+    pub log_abundances: [f64; 15], // log10(N/N_H) + 12, in the order of ELEMENTS
+}
+
+impl AbundanceSet {
+    /// The photospheric abundances of the element table.
+    pub fn photospheric() -> Self {
+        AbundanceSet {
+            name: "photospheric".to_string(),
+            log_abundances: ELEMENTS.map(|e| e.log_abundance),
+        }
+    }
+
+    /// Photospheric abundances with the low-FIP elements enhanced by `bias`.
+    pub fn with_fip_bias(name: &str, bias: f64) -> Self {
+        AbundanceSet {
+            name: name.to_string(),
+            log_abundances: ELEMENTS.map(|e| {
+                if e.symbol != "H" && e.ionization_energy[0] < LOW_FIP_LIMIT {
+                    e.log_abundance + bias.log10()
+                } else {
+                    e.log_abundance
+                }
+            }),
+        }
+    }
+
+    /// Typical closed-field coronal abundances.
+    pub fn coronal() -> Self {
+        Self::with_fip_bias("coronal", CORONAL_FIP_BIAS)
+    }
+
+    /// Number abundance of `element` relative to hydrogen.
+    pub fn abundance(&self, element: &Element) -> f64 {
+        ELEMENTS
+            .iter()
+            .position(|e| e.symbol == element.symbol)
+            .map_or(0.0, |i| 10f64.powf(self.log_abundances[i] - 12.0))
+    }
+}
+
+impl Element {
+    // Number abundance relative to hydrogen
+    pub fn abundance(&self) -> f64 {
+        10f64.powf(self.log_abundance - 12.0)
+    }
+
+    /// Fractions of the element in ionization stages I, II and III.
+    pub fn ionization_fractions(&self, temperature: f64, electron_density: f64) -> [f64; 3] {
+        let [u1, u2, u3] = self.partition_function;
+        let first = saha_ratio(u1, u2, self.ionization_energy[0], temperature, electron_density);
+        let second = if self.ionization_energy[1] > 0.0 {
+            saha_ratio(u2, u3, self.ionization_energy[1], temperature, electron_density)
+        } else {
+            0.0
+        };
+        let neutral = 1.0 / (1.0 + first + first * second);
+        [neutral, neutral * first, neutral * first * second]
+    }
+}
+
+/// Saha equation: ratio n_(i+1) / n_i of two successive ionization stages.
+///
+/// # Arguments
+///
+/// * `lower_partition` - Partition function of the lower stage.
+/// * `upper_partition` - Partition function of the upper stage.
+/// * `ionization_energy` - Ionization energy of the lower stage in eV.
+/// * `temperature` - The temperature in Kelvin.
+/// * `electron_density` - The electron number density in m^-3.
+pub fn saha_ratio(
+    lower_partition: f64,
+    upper_partition: f64,
+    ionization_energy: f64,
+    temperature: f64,
+    electron_density: f64,
+) -> f64 {
+    let thermal = 2.0 * std::f64::consts::PI * ELECTRON_MASS * BOLTZMANN_CONSTANT * temperature
+        / (PLANCK_CONSTANT * PLANCK_CONSTANT);
+    2.0 * upper_partition / lower_partition * thermal.powf(1.5)
+        * (-ionization_energy * ELECTRON_VOLT / (BOLTZMANN_CONSTANT * temperature)).exp()
+        / electron_density
+}
+
+/// Boltzmann factor g * exp(-E / kT) / U for a level of an ionization stage.
+pub fn boltzmann_fraction(statistical_weight: f64, excitation_energy: f64, partition: f64, temperature: f64) -> f64 {
+    statistical_weight * (-excitation_energy * ELECTRON_VOLT / (BOLTZMANN_CONSTANT * temperature)).exp() / partition
+}
+
+/// Mass per hydrogen nucleus of the mixture, in kg.
+pub fn mass_per_hydrogen() -> f64 {
+    ELEMENTS.iter().map(|e| e.abundance() * e.mass_number).sum::<f64>() * ATOMIC_MASS_UNIT
+}
+
+/// Total hydrogen nuclei density (m^-3) of gas with mass density `density` (kg/m^3).
+pub fn hydrogen_density(density: f64) -> f64 {
+    density / mass_per_hydrogen()
+}
+
+/// Electron density in LTE from charge conservation over the element table.
+///
+/// # Arguments
+///
+/// * `temperature` - The temperature in Kelvin.
+/// * `hydrogen_density` - The total hydrogen nuclei density in m^-3.
+///
+/// # Returns
+///
+/// The electron number density in m^-3.
+pub fn electron_density(temperature: f64, hydrogen_density: f64) -> f64 {
+    let electrons_supplied = |electron_density: f64| -> f64 {
+        ELEMENTS
+            .iter()
+            .map(|e| {
+                let [_, single, double] = e.ionization_fractions(temperature, electron_density);
+                e.abundance() * (single + 2.0 * double)
+            })
+            .sum::<f64>()
+            * hydrogen_density
+    };
+
+    // The supply falls as n_e rises, so bisect in log(n_e) for the crossing
+    let mut low = (hydrogen_density * 1.0e-12).ln();
+    let mut high = (hydrogen_density * 2.5).ln();
+    for _ in 0..100 {
+        let mid = 0.5 * (low + high);
+        if electrons_supplied(mid.exp()) > mid.exp() {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    (0.5 * (low + high)).exp()
+}
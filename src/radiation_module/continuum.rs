@@ -0,0 +1,199 @@
+// Continuous opacity of a hydrogen-dominated gas: H- bound-free and
+// free-free absorption, bound-free absorption from the hydrogen levels and
+// from the ground states of the neutral metals, hydrogen free-free
+// absorption and Thomson scattering.
+//
+// Level and H- populations are taken in LTE with respect to the neutral
+// hydrogen density, so the caller decides how ionized hydrogen is; the
+// metals follow the Saha equation at the given electron density.
+
+use super::atomic_data::{self, ELECTRON_MASS, ELECTRON_VOLT};
+use super::planck::{BOLTZMANN_CONSTANT, PLANCK_CONSTANT, SPEED_OF_LIGHT};
+
+pub const THOMSON_CROSS_SECTION: f64 = 6.6524587e-29; // m^2
+pub const HYDROGEN_IONIZATION_ENERGY: f64 = 13.598; // eV
+pub const H_MINUS_BINDING_ENERGY: f64 = 0.754; // eV
+
+// Photoionization threshold of H- (m)
+const H_MINUS_THRESHOLD: f64 = 1.6419e-6;
+// Range of the H- polynomial fits (Gray 2005), in m
+const H_MINUS_BOUND_FREE_MIN: f64 = 225.0e-9;
+const H_MINUS_FREE_FREE_MAX: f64 = 11.39e-6;
+// Kramers cross section of the hydrogen ground state at threshold (m^2)
+const KRAMERS_CROSS_SECTION: f64 = 7.91e-22;
+// Lyman limit (m); level n has its threshold at n^2 times this
+const LYMAN_LIMIT: f64 = 91.18e-9;
+// Hydrogen levels included in the bound-free sum
+const BOUND_FREE_LEVELS: u32 = 8;
+// Metals whose ground-term edges shape the UV continuum between the Lyman
+// limit and ~210 nm: statistical weight of the ground term and a
+// representative photoionization cross section at threshold (m^2)
+const METAL_EDGES: [(&str, f64, f64); 6] = [
+    ("C", 9.0, 1.2e-21),
+    ("Mg", 1.0, 1.2e-22),
+    ("Al", 6.0, 6.5e-21),
+    ("Si", 9.0, 3.7e-21),
+    ("Ca", 1.0, 4.6e-22),
+    ("Fe", 25.0, 5.0e-22),
+];
+
+// Plasma state that sets the continuous opacity
+#[derive(Clone, Copy, Debug)]
+pub struct ContinuumState {
+    pub temperature: f64,              // K
+    pub electron_density: f64,         // m^-3
+    pub neutral_hydrogen_density: f64, // m^-3
+    pub proton_density: f64,           // m^-3
+}
+
+/// H- number density (m^-3) from the Saha equation for H + e <-> H-,
+/// with statistical weights 1 for H- and 2 for the hydrogen ground state.
+pub fn h_minus_density(temperature: f64, neutral_hydrogen_density: f64, electron_density: f64) -> f64 {
+    let thermal = PLANCK_CONSTANT * PLANCK_CONSTANT
+        / (2.0 * std::f64::consts::PI * ELECTRON_MASS * BOLTZMANN_CONSTANT * temperature);
+    0.25 * neutral_hydrogen_density
+        * electron_density
+        * thermal.powf(1.5)
+        * (H_MINUS_BINDING_ENERGY * ELECTRON_VOLT / (BOLTZMANN_CONSTANT * temperature)).exp()
+}
+
+/// H- photoionization cross section (m^2), from the polynomial fit of
+/// Gray (2005); shortward of the fit range the value at its edge is used.
+pub fn h_minus_bound_free_cross_section(wavelength: f64) -> f64 {
+    if wavelength >= H_MINUS_THRESHOLD {
+        return 0.0;
+    }
+    const COEFFICIENTS: [f64; 7] = [
+        1.99654,
+        -1.18267e-5,
+        2.64243e-6,
+        -4.40524e-10,
+        3.23992e-14,
+        -1.39568e-18,
+        2.78701e-23,
+    ];
+    let angstrom = wavelength.max(H_MINUS_BOUND_FREE_MIN) * 1.0e10;
+    let cgs = COEFFICIENTS.iter().rev().fold(0.0, |sum, &c| sum * angstrom + c) * 1.0e-18;
+    (cgs * 1.0e-4).max(0.0)
+}
+
+/// H- free-free absorption (m^2 per neutral hydrogen atom), stimulated
+/// emission included, from the fit of Gray (2005). Beyond the fit range the
+/// lambda^2 dependence of free-free absorption carries it on.
+pub fn h_minus_free_free_cross_section(wavelength: f64, temperature: f64, electron_density: f64) -> f64 {
+    let angstrom = wavelength.min(H_MINUS_FREE_FREE_MAX) * 1.0e10;
+    let log_lambda = angstrom.log10();
+    let log_theta = (5040.0 / temperature).log10();
+    let f0 = -2.2763 - 1.6850 * log_lambda + 0.76661 * log_lambda.powi(2) - 0.053346 * log_lambda.powi(3);
+    let f1 = 15.2827 - 9.2846 * log_lambda + 1.99381 * log_lambda.powi(2) - 0.142631 * log_lambda.powi(3);
+    let f2 = -197.789 + 190.266 * log_lambda - 67.9775 * log_lambda.powi(2) + 10.6913 * log_lambda.powi(3)
+        - 0.625151 * log_lambda.powi(4);
+    // Electron pressure in dyn/cm^2
+    let electron_pressure = electron_density * BOLTZMANN_CONSTANT * temperature * 10.0;
+    let cgs = 1.0e-26 * electron_pressure * 10f64.powf(f0 + f1 * log_theta + f2 * log_theta * log_theta);
+    let extrapolation = (wavelength / H_MINUS_FREE_FREE_MAX).max(1.0).powi(2);
+    cgs * 1.0e-4 * extrapolation
+}
+
+/// Photoionization cross section (m^2) of hydrogen level `level` in the
+/// Kramers approximation with unit Gaunt factor.
+pub fn hydrogen_bound_free_cross_section(level: u32, wavelength: f64) -> f64 {
+    let n = level as f64;
+    let threshold = n * n * LYMAN_LIMIT;
+    if wavelength > threshold {
+        return 0.0;
+    }
+    KRAMERS_CROSS_SECTION * n * (wavelength / threshold).powi(3)
+}
+
+/// LTE population (m^-3) of hydrogen level `level` out of the neutral
+/// hydrogen density, with g = 2 n^2 and a partition function of 2.
+pub fn hydrogen_level_population(level: u32, temperature: f64, neutral_hydrogen_density: f64) -> f64 {
+    let n = level as f64;
+    let excitation = HYDROGEN_IONIZATION_ENERGY * (1.0 - 1.0 / (n * n)) * ELECTRON_VOLT;
+    neutral_hydrogen_density * n * n * (-excitation / (BOLTZMANN_CONSTANT * temperature)).exp()
+}
+
+/// Ground-state photoionization of the neutral metals (m^-1), with the
+/// cross section falling as lambda^3 from the ionization edge.
+pub fn metal_bound_free_absorption(
+    wavelength: f64,
+    temperature: f64,
+    electron_density: f64,
+    hydrogen_density: f64,
+) -> f64 {
+    METAL_EDGES
+        .iter()
+        .filter_map(|&(symbol, weight, cross_section)| atomic_data::element(symbol).map(|e| (e, weight, cross_section)))
+        .map(|(element, weight, cross_section)| {
+            let threshold = PLANCK_CONSTANT * SPEED_OF_LIGHT / (element.ionization_energy[0] * ELECTRON_VOLT);
+            if wavelength > threshold {
+                return 0.0;
+            }
+            let [neutral, _, _] = element.ionization_fractions(temperature, electron_density);
+            let ground =
+                atomic_data::boltzmann_fraction(weight, 0.0, element.partition_function[0], temperature).min(1.0);
+            element.abundance() * hydrogen_density * neutral * ground * cross_section * (wavelength / threshold).powi(3)
+        })
+        .sum()
+}
+
+/// Free-free absorption coefficient (m^-1) of electrons on singly charged
+/// ions, with unit Gaunt factor and stimulated emission included.
+pub fn free_free_absorption(wavelength: f64, temperature: f64, electron_density: f64, ion_density: f64) -> f64 {
+    let frequency = SPEED_OF_LIGHT / wavelength;
+    3.7e-2 * electron_density * ion_density * stimulated_emission_factor(wavelength, temperature)
+        / (temperature.sqrt() * frequency.powi(3))
+}
+
+// 1 - exp(-h nu / kT)
+fn stimulated_emission_factor(wavelength: f64, temperature: f64) -> f64 {
+    -(-PLANCK_CONSTANT * SPEED_OF_LIGHT / (wavelength * BOLTZMANN_CONSTANT * temperature)).exp_m1()
+}
+
+/// Continuous absorption coefficient (m^-1) at `wavelength`: every process
+/// that destroys photons, with stimulated emission included.
+pub fn absorption(state: &ContinuumState, wavelength: f64) -> f64 {
+    background_absorption(state, wavelength) + hydrogen_bound_free_absorption(state, wavelength)
+}
+
+/// Bound-free absorption (m^-1) from the LTE hydrogen levels, stimulated
+/// emission included.
+pub fn hydrogen_bound_free_absorption(state: &ContinuumState, wavelength: f64) -> f64 {
+    (1..=BOUND_FREE_LEVELS)
+        .map(|level| {
+            hydrogen_level_population(level, state.temperature, state.neutral_hydrogen_density)
+                * hydrogen_bound_free_cross_section(level, wavelength)
+        })
+        .sum::<f64>()
+        * stimulated_emission_factor(wavelength, state.temperature)
+}
+
+/// Continuous absorption (m^-1) from everything but the hydrogen bound-free
+/// continua, the background for a solver that treats hydrogen itself.
+pub fn background_absorption(state: &ContinuumState, wavelength: f64) -> f64 {
+    let temperature = state.temperature;
+    let stimulated = stimulated_emission_factor(wavelength, temperature);
+
+    let h_minus = h_minus_density(temperature, state.neutral_hydrogen_density, state.electron_density)
+        * h_minus_bound_free_cross_section(wavelength)
+        * stimulated
+        + state.neutral_hydrogen_density
+            * h_minus_free_free_cross_section(wavelength, temperature, state.electron_density);
+    let hydrogen_density = state.neutral_hydrogen_density + state.proton_density;
+    let metals =
+        metal_bound_free_absorption(wavelength, temperature, state.electron_density, hydrogen_density) * stimulated;
+    let free_free = free_free_absorption(wavelength, temperature, state.electron_density, state.proton_density);
+
+    h_minus + metals + free_free
+}
+
+/// Thomson scattering coefficient (m^-1).
+pub fn scattering(state: &ContinuumState) -> f64 {
+    THOMSON_CROSS_SECTION * state.electron_density
+}
+
+/// Total continuous extinction coefficient (m^-1) at `wavelength`.
+pub fn extinction(state: &ContinuumState, wavelength: f64) -> f64 {
+    absorption(state, wavelength) + scattering(state)
+}
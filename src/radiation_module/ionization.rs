@@ -0,0 +1,280 @@
+// Collisional ionization equilibrium of the coronal ions.
+//
+// In the low-density corona every ion is ionized by electron collisions and
+// recombines radiatively or dielectronically; photoionization and
+// three-body recombination are negligible, so the charge state
+// distribution depends on the temperature alone:
+//
+//   n_(z+1) / n_z = S_z(T) / alpha_(z+1)(T)
+//
+// The rate formulas and the per-ion data are documented in
+// data/atomic/ionization.dat.
+
+use super::atomic_data::{self, Element};
+use super::numerics::exponential_integral;
+use super::planck::BOLTZMANN_CONSTANT;
+
+use std::error::Error;
+use std::fs;
+
+// Ion data shipped with the repository
+const BUNDLED_IONIZATION: &str = include_str!("../../data/atomic/ionization.dat");
+
+// Lotz constant 6.69e7 a with a = 4.5e-14 cm^2 eV^2, in cm^3 s^-1 eV^(3/2)
+const LOTZ_CONSTANT: f64 = 6.69e7 * 4.5e-14;
+// cm^3 s^-1 to m^3 s^-1
+const CGS_RATE_TO_SI: f64 = 1.0e-6;
+const RYDBERG_ENERGY: f64 = 13.605693; // eV
+
+// One ionization stage of an element
+#[derive(Clone, Debug)]
+pub struct Ion {
+    pub element: &'static Element,
+    pub charge: u32,                       // 0 for the neutral atom
+    pub ionization_potential: f64,         // eV
+    pub outer_electrons: f64,              // electrons in the outer subshell
+    pub inner_binding: f64,                // eV, binding energy of the next subshell (0 if none)
+    pub inner_electrons: f64,              // electrons in that subshell
+    pub core_transitions: Vec<(f64, f64)>, // (energy in eV, oscillator strength) for dielectronic capture
+}
+
+// Every ionization stage of one element, ordered by charge
+#[derive(Clone, Debug)]
+pub struct ElementIons {
+    pub element: &'static Element,
+    pub ions: Vec<Ion>,
+}
+
+#[derive(Clone, Debug)]
+pub struct IonizationData {
+    pub elements: Vec<ElementIons>,
+}
+
+impl Ion {
+    /// Spectroscopic name such as "Fe IX".
+    pub fn name(&self) -> String {
+        format!("{} {}", self.element.symbol, roman_numeral(self.charge + 1))
+    }
+
+    /// Collisional ionization rate coefficient (m^3 s^-1) at `temperature` (K).
+    pub fn ionization_rate(&self, temperature: f64) -> f64 {
+        let thermal = BOLTZMANN_CONSTANT * temperature / atomic_data::ELECTRON_VOLT;
+        let subshell = |binding: f64, electrons: f64| -> f64 {
+            if binding <= 0.0 || electrons <= 0.0 {
+                return 0.0;
+            }
+            let x = binding / thermal;
+            if x > 700.0 {
+                return 0.0;
+            }
+            LOTZ_CONSTANT * electrons * thermal.powf(-1.5) * exponential_integral(x) / x
+        };
+        (subshell(self.ionization_potential, self.outer_electrons) + subshell(self.inner_binding, self.inner_electrons))
+            * CGS_RATE_TO_SI
+    }
+
+    /// Rate coefficient (m^3 s^-1) for recombination onto this ion, which
+    /// must carry a charge.
+    pub fn recombination_rate(&self, temperature: f64) -> f64 {
+        let z = self.charge as f64;
+        let thermal = BOLTZMANN_CONSTANT * temperature / atomic_data::ELECTRON_VOLT;
+        let shape = z.sqrt() * (z + 1.0).powf(2.5) / (z * z + 13.4).sqrt();
+        let beta = 1.0 / (1.0 + 0.015 * z.powi(3) / ((z + 1.0) * (z + 1.0)));
+        let dielectronic = self
+            .core_transitions
+            .iter()
+            .map(|&(energy, strength)| {
+                let x = energy / RYDBERG_ENERGY / (z + 1.0);
+                strength * x.sqrt() / (1.0 + 0.105 * x + 0.015 * x * x) * (-beta * energy / thermal).exp()
+            })
+            .sum::<f64>()
+            * 3.0e-3
+            * temperature.powf(-1.5)
+            * shape;
+        (radiative_recombination(z, temperature) + dielectronic) * CGS_RATE_TO_SI
+    }
+}
+
+impl ElementIons {
+    /// Ionization rates of stages 0..Z-1 and recombination rates onto
+    /// stages 1..Z (m^3 s^-1), so that entry i of each couples charges i and
+    /// i + 1.
+    pub fn rates(&self, temperature: f64) -> (Vec<f64>, Vec<f64>) {
+        let ionization = self.ions.iter().map(|ion| ion.ionization_rate(temperature)).collect();
+        let mut recombination: Vec<f64> = self.ions[1..]
+            .iter()
+            .map(|ion| ion.recombination_rate(temperature))
+            .collect();
+        // The bare nucleus recombines like a hydrogenic ion
+        let nuclear_charge = self.element.atomic_number as f64;
+        recombination.push(radiative_recombination(nuclear_charge, temperature) * CGS_RATE_TO_SI);
+        (ionization, recombination)
+    }
+
+    /// Equilibrium fractions of the charges 0..=Z at `temperature` (K).
+    pub fn equilibrium(&self, temperature: f64) -> Vec<f64> {
+        let (ionization, recombination) = self.rates(temperature);
+        // Work with logarithms: the ratios span hundreds of decades
+        let mut log_populations = vec![0.0];
+        for (up, down) in ionization.iter().zip(&recombination) {
+            let last = log_populations[log_populations.len() - 1];
+            log_populations.push(last + up.max(f64::MIN_POSITIVE).ln() - down.ln());
+        }
+        let largest = log_populations.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        let populations: Vec<f64> = log_populations.iter().map(|l| (l - largest).exp()).collect();
+        let total: f64 = populations.iter().sum();
+        populations.iter().map(|p| p / total).collect()
+    }
+
+    /// Mean charge of the element in equilibrium at `temperature` (K).
+    pub fn mean_charge(&self, temperature: f64) -> f64 {
+        self.equilibrium(temperature)
+            .iter()
+            .enumerate()
+            .map(|(charge, fraction)| charge as f64 * fraction)
+            .sum()
+    }
+}
+
+impl IonizationData {
+    /// The C, O, Ne, Mg, Si and Fe ions bundled with the repository.
+    pub fn bundled() -> Self {
+        parse_ionization_data("bundled ionization data", BUNDLED_IONIZATION)
+            .expect("bundled ionization table is well formed")
+    }
+
+    /// All stages of `element`, or `None` if it is not tabulated.
+    pub fn element(&self, element: &Element) -> Option<&ElementIons> {
+        self.elements.iter().find(|e| e.element.symbol == element.symbol)
+    }
+
+    /// Equilibrium fraction of `element` in the ion of the given `charge`,
+    /// zero if the element is not tabulated.
+    pub fn ion_fraction(&self, element: &Element, charge: u32, temperature: f64) -> f64 {
+        self.element(element)
+            .and_then(|ions| ions.equilibrium(temperature).get(charge as usize).copied())
+            .unwrap_or(0.0)
+    }
+
+    /// Temperature (K) of the largest equilibrium fraction of an ion,
+    /// searched over 10^4 to 10^8 K in steps of 0.01 dex.
+    pub fn peak_temperature(&self, element: &Element, charge: u32) -> Option<f64> {
+        let ions = self.element(element)?;
+        (0..=400)
+            .map(|i| 10f64.powf(4.0 + 0.01 * i as f64))
+            .map(|t| (t, ions.equilibrium(t).get(charge as usize).copied().unwrap_or(0.0)))
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(t, _)| t)
+    }
+}
+
+// Hydrogenic radiative recombination onto charge z (Seaton 1959), cm^3 s^-1
+fn radiative_recombination(z: f64, temperature: f64) -> f64 {
+    let lambda = 157890.0 * z * z / temperature;
+    5.197e-14 * z * lambda.sqrt() * (0.4288 + 0.5 * lambda.ln() + 0.469 * lambda.powf(-1.0 / 3.0))
+}
+
+/// Roman numeral of a spectroscopic stage, e.g. 9 -> "IX".
+pub fn roman_numeral(stage: u32) -> String {
+    const NUMERALS: [(u32, &str); 7] = [
+        (50, "L"),
+        (40, "XL"),
+        (10, "X"),
+        (9, "IX"),
+        (5, "V"),
+        (4, "IV"),
+        (1, "I"),
+    ];
+    let mut remaining = stage;
+    let mut numeral = String::new();
+    for (value, symbol) in NUMERALS {
+        while remaining >= value {
+            numeral.push_str(symbol);
+            remaining -= value;
+        }
+    }
+    numeral
+}
+
+/// Spectroscopic stage of a Roman numeral, e.g. "IX" -> 9.
+pub fn parse_roman_numeral(numeral: &str) -> Option<u32> {
+    (1..=60).find(|&stage| roman_numeral(stage) == numeral)
+}
+
+/// Parses an ion table (format documented in data/atomic/ionization.dat).
+pub fn parse_ionization_data(name: &str, text: &str) -> Result<IonizationData, Box<dyn Error>> {
+    let mut elements: Vec<ElementIons> = Vec::new();
+    for (number, raw) in text.lines().enumerate() {
+        let content = raw.split('#').next().unwrap_or("");
+        let fields: Vec<&str> = content.split_whitespace().collect();
+        if fields.is_empty() {
+            continue;
+        }
+        if fields.len() < 10 {
+            return Err(format!(
+                "{} line {}: expected 10 columns, found {}",
+                name,
+                number + 1,
+                fields.len()
+            )
+            .into());
+        }
+        let value = |i: usize| -> Result<f64, Box<dyn Error>> {
+            fields[i]
+                .parse::<f64>()
+                .map_err(|e| format!("{} line {}, column {}: {}", name, number + 1, i + 1, e).into())
+        };
+        let element = atomic_data::element(fields[0])
+            .ok_or_else(|| format!("{} line {}: unknown element {}", name, number + 1, fields[0]))?;
+        let stage = parse_roman_numeral(fields[1])
+            .ok_or_else(|| format!("{} line {}: bad ionization stage {}", name, number + 1, fields[1]))?;
+        let core_transitions = [(value(6)?, value(7)?), (value(8)?, value(9)?)]
+            .into_iter()
+            .filter(|&(energy, strength)| energy > 0.0 && strength > 0.0)
+            .collect();
+        let ion = Ion {
+            element,
+            charge: stage - 1,
+            ionization_potential: value(2)?,
+            outer_electrons: value(3)?,
+            inner_binding: value(4)?,
+            inner_electrons: value(5)?,
+            core_transitions,
+        };
+        if ion.ionization_potential <= 0.0 || ion.outer_electrons <= 0.0 {
+            return Err(format!(
+                "{} line {}: ionization potential and outer electrons must be positive",
+                name,
+                number + 1
+            )
+            .into());
+        }
+        match elements.iter_mut().find(|e| e.element.symbol == element.symbol) {
+            Some(existing) => existing.ions.push(ion),
+            None => elements.push(ElementIons {
+                element,
+                ions: vec![ion],
+            }),
+        }
+    }
+    for ions in &mut elements {
+        ions.ions.sort_by_key(|ion| ion.charge);
+        let complete = ions.ions.len() == ions.element.atomic_number as usize
+            && ions.ions.iter().enumerate().all(|(i, ion)| ion.charge as usize == i);
+        if !complete {
+            return Err(format!(
+                "{}: {} needs every stage from I to {}",
+                name,
+                ions.element.symbol,
+                roman_numeral(ions.element.atomic_number)
+            )
+            .into());
+        }
+    }
+    Ok(IonizationData { elements })
+}
+
+/// Loads an ion table from a local file.
+pub fn load_ionization_data(path: &str) -> Result<IonizationData, Box<dyn Error>> {
+    parse_ionization_data(path, &fs::read_to_string(path)?)
+}
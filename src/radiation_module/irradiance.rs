@@ -0,0 +1,133 @@
+// Spectral and total irradiance of a spherically symmetric star.
+//
+// The specific intensity leaving the stellar surface is carried through the
+// shells above it along rays of constant impact parameter, and the emergent
+// intensity is integrated over the projected disk and the emission beyond
+// the limb: E = (1 / d^2) * integral of I(p) 2 pi p dp.
+
+use super::numerics::{gauss_legendre_unit_interval, trapezoid};
+
+pub const ASTRONOMICAL_UNIT: f64 = 1.495978707e11; // m
+
+// Homogeneous spherical shell at one wavelength
+#[derive(Clone, Copy, Debug)]
+pub struct SphericalShell {
+    pub inner_radius: f64,    // m
+    pub outer_radius: f64,    // m
+    pub optical_depth: f64,   // radial optical thickness of the whole shell
+    pub source_function: f64, // W m^-2 sr^-1 m^-1
+}
+
+impl SphericalShell {
+    // Geometric path through the near half of the shell along impact parameter p
+    fn path_length(&self, impact_parameter: f64) -> f64 {
+        let chord = |radius: f64| (radius * radius - impact_parameter * impact_parameter).max(0.0).sqrt();
+        chord(self.outer_radius) - chord(self.inner_radius)
+    }
+
+    // Intensity after crossing one half of the shell
+    fn attenuate(&self, intensity: f64, impact_parameter: f64) -> f64 {
+        let thickness = self.outer_radius - self.inner_radius;
+        if thickness <= 0.0 {
+            return intensity;
+        }
+        let tau = self.optical_depth * self.path_length(impact_parameter) / thickness;
+        let transmission = (-tau).exp();
+        intensity * transmission + self.source_function * (1.0 - transmission)
+    }
+}
+
+/// Emergent intensity along the ray with impact parameter `impact_parameter` (m).
+///
+/// Rays that hit the surface start from its intensity at the local mu; rays
+/// beyond the limb cross the far and then the near half of every shell.
+/// `shells` are ordered from the innermost outward.
+pub fn ray_intensity<F: Fn(f64) -> f64>(
+    surface_radius: f64,
+    surface_intensity: &F,
+    shells: &[SphericalShell],
+    impact_parameter: f64,
+) -> f64 {
+    let mut intensity = if impact_parameter < surface_radius {
+        let mu = (1.0 - (impact_parameter / surface_radius).powi(2)).sqrt();
+        surface_intensity(mu)
+    } else {
+        shells
+            .iter()
+            .rev()
+            .fold(0.0, |intensity, shell| shell.attenuate(intensity, impact_parameter))
+    };
+    for shell in shells {
+        intensity = shell.attenuate(intensity, impact_parameter);
+    }
+    intensity
+}
+
+/// Spectral irradiance (W m^-2 m^-1) at `distance` (m) from the centre of
+/// the star, integrated over the disk and the shells beyond the limb.
+///
+/// # Arguments
+///
+/// * `surface_radius` - Radius of the optically thick surface in meters.
+/// * `surface_intensity` - Emergent surface intensity as a function of mu.
+/// * `shells` - Shells above the surface, innermost first.
+/// * `distance` - Distance of the observer in meters.
+pub fn disk_integrated_irradiance<F: Fn(f64) -> f64>(
+    surface_radius: f64,
+    surface_intensity: &F,
+    shells: &[SphericalShell],
+    distance: f64,
+) -> f64 {
+    let nodes = gauss_legendre_unit_interval();
+
+    // On the disk p dp = -R^2 mu dmu
+    let disk: f64 = nodes
+        .iter()
+        .map(|&(mu, weight)| {
+            let impact_parameter = surface_radius * (1.0 - mu * mu).sqrt();
+            weight * mu * ray_intensity(surface_radius, surface_intensity, shells, impact_parameter)
+        })
+        .sum::<f64>()
+        * surface_radius
+        * surface_radius;
+
+    // Beyond the limb, one quadrature per shell
+    let mut limb = 0.0;
+    let mut inner = surface_radius;
+    for shell in shells {
+        let outer = shell.outer_radius.max(inner);
+        let width = outer - inner;
+        limb += nodes
+            .iter()
+            .map(|&(x, weight)| {
+                let impact_parameter = inner + x * width;
+                weight * impact_parameter * ray_intensity(surface_radius, surface_intensity, shells, impact_parameter)
+            })
+            .sum::<f64>()
+            * width;
+        inner = outer;
+    }
+
+    2.0 * std::f64::consts::PI * (disk + limb) / (distance * distance)
+}
+
+/// Rescales a spectral irradiance measured at `from` to the distance `to`.
+pub fn scale_to_distance(spectrum: &[(f64, f64)], from: f64, to: f64) -> Vec<(f64, f64)> {
+    let factor = (from / to).powi(2);
+    spectrum.iter().map(|&(wavelength, value)| (wavelength, value * factor)).collect()
+}
+
+/// Total irradiance (W/m^2) of a spectral irradiance given as (wavelength in m, W m^-2 m^-1) pairs.
+pub fn total_irradiance(spectrum: &[(f64, f64)]) -> f64 {
+    trapezoid(spectrum)
+}
+
+/// Irradiance (W/m^2) between two wavelengths (m).
+pub fn band_irradiance(spectrum: &[(f64, f64)], start: f64, end: f64) -> f64 {
+    let band: Vec<(f64, f64)> = spectrum
+        .iter()
+        .copied()
+        .filter(|&(wavelength, _)| wavelength >= start && wavelength <= end)
+        .collect();
+    trapezoid(&band)
+}
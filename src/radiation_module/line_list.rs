@@ -0,0 +1,108 @@
+// Atomic line lists: parsing, damping widths and the bundled default list.
+
+use super::atomic_data::{self, Element};
+use super::planck::SPEED_OF_LIGHT;
+use super::zeeman::ZeemanLevels;
+
+use std::error::Error;
+use std::fs;
+
+// Line list shipped with the repository (data/atomic/line_list.dat)
+const DEFAULT_LINE_LIST: &str = include_str!("../../data/atomic/line_list.dat");
+
+pub struct SpectralLine {
+    pub element: &'static Element,
+    pub ion_stage: usize,         // 1 = neutral, 2 = singly ionized, ...
+    pub wavelength: f64,          // meters (air)
+    pub log_gf: f64,
+    pub excitation_energy: f64,   // lower level, eV
+    pub log_gamma_radiative: f64, // log10(s^-1)
+    pub log_gamma_stark: f64,     // log10(s^-1 cm^3) per electron at 10^4 K
+    pub log_gamma_van_der_waals: f64, // log10(s^-1 cm^3) per hydrogen atom at 10^4 K
+    pub zeeman: Option<ZeemanLevels>,
+    pub label: String,
+}
+
+impl SpectralLine {
+    pub fn frequency(&self) -> f64 {
+        SPEED_OF_LIGHT / self.wavelength
+    }
+
+    /// Total Lorentzian damping rate (s^-1) from radiative, quadratic Stark
+    /// and van der Waals broadening.
+    ///
+    /// # Arguments
+    ///
+    /// * `temperature` - The temperature in Kelvin.
+    /// * `electron_density` - The electron density in m^-3.
+    /// * `neutral_hydrogen_density` - The neutral hydrogen density in m^-3.
+    pub fn damping_rate(&self, temperature: f64, electron_density: f64, neutral_hydrogen_density: f64) -> f64 {
+        let radiative = 10f64.powf(self.log_gamma_radiative);
+        let stark = 10f64.powf(self.log_gamma_stark) * electron_density * 1.0e-6;
+        let van_der_waals = 10f64.powf(self.log_gamma_van_der_waals)
+            * neutral_hydrogen_density
+            * 1.0e-6
+            * (temperature / 1.0e4).powf(0.3);
+        radiative + stark + van_der_waals
+    }
+}
+
+/// Parses a whitespace-separated line list (format documented in
+/// data/atomic/line_list.dat).
+pub fn parse_line_list(text: &str) -> Result<Vec<SpectralLine>, Box<dyn Error>> {
+    let mut lines = Vec::new();
+    for (number, raw) in text.lines().enumerate() {
+        let (content, label) = match raw.split_once('#') {
+            Some((content, label)) => (content, label.trim()),
+            None => (raw, ""),
+        };
+        let fields: Vec<&str> = content.split_whitespace().collect();
+        if fields.is_empty() {
+            continue;
+        }
+        if fields.len() < 8 {
+            return Err(format!("line list line {}: expected 8 columns, found {}", number + 1, fields.len()).into());
+        }
+        let element = atomic_data::element(fields[0])
+            .ok_or_else(|| format!("line list line {}: unknown element {}", number + 1, fields[0]))?;
+        let value = |i: usize| -> Result<f64, Box<dyn Error>> {
+            fields[i]
+                .parse::<f64>()
+                .map_err(|e| format!("line list line {}, column {}: {}", number + 1, i + 1, e).into())
+        };
+        // Optional J and Landé factor of the lower and upper level
+        let zeeman = if fields.len() >= 12 {
+            Some(ZeemanLevels {
+                lower_j: value(8)?,
+                lower_g: value(9)?,
+                upper_j: value(10)?,
+                upper_g: value(11)?,
+            })
+        } else {
+            None
+        };
+        lines.push(SpectralLine {
+            element,
+            ion_stage: fields[1].parse()?,
+            wavelength: value(2)? * 1.0e-9,
+            log_gf: value(3)?,
+            excitation_energy: value(4)?,
+            log_gamma_radiative: value(5)?,
+            log_gamma_stark: value(6)?,
+            log_gamma_van_der_waals: value(7)?,
+            zeeman,
+            label: label.to_string(),
+        });
+    }
+    Ok(lines)
+}
+
+/// Loads a line list from a local file.
+pub fn load_line_list(path: &str) -> Result<Vec<SpectralLine>, Box<dyn Error>> {
+    parse_line_list(&fs::read_to_string(path)?)
+}
+
+/// The line list bundled with the repository.
+pub fn default_line_list() -> Vec<SpectralLine> {
+    parse_line_list(DEFAULT_LINE_LIST).expect("bundled line list is well formed")
+}
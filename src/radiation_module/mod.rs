@@ -0,0 +1,21 @@
+// Radiation physics shared by every layer of the stellar body.
+//
+// Built as the `radiation` library, which every layer program uses; layers
+// that combine others include those layers' modules and share this one.
+
+pub mod atomic_data;
+pub mod continuum;
+pub mod ionization;
+pub mod irradiance;
+pub mod line_list;
+pub mod line_profile;
+pub mod non_equilibrium_ionization;
+pub mod numerics;
+pub mod photometry;
+pub mod planck;
+pub mod radiative_loss;
+pub mod spectral_grid;
+pub mod stokes;
+pub mod transfer;
+pub mod xray_continuum;
+pub mod zeeman;
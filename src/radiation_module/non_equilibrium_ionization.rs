@@ -0,0 +1,196 @@
+// Time-dependent (non-equilibrium) ionization of an element along a plasma
+// history.
+//
+// When the temperature or density changes faster than the ions can
+// ionize or recombine, as in flares or in the accelerating solar wind, the
+// charge states lag behind the equilibrium of the local temperature:
+//
+//   dn_z/dt = n_e [S_(z-1) n_(z-1) + alpha_(z+1) n_(z+1) - (S_z + alpha_z) n_z]
+//
+// The rate matrix is tridiagonal and stiff, so each step is taken
+// implicitly (backward Euler), which is stable for any step and relaxes to
+// the equilibrium of data/atomic/ionization.dat when the plasma is steady.
+// Once n_e times the rates falls below the expansion rate the charge states
+// freeze in, and ratios such as O7+/O6+ carry the temperature of the
+// freeze-in region out into the heliosphere.
+
+use super::ionization::ElementIons;
+
+// Largest change of log10 T and of the ionization or recombination
+// probability (n_e * rate * dt) allowed in one implicit step
+const MAXIMUM_LOG_TEMPERATURE_STEP: f64 = 0.01;
+const MAXIMUM_RATE_STEP: f64 = 0.5;
+// Steps between two samples of a history; beyond it the exposure of a step
+// exceeds one and backward Euler simply relaxes to the local equilibrium
+const MAXIMUM_SUBSTEPS: usize = 200;
+
+// The plasma at one instant of its history
+#[derive(Clone, Copy, Debug)]
+pub struct PlasmaSample {
+    pub time: f64,             // s
+    pub temperature: f64,      // K
+    pub electron_density: f64, // m^-3
+}
+
+// Charge state fractions of one element at every sample of a history
+#[derive(Clone, Debug)]
+pub struct ChargeStateEvolution {
+    pub samples: Vec<PlasmaSample>,
+    pub fractions: Vec<Vec<f64>>, // [sample][charge], charges 0..=Z
+}
+
+impl ElementIons {
+    /// Charge states along a plasma history, starting from `initial`
+    /// fractions at the first sample.
+    ///
+    /// # Arguments
+    ///
+    /// * `initial` - Fractions of the charges 0..=Z at the first sample, e.g.
+    ///   `self.equilibrium(history[0].temperature)`.
+    /// * `history` - Samples of the temperature and electron density, in
+    ///   increasing time.
+    ///
+    /// # Returns
+    ///
+    /// The fractions at every sample.
+    pub fn evolve(&self, initial: &[f64], history: &[PlasmaSample]) -> ChargeStateEvolution {
+        let mut fractions = Vec::with_capacity(history.len());
+        let mut current = initial.to_vec();
+        if !history.is_empty() {
+            fractions.push(current.clone());
+        }
+        for pair in history.windows(2) {
+            let (start, end) = (pair[0], pair[1]);
+            let interval = end.time - start.time;
+            if interval <= 0.0 {
+                fractions.push(current.clone());
+                continue;
+            }
+            // Enough steps to follow the temperature and the fastest rate
+            let log_change = (end.temperature / start.temperature).log10().abs();
+            let (ionization, recombination) = self.rates(start.temperature.max(end.temperature));
+            let fastest = ionization.iter().chain(&recombination).cloned().fold(0.0, f64::max);
+            let probability = start.electron_density.max(end.electron_density) * fastest * interval;
+            let steps = (log_change / MAXIMUM_LOG_TEMPERATURE_STEP)
+                .max(probability / MAXIMUM_RATE_STEP)
+                .ceil()
+                .clamp(1.0, MAXIMUM_SUBSTEPS as f64) as usize;
+            let dt = interval / steps as f64;
+            for step in 0..steps {
+                // Rates at the middle of the step, interpolated in log T and log n_e
+                let fraction = (step as f64 + 0.5) / steps as f64;
+                let temperature = start.temperature * (end.temperature / start.temperature).powf(fraction);
+                let electron_density =
+                    start.electron_density * (end.electron_density / start.electron_density).powf(fraction);
+                current = self.implicit_step(&current, temperature, electron_density * dt);
+            }
+            fractions.push(current.clone());
+        }
+        ChargeStateEvolution {
+            samples: history.to_vec(),
+            fractions,
+        }
+    }
+
+    /// Equilibrium temperature (K) at which the ratio of the charges `upper`
+    /// to `lower` equals `ratio`, searched over 10^4 to 10^8 K; the usual
+    /// reading of a frozen-in ratio.
+    pub fn freeze_in_temperature(&self, upper: usize, lower: usize, ratio: f64) -> Option<f64> {
+        let equilibrium_ratio = |log_t: f64| -> f64 {
+            let fractions = self.equilibrium(10f64.powf(log_t));
+            fractions[upper] / fractions[lower].max(f64::MIN_POSITIVE)
+        };
+        // The ratio of a higher to a lower charge rises with temperature
+        let (mut low, mut high) = (4.0, 8.0);
+        if ratio <= equilibrium_ratio(low) || ratio >= equilibrium_ratio(high) {
+            return None;
+        }
+        for _ in 0..60 {
+            let mid = 0.5 * (low + high);
+            if equilibrium_ratio(mid) < ratio {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+        Some(10f64.powf(0.5 * (low + high)))
+    }
+
+    // One backward Euler step (I - n_e dt M) x_new = x_old of the
+    // tridiagonal rate matrix M, solved by the Thomas algorithm
+    fn implicit_step(&self, current: &[f64], temperature: f64, exposure: f64) -> Vec<f64> {
+        let (ionization, recombination) = self.rates(temperature);
+        let n = current.len();
+        // Row z: -e S_(z-1) x_(z-1) + (1 + e (S_z + alpha_z)) x_z - e alpha_(z+1) x_(z+1)
+        let lower: Vec<f64> = (0..n)
+            .map(|z| if z > 0 { -exposure * ionization[z - 1] } else { 0.0 })
+            .collect();
+        let upper: Vec<f64> = (0..n)
+            .map(|z| if z + 1 < n { -exposure * recombination[z] } else { 0.0 })
+            .collect();
+        let diagonal: Vec<f64> = (0..n)
+            .map(|z| {
+                let out_up = if z + 1 < n { ionization[z] } else { 0.0 };
+                let out_down = if z > 0 { recombination[z - 1] } else { 0.0 };
+                1.0 + exposure * (out_up + out_down)
+            })
+            .collect();
+
+        let mut modified_upper = vec![0.0; n];
+        let mut modified_rhs = vec![0.0; n];
+        for z in 0..n {
+            let pivot = if z > 0 {
+                diagonal[z] - lower[z] * modified_upper[z - 1]
+            } else {
+                diagonal[z]
+            };
+            modified_upper[z] = upper[z] / pivot;
+            let carried = if z > 0 { lower[z] * modified_rhs[z - 1] } else { 0.0 };
+            modified_rhs[z] = (current[z] - carried) / pivot;
+        }
+        let mut next = vec![0.0; n];
+        for z in (0..n).rev() {
+            let following = if z + 1 < n {
+                modified_upper[z] * next[z + 1]
+            } else {
+                0.0
+            };
+            next[z] = (modified_rhs[z] - following).max(0.0);
+        }
+        // The step conserves the element; renormalize away the rounding. An
+        // exposure too long for f64 is the equilibrium it relaxes to
+        let total: f64 = next.iter().sum();
+        if !(total.is_finite() && total > 0.0) {
+            return self.equilibrium(temperature);
+        }
+        next.iter().map(|x| x / total).collect()
+    }
+}
+
+impl ChargeStateEvolution {
+    /// Fractions at the last sample.
+    pub fn final_fractions(&self) -> &[f64] {
+        self.fractions.last().map(|f| f.as_slice()).unwrap_or(&[])
+    }
+
+    /// Ratio of the charges `upper` to `lower` at every sample.
+    pub fn ratio(&self, upper: usize, lower: usize) -> Vec<f64> {
+        self.fractions
+            .iter()
+            .map(|f| f[upper] / f[lower].max(f64::MIN_POSITIVE))
+            .collect()
+    }
+
+    /// Index of the first sample after which the ratio of `upper` to `lower`
+    /// stays within `tolerance` (relative) of its final value: where the
+    /// ratio froze in.
+    pub fn freeze_in_index(&self, upper: usize, lower: usize, tolerance: f64) -> Option<usize> {
+        let ratios = self.ratio(upper, lower);
+        let last = *ratios.last()?;
+        let mut index = ratios.len() - 1;
+        while index > 0 && ((ratios[index - 1] - last) / last).abs() <= tolerance {
+            index -= 1;
+        }
+        Some(index)
+    }
+}
@@ -0,0 +1,174 @@
+// Synthetic photometry: spectra convolved with filter transmission curves,
+// giving magnitudes, colors and bolometric corrections.
+//
+// Filters are photon-counting passbands, so the band-averaged flux density
+// weights F_lambda by lambda * T(lambda). Johnson-Cousins bands use Vega
+// zero points; SDSS and the Gaia-like bands use AB magnitudes.
+
+use super::numerics::trapezoid;
+use super::planck::SPEED_OF_LIGHT;
+
+use std::error::Error;
+use std::fs;
+
+pub const PARSEC: f64 = 3.0856776e16; // m
+pub const SOLAR_LUMINOSITY: f64 = 3.828e26; // W (IAU 2015 nominal)
+pub const SOLAR_BOLOMETRIC_MAGNITUDE: f64 = 4.74; // IAU 2015 zero point
+// AB zero point: m_AB = -2.5 log10(f_nu / 3631 Jy)
+const AB_ZERO_POINT: f64 = 3631.0e-26; // W m^-2 Hz^-1
+
+// Filter files shipped with the repository (data/filters)
+const BUNDLED_FILTERS: [(&str, &str); 11] = [
+    ("U", include_str!("../../data/filters/johnson_U.dat")),
+    ("B", include_str!("../../data/filters/johnson_B.dat")),
+    ("V", include_str!("../../data/filters/johnson_V.dat")),
+    ("u", include_str!("../../data/filters/sdss_u.dat")),
+    ("g", include_str!("../../data/filters/sdss_g.dat")),
+    ("r", include_str!("../../data/filters/sdss_r.dat")),
+    ("i", include_str!("../../data/filters/sdss_i.dat")),
+    ("z", include_str!("../../data/filters/sdss_z.dat")),
+    ("G", include_str!("../../data/filters/gaia_G.dat")),
+    ("BP", include_str!("../../data/filters/gaia_BP.dat")),
+    ("RP", include_str!("../../data/filters/gaia_RP.dat")),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MagnitudeSystem {
+    Ab,
+    Vega { zero_point: f64 }, // F_lambda of a zero-magnitude star, W m^-2 m^-1
+}
+
+#[derive(Clone, Debug)]
+pub struct Filter {
+    pub name: String,
+    pub system: MagnitudeSystem,
+    pub transmission: Vec<(f64, f64)>, // (wavelength in m, relative transmission)
+}
+
+impl Filter {
+    /// Transmission at `wavelength` (m), linearly interpolated and zero outside the band.
+    pub fn transmission_at(&self, wavelength: f64) -> f64 {
+        interpolate(&self.transmission, wavelength)
+    }
+
+    /// Photon-weighted pivot wavelength (m) of the band.
+    pub fn pivot_wavelength(&self) -> f64 {
+        let numerator = trapezoid(&self.map(|w, t| w * t));
+        let denominator = trapezoid(&self.map(|w, t| t / w));
+        (numerator / denominator).sqrt()
+    }
+
+    /// Band-averaged F_lambda of a spectrum given as (wavelength in m, F_lambda) pairs.
+    pub fn mean_flux_density(&self, spectrum: &[(f64, f64)]) -> f64 {
+        let weighted = trapezoid(&self.map(|w, t| interpolate(spectrum, w) * w * t));
+        weighted / trapezoid(&self.map(|w, t| w * t))
+    }
+
+    /// Apparent magnitude of a spectrum of flux received at the observer (W m^-2 m^-1).
+    pub fn magnitude(&self, spectrum: &[(f64, f64)]) -> f64 {
+        match self.system {
+            MagnitudeSystem::Vega { zero_point } => -2.5 * (self.mean_flux_density(spectrum) / zero_point).log10(),
+            MagnitudeSystem::Ab => {
+                // <f_nu> = integral(F_lambda lambda T) / integral(c T / lambda)
+                let weighted = trapezoid(&self.map(|w, t| interpolate(spectrum, w) * w * t));
+                let mean_f_nu = weighted / (SPEED_OF_LIGHT * trapezoid(&self.map(|w, t| t / w)));
+                -2.5 * (mean_f_nu / AB_ZERO_POINT).log10()
+            }
+        }
+    }
+
+    /// Absolute magnitude of a spherical source of the given radius (m)
+    /// emitting the surface flux `surface_flux` (W m^-2 m^-1).
+    pub fn absolute_magnitude(&self, surface_flux: &[(f64, f64)], radius: f64) -> f64 {
+        self.magnitude(&at_distance(surface_flux, radius, 10.0 * PARSEC))
+    }
+
+    // Transmission curve mapped through f(wavelength, transmission)
+    fn map<F: Fn(f64, f64) -> f64>(&self, f: F) -> Vec<(f64, f64)> {
+        self.transmission.iter().map(|&(w, t)| (w, f(w, t))).collect()
+    }
+}
+
+/// Parses a filter file: '#' comments, one `system ab` or
+/// `system vega <zero point>` line, then wavelength (nm) and transmission columns.
+pub fn parse_filter(name: &str, text: &str) -> Result<Filter, Box<dyn Error>> {
+    let mut system = None;
+    let mut transmission = Vec::new();
+    for (number, raw) in text.lines().enumerate() {
+        let content = raw.split('#').next().unwrap_or("");
+        let fields: Vec<&str> = content.split_whitespace().collect();
+        match fields.as_slice() {
+            [] => continue,
+            ["system", "ab"] => system = Some(MagnitudeSystem::Ab),
+            ["system", "vega", zero_point] => {
+                system = Some(MagnitudeSystem::Vega {
+                    zero_point: zero_point.parse()?,
+                })
+            }
+            [wavelength, value] => transmission.push((wavelength.parse::<f64>()? * 1.0e-9, value.parse::<f64>()?)),
+            _ => return Err(format!("filter {} line {}: cannot parse '{}'", name, number + 1, raw.trim()).into()),
+        }
+    }
+    let system = system.ok_or_else(|| format!("filter {}: missing 'system' line", name))?;
+    if transmission.len() < 2 {
+        return Err(format!("filter {}: needs at least two transmission points", name).into());
+    }
+    transmission.sort_by(|a, b| a.0.total_cmp(&b.0));
+    Ok(Filter {
+        name: name.to_string(),
+        system,
+        transmission,
+    })
+}
+
+/// Loads a filter from a local file; the file stem names the filter.
+pub fn load_filter(path: &str) -> Result<Filter, Box<dyn Error>> {
+    let name = std::path::Path::new(path)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or(path);
+    parse_filter(name, &fs::read_to_string(path)?)
+}
+
+/// One of the bundled filters: U, B, V (Johnson-Cousins), u, g, r, i, z (SDSS),
+/// G, BP, RP (Gaia-like).
+pub fn filter(name: &str) -> Option<Filter> {
+    BUNDLED_FILTERS
+        .iter()
+        .find(|(band, _)| *band == name)
+        .map(|(band, text)| parse_filter(band, text).expect("bundled filter is well formed"))
+}
+
+/// Color index between two bands, e.g. B - V.
+pub fn color(spectrum: &[(f64, f64)], first: &Filter, second: &Filter) -> f64 {
+    first.magnitude(spectrum) - second.magnitude(spectrum)
+}
+
+/// Flux received at `distance` (m) from a sphere of `radius` (m) with surface flux `surface_flux`.
+pub fn at_distance(surface_flux: &[(f64, f64)], radius: f64, distance: f64) -> Vec<(f64, f64)> {
+    let dilution = (radius / distance).powi(2);
+    surface_flux.iter().map(|&(w, f)| (w, f * dilution)).collect()
+}
+
+/// Absolute bolometric magnitude from the luminosity of a sphere, using the
+/// surface flux integrated over the whole spectrum.
+pub fn bolometric_magnitude(surface_flux: &[(f64, f64)], radius: f64) -> f64 {
+    let luminosity = 4.0 * std::f64::consts::PI * radius * radius * trapezoid(surface_flux);
+    SOLAR_BOLOMETRIC_MAGNITUDE - 2.5 * (luminosity / SOLAR_LUMINOSITY).log10()
+}
+
+/// Bolometric correction BC = M_bol - M_band.
+pub fn bolometric_correction(surface_flux: &[(f64, f64)], radius: f64, band: &Filter) -> f64 {
+    bolometric_magnitude(surface_flux, radius) - band.absolute_magnitude(surface_flux, radius)
+}
+
+// Linear interpolation in a table sorted by x, zero outside its range
+fn interpolate(table: &[(f64, f64)], x: f64) -> f64 {
+    let index = table.partition_point(|&(tx, _)| tx < x);
+    if index == 0 || index == table.len() {
+        return if table.first().is_some_and(|&(tx, _)| tx == x) { table[0].1 } else { 0.0 };
+    }
+    let (x0, y0) = table[index - 1];
+    let (x1, y1) = table[index];
+    y0 + (y1 - y0) * (x - x0) / (x1 - x0)
+}
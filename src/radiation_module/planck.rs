@@ -0,0 +1,152 @@
+// Planck function in wavelength and frequency form, plus integrated band fluxes.
+
+pub const PLANCK_CONSTANT: f64 = 6.62607015e-34; // J*s
+pub const SPEED_OF_LIGHT: f64 = 299792458.0; // m/s
+pub const BOLTZMANN_CONSTANT: f64 = 1.380649e-23; // J/K
+pub const STEFAN_BOLTZMANN_CONSTANT: f64 = 5.670374419e-8; // W m^-2 K^-4
+
+// Beyond this value of h*nu / (k*T) the Wien limit is exact to double precision
+const WIEN_LIMIT: f64 = 40.0;
+// Below this value the Rayleigh-Jeans limit is exact to double precision
+const RAYLEIGH_JEANS_LIMIT: f64 = 1.0e-8;
+
+/// Calculates the spectral radiance per unit wavelength, B_lambda.
+///
+/// # Arguments
+///
+/// * `wavelength` - The wavelength in meters.
+/// * `temperature` - The temperature in Kelvin.
+///
+/// # Returns
+///
+/// The spectral radiance in W m^-2 sr^-1 m^-1.
+pub fn spectral_radiance_wavelength(wavelength: f64, temperature: f64) -> f64 {
+    if wavelength <= 0.0 || temperature <= 0.0 {
+        return 0.0;
+    }
+    let x = PLANCK_CONSTANT * SPEED_OF_LIGHT / (wavelength * BOLTZMANN_CONSTANT * temperature);
+    if x < RAYLEIGH_JEANS_LIMIT {
+        return 2.0 * SPEED_OF_LIGHT * BOLTZMANN_CONSTANT * temperature / wavelength.powi(4);
+    }
+    2.0 * PLANCK_CONSTANT * SPEED_OF_LIGHT.powi(2) / wavelength.powi(5) * planck_occupation(x)
+}
+
+/// Calculates the spectral radiance per unit frequency, B_nu.
+///
+/// # Arguments
+///
+/// * `frequency` - The frequency in Hz.
+/// * `temperature` - The temperature in Kelvin.
+///
+/// # Returns
+///
+/// The spectral radiance in W m^-2 sr^-1 Hz^-1.
+pub fn spectral_radiance_frequency(frequency: f64, temperature: f64) -> f64 {
+    if frequency <= 0.0 || temperature <= 0.0 {
+        return 0.0;
+    }
+    let x = PLANCK_CONSTANT * frequency / (BOLTZMANN_CONSTANT * temperature);
+    if x < RAYLEIGH_JEANS_LIMIT {
+        return 2.0 * frequency.powi(2) * BOLTZMANN_CONSTANT * temperature / SPEED_OF_LIGHT.powi(2);
+    }
+    2.0 * PLANCK_CONSTANT * frequency.powi(3) / SPEED_OF_LIGHT.powi(2) * planck_occupation(x)
+}
+
+/// Brightness temperature (K): the temperature of the blackbody whose
+/// B_lambda equals `intensity` (W m^-2 sr^-1 m^-1) at `wavelength` (m).
+pub fn brightness_temperature(wavelength: f64, intensity: f64) -> f64 {
+    if wavelength <= 0.0 || intensity <= 0.0 {
+        return 0.0;
+    }
+    let x = 2.0 * PLANCK_CONSTANT * SPEED_OF_LIGHT.powi(2) / (wavelength.powi(5) * intensity);
+    PLANCK_CONSTANT * SPEED_OF_LIGHT / (wavelength * BOLTZMANN_CONSTANT * x.ln_1p())
+}
+
+// 1 / (e^x - 1), evaluated without overflow for large x and without
+// cancellation for small x
+fn planck_occupation(x: f64) -> f64 {
+    if x > WIEN_LIMIT {
+        (-x).exp()
+    } else {
+        1.0 / x.exp_m1()
+    }
+}
+
+/// Radiance integrated over all wavelengths, sigma T^4 / pi.
+pub fn integrated_radiance(temperature: f64) -> f64 {
+    STEFAN_BOLTZMANN_CONSTANT * temperature.powi(4) / std::f64::consts::PI
+}
+
+/// Calculates the radiance integrated over a wavelength band.
+///
+/// # Arguments
+///
+/// * `min_wavelength` - The short-wavelength edge of the band in meters.
+/// * `max_wavelength` - The long-wavelength edge of the band in meters.
+/// * `temperature` - The temperature in Kelvin.
+///
+/// # Returns
+///
+/// The band radiance in W m^-2 sr^-1.
+pub fn band_radiance(min_wavelength: f64, max_wavelength: f64, temperature: f64) -> f64 {
+    if temperature <= 0.0 || max_wavelength <= min_wavelength {
+        return 0.0;
+    }
+    let scale = BOLTZMANN_CONSTANT * temperature / (PLANCK_CONSTANT * SPEED_OF_LIGHT);
+    // The short wavelength edge is the high x = h*c / (lambda*k*T) edge
+    let x_high = if min_wavelength > 0.0 { 1.0 / (min_wavelength * scale) } else { f64::INFINITY };
+    let x_low = 1.0 / (max_wavelength * scale);
+    let fraction = (planck_tail(x_low) - planck_tail(x_high)) / PLANCK_INTEGRAL;
+    fraction * integrated_radiance(temperature)
+}
+
+/// Flux leaving an isotropically radiating blackbody surface within a band,
+/// pi times the band radiance, in W/m^2.
+pub fn band_flux(min_wavelength: f64, max_wavelength: f64, temperature: f64) -> f64 {
+    std::f64::consts::PI * band_radiance(min_wavelength, max_wavelength, temperature)
+}
+
+// Integral of x^3 / (e^x - 1) from 0 to infinity, pi^4 / 15
+const PLANCK_INTEGRAL: f64 = 6.493939402266829;
+
+// Integral of x^3 / (e^x - 1) from x to infinity
+fn planck_tail(x: f64) -> f64 {
+    if x.is_infinite() {
+        return 0.0;
+    }
+    if x < 2.0 {
+        return PLANCK_INTEGRAL - planck_head(x);
+    }
+    // Sum of e^(-n x) (x^3/n + 3x^2/n^2 + 6x/n^3 + 6/n^4); converges fast for x >= 2
+    let mut sum = 0.0;
+    for n in 1..=40 {
+        let n = n as f64;
+        let term = (-n * x).exp() * (x.powi(3) / n + 3.0 * x.powi(2) / n.powi(2) + 6.0 * x / n.powi(3) + 6.0 / n.powi(4));
+        sum += term;
+        if term < 1.0e-17 * sum {
+            break;
+        }
+    }
+    sum
+}
+
+// Integral of x^3 / (e^x - 1) from 0 to x, from the Bernoulli expansion of
+// x / (e^x - 1); accurate for x < 2
+fn planck_head(x: f64) -> f64 {
+    // (n, B_n / n!) for the non-zero Bernoulli numbers up to B_14
+    const COEFFICIENTS: [(i32, f64); 9] = [
+        (0, 1.0),
+        (1, -0.5),
+        (2, 1.0 / 12.0),
+        (4, -1.0 / 720.0),
+        (6, 1.0 / 30240.0),
+        (8, -1.0 / 1209600.0),
+        (10, 1.0 / 47900160.0),
+        (12, -691.0 / 1307674368000.0),
+        (14, 1.0 / 74724249600.0),
+    ];
+    COEFFICIENTS
+        .iter()
+        .map(|&(n, coefficient)| coefficient * x.powi(n + 3) / (n + 3) as f64)
+        .sum()
+}
@@ -0,0 +1,280 @@
+// Optically thin radiative losses of a plasma in collisional ionization
+// equilibrium: the loss function Lambda(T), with the power radiated per
+// unit volume n_e n_H Lambda(T).
+//
+// The loss function is either read from a table of log T and log Lambda
+// (format documented in data/radiative_loss/rosner_1978.dat), such as one
+// exported from CHIANTI, or computed for an abundance set as the sum of
+//  - hydrogen and helium: their ionization balance, collisional excitation
+//    (H Ly alpha, He II 30.4 nm), collisional ionization, recombination and
+//    bremsstrahlung, with the rates of Cen (1992);
+//  - the line emission of the heavy elements, from fits to their
+//    equilibrium cooling (data/radiative_loss/metal_lines.dat) scaled by
+//    the abundances of the set.
+// Bremsstrahlung of the heavy ions adds a few per cent above 10^7 K and is
+// left out.
+
+use super::atomic_data::{self, AbundanceSet};
+
+use std::error::Error;
+use std::fs;
+
+// Tables shipped with the repository (data/radiative_loss)
+const BUNDLED_METAL_LINES: &str = include_str!("../../data/radiative_loss/metal_lines.dat");
+const ROSNER_1978: &str = include_str!("../../data/radiative_loss/rosner_1978.dat");
+
+// Temperature grid of computed loss functions, in log10 K
+const COMPUTED_LOG_TEMPERATURES: (f64, f64, usize) = (4.0, 8.5, 226);
+// erg cm^3 s^-1 to W m^3
+const CGS_TO_SI: f64 = 1.0e-13;
+
+// Line cooling of one group of ions of an element
+#[derive(Clone, Debug)]
+pub struct MetalCooling {
+    pub element: &'static atomic_data::Element,
+    pub peak_log_temperature: f64, // log10 K
+    pub log_width: f64,            // dex
+    pub peak_cooling: f64,         // W m^3 per unit abundance, per n_e n_H
+}
+
+// Contributions to Lambda(T), in W m^3 per n_e n_H
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LossComponents {
+    pub hydrogen_helium_lines: f64, // collisional excitation and ionization of H and He
+    pub recombination: f64,         // radiative and dielectronic recombination of H and He
+    pub bremsstrahlung: f64,        // free-free emission on H and He ions
+    pub metal_lines: f64,           // line emission of the heavy elements
+}
+
+#[derive(Clone, Debug)]
+pub struct RadiativeLossFunction {
+    pub name: String,
+    log_temperatures: Vec<f64>, // log10 K, increasing
+    log_losses: Vec<f64>,       // log10 of Lambda in W m^3
+}
+
+impl MetalCooling {
+    /// The fits bundled with the repository.
+    pub fn bundled() -> Vec<MetalCooling> {
+        parse_metal_cooling("bundled metal lines", BUNDLED_METAL_LINES)
+            .expect("bundled metal line table is well formed")
+    }
+
+    /// Line cooling (W m^3 per n_e n_H) of this group at `temperature` (K).
+    pub fn cooling(&self, temperature: f64, abundances: &AbundanceSet) -> f64 {
+        let offset = (temperature.log10() - self.peak_log_temperature) / self.log_width;
+        abundances.abundance(self.element) * self.peak_cooling * (-0.5 * offset * offset).exp()
+    }
+}
+
+impl LossComponents {
+    /// The contributions at `temperature` (K) for an abundance set.
+    pub fn at(temperature: f64, abundances: &AbundanceSet, metals: &[MetalCooling]) -> Self {
+        let t = temperature;
+        let root = t.sqrt();
+        let shielding = 1.0 + (t / 1.0e5).sqrt();
+        let recombination_shape = (t / 1.0e3).powf(-0.2) / (1.0 + (t / 1.0e6).powf(0.7));
+        let helium = abundances.abundance(&atomic_data::ELEMENTS[1]);
+
+        // Ionization balance from collisional ionization and recombination (cm^3 s^-1)
+        let ionize_h = 5.85e-11 * root * (-157809.1 / t).exp() / shielding;
+        let ionize_he = 2.38e-11 * root * (-285335.4 / t).exp() / shielding;
+        let ionize_he_ii = 5.68e-12 * root * (-631515.0 / t).exp() / shielding;
+        let dielectronic_shape = t.powf(-1.5) * (-470000.0 / t).exp() * (1.0 + 0.3 * (-94000.0 / t).exp());
+        let recombine_h = 8.40e-11 / root * recombination_shape;
+        let recombine_he_ii = 1.50e-10 * t.powf(-0.6353) + 1.9e-3 * dielectronic_shape;
+        let recombine_he_iii = 3.36e-10 / root * recombination_shape;
+        let protons = ionize_h / (ionize_h + recombine_h);
+        let first = ionize_he / recombine_he_ii;
+        let second = ionize_he_ii / recombine_he_iii;
+        let he_i = 1.0 / (1.0 + first + first * second);
+        let he_ii = he_i * first;
+        let he_iii = he_ii * second;
+
+        // Cooling rates per n_e and per ion, with ions per hydrogen nucleus
+        let lines = 7.50e-19 * (-118348.0 / t).exp() / shielding * (1.0 - protons)
+            + 5.54e-17 * t.powf(-0.397) * (-473638.0 / t).exp() / shielding * helium * he_ii
+            + 1.27e-21 * root * (-157809.1 / t).exp() / shielding * (1.0 - protons)
+            + 9.38e-22 * root * (-285335.4 / t).exp() / shielding * helium * he_i
+            + 4.95e-22 * root * (-631515.0 / t).exp() / shielding * helium * he_ii;
+        let recombination = 8.70e-27 * root * recombination_shape * protons
+            + (1.55e-26 * t.powf(0.3647) + 1.24e-13 * dielectronic_shape) * helium * he_ii
+            + 3.48e-26 * root * recombination_shape * helium * he_iii;
+        let gaunt = 1.1 + 0.34 * (-(5.5 - t.log10()).powi(2) / 3.0).exp();
+        let bremsstrahlung = 1.42e-27 * gaunt * root * (protons + helium * (he_ii + 4.0 * he_iii));
+
+        LossComponents {
+            hydrogen_helium_lines: lines * CGS_TO_SI,
+            recombination: recombination * CGS_TO_SI,
+            bremsstrahlung: bremsstrahlung * CGS_TO_SI,
+            metal_lines: metals.iter().map(|m| m.cooling(t, abundances)).sum(),
+        }
+    }
+
+    pub fn total(&self) -> f64 {
+        self.hydrogen_helium_lines + self.recombination + self.bremsstrahlung + self.metal_lines
+    }
+}
+
+impl RadiativeLossFunction {
+    /// The piecewise power law of Rosner, Tucker & Vaiana (1978).
+    pub fn rosner_1978() -> Self {
+        parse_radiative_loss("Rosner et al. 1978", ROSNER_1978).expect("bundled loss table is well formed")
+    }
+
+    /// Loss function computed for an abundance set.
+    ///
+    /// # Arguments
+    ///
+    /// * `abundances` - The element abundances of the plasma.
+    /// * `metals` - Line cooling fits of the heavy elements, e.g. `MetalCooling::bundled()`.
+    pub fn computed(abundances: &AbundanceSet, metals: &[MetalCooling]) -> Self {
+        let (start, end, points) = COMPUTED_LOG_TEMPERATURES;
+        let log_temperatures: Vec<f64> = (0..points)
+            .map(|i| start + (end - start) * i as f64 / (points - 1) as f64)
+            .collect();
+        let log_losses = log_temperatures
+            .iter()
+            .map(|&log_t| {
+                LossComponents::at(10f64.powf(log_t), abundances, metals)
+                    .total()
+                    .log10()
+            })
+            .collect();
+        RadiativeLossFunction {
+            name: format!("computed, {} abundances", abundances.name),
+            log_temperatures,
+            log_losses,
+        }
+    }
+
+    /// Lambda(T) in W m^3 per n_e n_H.
+    ///
+    /// Interpolated linearly in log-log; below the table the first value is
+    /// kept, above it the loss grows as sqrt(T) like bremsstrahlung.
+    pub fn evaluate(&self, temperature: f64) -> f64 {
+        let log_t = temperature.log10();
+        let n = self.log_temperatures.len();
+        let log_loss = if log_t <= self.log_temperatures[0] {
+            self.log_losses[0]
+        } else if log_t >= self.log_temperatures[n - 1] {
+            self.log_losses[n - 1] + 0.5 * (log_t - self.log_temperatures[n - 1])
+        } else {
+            let i = self.log_temperatures.partition_point(|&t| t < log_t).max(1);
+            let fraction =
+                (log_t - self.log_temperatures[i - 1]) / (self.log_temperatures[i] - self.log_temperatures[i - 1]);
+            self.log_losses[i - 1] + fraction * (self.log_losses[i] - self.log_losses[i - 1])
+        };
+        10f64.powf(log_loss)
+    }
+
+    /// Power radiated per unit volume, n_e n_H Lambda(T), in W/m^3.
+    ///
+    /// # Arguments
+    ///
+    /// * `temperature` - The temperature in Kelvin.
+    /// * `electron_density` - The electron number density in m^-3.
+    /// * `hydrogen_density` - The total hydrogen nuclei density in m^-3.
+    pub fn volumetric_loss(&self, temperature: f64, electron_density: f64, hydrogen_density: f64) -> f64 {
+        electron_density * hydrogen_density * self.evaluate(temperature)
+    }
+
+    /// Temperature (K) where Lambda peaks on the tabulated grid.
+    pub fn peak_temperature(&self) -> f64 {
+        let peak = (0..self.log_losses.len())
+            .max_by(|&a, &b| self.log_losses[a].total_cmp(&self.log_losses[b]))
+            .unwrap_or(0);
+        10f64.powf(self.log_temperatures[peak])
+    }
+}
+
+/// Parses a loss function table (format documented in data/radiative_loss/rosner_1978.dat).
+pub fn parse_radiative_loss(name: &str, text: &str) -> Result<RadiativeLossFunction, Box<dyn Error>> {
+    let mut log_temperatures = Vec::new();
+    let mut log_losses = Vec::new();
+    for (number, raw) in text.lines().enumerate() {
+        let content = raw.split('#').next().unwrap_or("");
+        let fields: Vec<&str> = content.split_whitespace().collect();
+        if fields.is_empty() {
+            continue;
+        }
+        if fields.len() < 2 {
+            return Err(format!(
+                "{} line {}: expected 2 columns, found {}",
+                name,
+                number + 1,
+                fields.len()
+            )
+            .into());
+        }
+        let value = |i: usize| -> Result<f64, Box<dyn Error>> {
+            fields[i]
+                .parse::<f64>()
+                .map_err(|e| format!("{} line {}, column {}: {}", name, number + 1, i + 1, e).into())
+        };
+        let log_t = value(0)?;
+        if log_temperatures.last().is_some_and(|&last| log_t <= last) {
+            return Err(format!("{} line {}: temperatures must increase", name, number + 1).into());
+        }
+        log_temperatures.push(log_t);
+        log_losses.push(value(1)? + CGS_TO_SI.log10());
+    }
+    if log_temperatures.len() < 2 {
+        return Err(format!("{}: a loss function needs at least 2 points", name).into());
+    }
+    Ok(RadiativeLossFunction {
+        name: name.to_string(),
+        log_temperatures,
+        log_losses,
+    })
+}
+
+/// Loads a loss function table from a local file.
+pub fn load_radiative_loss(path: &str) -> Result<RadiativeLossFunction, Box<dyn Error>> {
+    parse_radiative_loss(path, &fs::read_to_string(path)?)
+}
+
+/// Parses a table of metal line cooling fits (format documented in
+/// data/radiative_loss/metal_lines.dat).
+pub fn parse_metal_cooling(name: &str, text: &str) -> Result<Vec<MetalCooling>, Box<dyn Error>> {
+    let mut humps = Vec::new();
+    for (number, raw) in text.lines().enumerate() {
+        let content = raw.split('#').next().unwrap_or("");
+        let fields: Vec<&str> = content.split_whitespace().collect();
+        if fields.is_empty() {
+            continue;
+        }
+        if fields.len() < 4 {
+            return Err(format!(
+                "{} line {}: expected 4 columns, found {}",
+                name,
+                number + 1,
+                fields.len()
+            )
+            .into());
+        }
+        let value = |i: usize| -> Result<f64, Box<dyn Error>> {
+            fields[i]
+                .parse::<f64>()
+                .map_err(|e| format!("{} line {}, column {}: {}", name, number + 1, i + 1, e).into())
+        };
+        let element = atomic_data::element(fields[0])
+            .ok_or_else(|| format!("{} line {}: unknown element {}", name, number + 1, fields[0]))?;
+        let log_width = value(2)?;
+        if log_width <= 0.0 {
+            return Err(format!("{} line {}: width must be positive", name, number + 1).into());
+        }
+        humps.push(MetalCooling {
+            element,
+            peak_log_temperature: value(1)?,
+            log_width,
+            peak_cooling: 10f64.powf(value(3)?) * CGS_TO_SI,
+        });
+    }
+    Ok(humps)
+}
+
+/// Loads metal line cooling fits from a local file.
+pub fn load_metal_cooling(path: &str) -> Result<Vec<MetalCooling>, Box<dyn Error>> {
+    parse_metal_cooling(path, &fs::read_to_string(path)?)
+}
@@ -0,0 +1,113 @@
+// Spectral sampling grids shared by every emission spectrum.
+//
+// A grid stores its points in the unit it was built in; spectra computed on
+// a grid are always returned as (wavelength in meters, value) pairs.
+
+use super::planck::{PLANCK_CONSTANT, SPEED_OF_LIGHT};
+
+const ELECTRON_VOLT: f64 = 1.602176634e-19; // J
+
+// Quantity the grid points are expressed in
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SpectralUnit {
+    Wavelength, // meters
+    Frequency,  // Hz
+    Energy,     // eV
+}
+
+impl SpectralUnit {
+    // Convert a value in this unit to a wavelength in meters
+    pub fn to_wavelength(self, value: f64) -> f64 {
+        match self {
+            SpectralUnit::Wavelength => value,
+            SpectralUnit::Frequency => SPEED_OF_LIGHT / value,
+            SpectralUnit::Energy => PLANCK_CONSTANT * SPEED_OF_LIGHT / (value * ELECTRON_VOLT),
+        }
+    }
+
+    // Convert a wavelength in meters to a value in this unit
+    pub fn wavelength_to_value(self, wavelength: f64) -> f64 {
+        match self {
+            SpectralUnit::Wavelength => wavelength,
+            SpectralUnit::Frequency => SPEED_OF_LIGHT / wavelength,
+            SpectralUnit::Energy => PLANCK_CONSTANT * SPEED_OF_LIGHT / (wavelength * ELECTRON_VOLT),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SpectralGrid {
+    unit: SpectralUnit,
+    points: Vec<f64>,
+}
+
+impl SpectralGrid {
+    // Evenly spaced points from `start` to `end` inclusive
+    pub fn linear(unit: SpectralUnit, start: f64, end: f64, num_points: usize) -> Self {
+        assert!(num_points >= 2, "a linear grid needs at least two points");
+        let step = (end - start) / (num_points - 1) as f64;
+        let points = (0..num_points).map(|i| start + i as f64 * step).collect();
+        SpectralGrid { unit, points }
+    }
+
+    // Points from `start` to `end` inclusive with a fixed `step`; computed
+    // from the index so the step does not accumulate rounding error
+    pub fn with_step(unit: SpectralUnit, start: f64, end: f64, step: f64) -> Self {
+        assert!(step > 0.0, "a stepped grid needs a positive step");
+        assert!(end > start, "a stepped grid needs end above start");
+        let num_points = ((end - start) / step).round() as usize + 1;
+        let points = (0..num_points).map(|i| start + i as f64 * step).collect();
+        SpectralGrid { unit, points }
+    }
+
+    // Points evenly spaced in log(value) from `start` to `end` inclusive
+    pub fn logarithmic(unit: SpectralUnit, start: f64, end: f64, num_points: usize) -> Self {
+        assert!(num_points >= 2, "a logarithmic grid needs at least two points");
+        assert!(start > 0.0 && end > 0.0, "a logarithmic grid needs positive bounds");
+        let (log_start, log_end) = (start.ln(), end.ln());
+        let step = (log_end - log_start) / (num_points - 1) as f64;
+        let points = (0..num_points).map(|i| (log_start + i as f64 * step).exp()).collect();
+        SpectralGrid { unit, points }
+    }
+
+    // User-supplied points, e.g. a line list or an instrument's sampling
+    pub fn from_points(unit: SpectralUnit, points: Vec<f64>) -> Self {
+        SpectralGrid { unit, points }
+    }
+
+    // The historical default: 200 nm to 700 nm in steps of 1 nm
+    pub fn visible() -> Self {
+        Self::with_step(SpectralUnit::Wavelength, 200.0e-9, 700.0e-9, 1.0e-9)
+    }
+
+    pub fn unit(&self) -> SpectralUnit {
+        self.unit
+    }
+
+    pub fn points(&self) -> &[f64] {
+        &self.points
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    // Every grid point as a wavelength in meters
+    pub fn wavelengths(&self) -> Vec<f64> {
+        self.points.iter().map(|&p| self.unit.to_wavelength(p)).collect()
+    }
+
+    // Every grid point as a frequency in Hz
+    pub fn frequencies(&self) -> Vec<f64> {
+        self.wavelengths().iter().map(|&w| SPEED_OF_LIGHT / w).collect()
+    }
+
+    // Evaluate `f(wavelength)` at every grid point
+    pub fn map_wavelengths<F: FnMut(f64) -> f64>(&self, mut f: F) -> Vec<(f64, f64)> {
+        self.wavelengths().into_iter().map(|w| (w, f(w))).collect()
+    }
+}
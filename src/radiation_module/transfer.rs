@@ -0,0 +1,201 @@
+// Formal solution of the radiative transfer equation in a plane-parallel,
+// stratified layer.
+//
+// Optical depth is built from dtau = kappa * rho * ds along the ray and the
+// transfer equation mu dI/dtau = I - S is integrated with the method of short
+// characteristics, assuming the source function varies linearly in tau
+// between depth points.
+
+use super::numerics::gauss_legendre_unit_interval;
+use super::spectral_grid::SpectralGrid;
+
+// Below this optical thickness per interval the exponential integrals are
+// evaluated from their Taylor series to avoid cancellation
+const THIN_INTERVAL: f64 = 1.0e-4;
+
+/// A stratified layer that can be handed to the formal solver.
+///
+/// Depth points are ordered from the top of the layer downward, so heights
+/// decrease and optical depth increases with the index.
+pub trait RadiatingLayer {
+    /// Height of each depth point in meters.
+    fn heights(&self) -> Vec<f64>;
+
+    /// Mass density at each depth point in kg/m^3.
+    fn densities(&self) -> Vec<f64>;
+
+    /// Opacity at each depth point in m^2/kg.
+    fn opacities(&self, wavelength: f64) -> Vec<f64>;
+
+    /// Source function at each depth point in W m^-2 sr^-1 m^-1.
+    fn source_function(&self, wavelength: f64) -> Vec<f64>;
+
+    /// Intensity entering through the bottom of the layer. `None` means the
+    /// layer is optically thick there and the diffusion approximation applies.
+    fn incident_intensity(&self, _wavelength: f64, _mu: f64) -> Option<f64> {
+        None
+    }
+}
+
+/// Integrates dtau = kappa * rho * dh downward from the top of the layer.
+///
+/// # Arguments
+///
+/// * `heights` - Height of each depth point in meters, decreasing with index.
+/// * `densities` - Mass density at each depth point in kg/m^3.
+/// * `opacities` - Opacity at each depth point in m^2/kg.
+///
+/// # Returns
+///
+/// The vertical optical depth at each depth point. The material above the top
+/// point is assumed to fall off exponentially with the local density scale
+/// height.
+pub fn optical_depth_scale(heights: &[f64], densities: &[f64], opacities: &[f64]) -> Vec<f64> {
+    let n = heights.len();
+    let mut tau = vec![0.0; n];
+    if n == 0 {
+        return tau;
+    }
+    if n > 1 && densities[1] > densities[0] && densities[0] > 0.0 {
+        let scale_height = (heights[0] - heights[1]) / (densities[1] / densities[0]).ln();
+        tau[0] = opacities[0] * densities[0] * scale_height;
+    }
+    for i in 1..n {
+        let extinction_upper = opacities[i - 1] * densities[i - 1];
+        let extinction_lower = opacities[i] * densities[i];
+        tau[i] = tau[i - 1] + 0.5 * (extinction_upper + extinction_lower) * (heights[i - 1] - heights[i]);
+    }
+    tau
+}
+
+/// Solves the transfer equation along an outgoing ray by short characteristics.
+///
+/// # Arguments
+///
+/// * `tau` - Vertical optical depth at each depth point, increasing with index.
+/// * `source` - Source function at each depth point.
+/// * `mu` - Cosine of the angle between the ray and the outward normal (0 < mu <= 1).
+/// * `incident` - Intensity entering at the bottom, or `None` for the diffusion approximation.
+///
+/// # Returns
+///
+/// The outgoing intensity at each depth point; the first entry is the emergent intensity.
+pub fn solve_outgoing_ray(tau: &[f64], source: &[f64], mu: f64, incident: Option<f64>) -> Vec<f64> {
+    let n = tau.len();
+    let mut intensity = vec![0.0; n];
+    if n == 0 {
+        return intensity;
+    }
+
+    intensity[n - 1] = incident.unwrap_or_else(|| {
+        if n > 1 && tau[n - 1] > tau[n - 2] {
+            // I = S + mu dS/dtau deep in an optically thick medium
+            source[n - 1] + mu * (source[n - 1] - source[n - 2]) / (tau[n - 1] - tau[n - 2])
+        } else {
+            source[n - 1]
+        }
+    });
+
+    for k in (0..n - 1).rev() {
+        let delta = (tau[k + 1] - tau[k]) / mu;
+        let (weight_near, weight_far) = linear_source_weights(delta);
+        intensity[k] = intensity[k + 1] * (-delta).exp() + weight_near * source[k] + weight_far * source[k + 1];
+    }
+    intensity
+}
+
+/// Solves the transfer equation along an incoming ray by short characteristics,
+/// starting from `incident` at the top of the layer (zero for empty space).
+///
+/// # Returns
+///
+/// The inward intensity at each depth point for direction cosine -mu.
+pub fn solve_incoming_ray(tau: &[f64], source: &[f64], mu: f64, incident: f64) -> Vec<f64> {
+    let n = tau.len();
+    let mut intensity = vec![0.0; n];
+    if n == 0 {
+        return intensity;
+    }
+    intensity[0] = incident;
+    for k in 1..n {
+        let delta = (tau[k] - tau[k - 1]) / mu;
+        let (weight_near, weight_far) = linear_source_weights(delta);
+        intensity[k] = intensity[k - 1] * (-delta).exp() + weight_near * source[k] + weight_far * source[k - 1];
+    }
+    intensity
+}
+
+// Weights of the source function at the near and far end of an interval of
+// optical thickness `delta`, for a source that is linear in tau:
+// integral over [0, delta] of S(t) e^(-t) dt = w_near S(0) + w_far S(delta)
+pub(crate) fn linear_source_weights(delta: f64) -> (f64, f64) {
+    if delta < THIN_INTERVAL {
+        return (delta / 2.0 - delta * delta / 6.0, delta / 2.0 - delta * delta / 3.0);
+    }
+    let attenuation = (-delta).exp();
+    let e0 = 1.0 - attenuation;
+    let e1 = e0 - delta * attenuation;
+    (e0 - e1 / delta, e1 / delta)
+}
+
+/// Angle-averaged mean intensity J and the diagonal of the Lambda operator.
+///
+/// Both hemispheres are integrated with Gauss-Legendre angles, with no
+/// radiation entering at the top and the diffusion approximation at the
+/// bottom. The diagonal dJ_k / dS_k is the local approximate operator of
+/// accelerated lambda iteration.
+///
+/// # Returns
+///
+/// (J, Lambda*) at each depth point.
+pub fn mean_intensity(tau: &[f64], source: &[f64]) -> (Vec<f64>, Vec<f64>) {
+    let n = tau.len();
+    let mut mean = vec![0.0; n];
+    let mut diagonal = vec![0.0; n];
+    for (mu, weight) in gauss_legendre_unit_interval() {
+        let outgoing = solve_outgoing_ray(tau, source, mu, None);
+        let incoming = solve_incoming_ray(tau, source, mu, 0.0);
+        for k in 0..n {
+            mean[k] += 0.5 * weight * (outgoing[k] + incoming[k]);
+            // Outgoing: the bottom point is thermalized, above it the near-end weight
+            let out_near = if k + 1 < n { linear_source_weights((tau[k + 1] - tau[k]) / mu).0 } else { 1.0 };
+            let in_near = if k > 0 { linear_source_weights((tau[k] - tau[k - 1]) / mu).0 } else { 0.0 };
+            diagonal[k] += 0.5 * weight * (out_near + in_near);
+        }
+    }
+    (mean, diagonal)
+}
+
+/// Total vertical optical depth through a layer at `wavelength`.
+pub fn optical_depth<L: RadiatingLayer>(layer: &L, wavelength: f64) -> f64 {
+    let tau = optical_depth_scale(&layer.heights(), &layer.densities(), &layer.opacities(wavelength));
+    tau.last().copied().unwrap_or(0.0)
+}
+
+/// Emergent specific intensity I(lambda, mu) from the top of a layer.
+pub fn emergent_intensity<L: RadiatingLayer>(layer: &L, wavelength: f64, mu: f64) -> f64 {
+    let tau = optical_depth_scale(&layer.heights(), &layer.densities(), &layer.opacities(wavelength));
+    let source = layer.source_function(wavelength);
+    solve_outgoing_ray(&tau, &source, mu, layer.incident_intensity(wavelength, mu))[0]
+}
+
+/// Emergent spectrum I(lambda, mu) on a spectral grid, as (wavelength, intensity) pairs.
+pub fn emergent_spectrum<L: RadiatingLayer>(layer: &L, grid: &SpectralGrid, mu: f64) -> Vec<(f64, f64)> {
+    grid.map_wavelengths(|wavelength| emergent_intensity(layer, wavelength, mu))
+}
+
+/// Emergent surface flux F_lambda = 2 pi * integral of I(mu) mu dmu, the
+/// physical flux leaving a unit area of the layer (W m^-2 m^-1); the
+/// astrophysical flux is F_lambda / pi.
+pub fn emergent_flux<L: RadiatingLayer>(layer: &L, wavelength: f64) -> f64 {
+    let tau = optical_depth_scale(&layer.heights(), &layer.densities(), &layer.opacities(wavelength));
+    let source = layer.source_function(wavelength);
+    let angular_integral: f64 = gauss_legendre_unit_interval()
+        .into_iter()
+        .map(|(mu, weight)| {
+            let intensity = solve_outgoing_ray(&tau, &source, mu, layer.incident_intensity(wavelength, mu))[0];
+            weight * intensity * mu
+        })
+        .sum();
+    2.0 * std::f64::consts::PI * angular_integral
+}
@@ -0,0 +1,146 @@
+// Thermal X-ray continuum of an optically thin, collisionally ionized plasma:
+// free-free (bremsstrahlung) and free-bound (recombination) emission.
+//
+// Free-free emission of electrons on ions of charge z is, per unit photon
+// energy and volume (Rybicki & Lightman 5.14b),
+//
+//   eps_ff(E) = C n_e n_z z^2 T^-1/2 exp(-E / kT) g_ff(E, T)
+//
+// with C = 6.8e-51 W m^3 K^1/2 Hz^-1 / h and the Born Gaunt factor
+// g_ff = (sqrt(3) / pi) exp(u / 2) K0(u / 2), u = E / kT, which holds for
+// z^2 Ry << kT as in the X-ray emitting corona. Recombination of an ion of
+// charge z into a shell n of binding energy chi adds, above the edge
+// (Kramers, with a unit Gaunt factor),
+//
+//   eps_fb(E) = C n_e n_z z^2 T^-1/2 exp(-(E - chi) / kT) (2 chi / (n kT)) v_n
+//
+// where v_n is the fraction of the shell still vacant. The recombination
+// edges use the ionization potentials of data/atomic/ionization.dat for the
+// outermost shell and hydrogenic binding energies z^2 Ry / n^2 above it;
+// hydrogen and helium edges lie below 0.1 keV and are left out.
+
+use super::atomic_data::{self, AbundanceSet, ELECTRON_VOLT, ELEMENTS};
+use super::ionization::IonizationData;
+use super::numerics::scaled_bessel_k0;
+use super::planck::{BOLTZMANN_CONSTANT, PLANCK_CONSTANT};
+
+use std::f64::consts::PI;
+
+// Free-free emission coefficient 6.8e-38 erg cm^3 s^-1 K^1/2 Hz^-1 in SI,
+// per Hz; divided by h for the emission per unit photon energy
+const FREE_FREE_COEFFICIENT: f64 = 6.8e-51;
+// Hydrogenic shells above the outermost one included in the edges
+const EXCITED_SHELLS: u32 = 4;
+// Shells closing at these electron counts: K, L, M, N
+const SHELL_CLOSURES: [u32; 4] = [2, 10, 28, 60];
+const RYDBERG_ENERGY: f64 = 13.605693; // eV
+
+// One recombination edge of the plasma
+#[derive(Clone, Copy, Debug)]
+pub struct RecombinationEdge {
+    pub energy: f64,   // J, binding energy of the shell
+    pub strength: f64, // n_z z^2 / n_H times (2 chi / (n kT)) v_n
+}
+
+// Continuum emission of a plasma at one temperature, per unit n_e n_H
+#[derive(Clone, Debug)]
+pub struct ThermalContinuum {
+    pub temperature: f64,      // K
+    pub free_free_weight: f64, // sum of n_z z^2 over ions, per n_H
+    pub edges: Vec<RecombinationEdge>,
+}
+
+/// Born free-free Gaunt factor at photon energy `photon_energy` (J) and
+/// `temperature` (K), averaged over a thermal electron distribution.
+pub fn free_free_gaunt_factor(photon_energy: f64, temperature: f64) -> f64 {
+    let half = 0.5 * photon_energy / (BOLTZMANN_CONSTANT * temperature);
+    3f64.sqrt() / PI * scaled_bessel_k0(half)
+}
+
+impl ThermalContinuum {
+    /// The continuum of a plasma in ionization equilibrium.
+    ///
+    /// # Arguments
+    ///
+    /// * `temperature` - The electron temperature in Kelvin.
+    /// * `abundances` - The element abundances of the plasma.
+    /// * `ions` - Ionization data for the charge states of the metals; metals
+    ///   without data are left out.
+    pub fn new(temperature: f64, abundances: &AbundanceSet, ions: &IonizationData) -> Self {
+        let thermal = BOLTZMANN_CONSTANT * temperature;
+        // Hydrogen and helium are fully ionized
+        let mut free_free_weight = 1.0;
+        if let Some(helium) = atomic_data::element("He") {
+            free_free_weight += 4.0 * abundances.abundance(helium);
+        }
+        let mut edges = Vec::new();
+        for element in ELEMENTS.iter().filter(|e| e.atomic_number > 2) {
+            let Some(stages) = ions.element(element) else {
+                continue;
+            };
+            let abundance = abundances.abundance(element);
+            for (charge, fraction) in stages.equilibrium(temperature).into_iter().enumerate().skip(1) {
+                let z = charge as f64;
+                let density = abundance * fraction * z * z;
+                free_free_weight += density;
+                if density <= 0.0 {
+                    continue;
+                }
+                // The recombined ion and the shell its new electron enters
+                let electrons = element.atomic_number - charge as u32 + 1;
+                let shell = SHELL_CLOSURES
+                    .iter()
+                    .position(|&closure| electrons <= closure)
+                    .unwrap_or(3);
+                let closed_below = if shell > 0 { SHELL_CLOSURES[shell - 1] } else { 0 };
+                let n = shell as u32 + 1;
+                let capacity = 2.0 * (n * n) as f64;
+                let vacant = (capacity - (electrons - 1 - closed_below) as f64) / capacity;
+                let binding = stages.ions[charge - 1].ionization_potential * ELECTRON_VOLT;
+                edges.push(RecombinationEdge {
+                    energy: binding,
+                    strength: density * 2.0 * binding / (n as f64 * thermal) * vacant,
+                });
+                for excited in n + 1..=n + EXCITED_SHELLS {
+                    let binding = z * z * RYDBERG_ENERGY / (excited * excited) as f64 * ELECTRON_VOLT;
+                    edges.push(RecombinationEdge {
+                        energy: binding,
+                        strength: density * 2.0 * binding / (excited as f64 * thermal),
+                    });
+                }
+            }
+        }
+        ThermalContinuum {
+            temperature,
+            free_free_weight,
+            edges,
+        }
+    }
+
+    /// Free-free emission per unit photon energy, W m^3 J^-1 per unit
+    /// n_e n_H, into all directions, at `photon_energy` (J).
+    pub fn free_free(&self, photon_energy: f64) -> f64 {
+        let thermal = BOLTZMANN_CONSTANT * self.temperature;
+        FREE_FREE_COEFFICIENT / PLANCK_CONSTANT * self.free_free_weight / self.temperature.sqrt()
+            * (-photon_energy / thermal).exp()
+            * free_free_gaunt_factor(photon_energy, self.temperature)
+    }
+
+    /// Free-bound emission per unit photon energy, W m^3 J^-1 per unit
+    /// n_e n_H, into all directions, at `photon_energy` (J).
+    pub fn free_bound(&self, photon_energy: f64) -> f64 {
+        let thermal = BOLTZMANN_CONSTANT * self.temperature;
+        let edges: f64 = self
+            .edges
+            .iter()
+            .filter(|edge| edge.energy <= photon_energy)
+            .map(|edge| edge.strength * (-(photon_energy - edge.energy) / thermal).exp())
+            .sum();
+        FREE_FREE_COEFFICIENT / PLANCK_CONSTANT * edges / self.temperature.sqrt()
+    }
+
+    /// Total continuum emission per unit photon energy (W m^3 J^-1).
+    pub fn emissivity(&self, photon_energy: f64) -> f64 {
+        self.free_free(photon_energy) + self.free_bound(photon_energy)
+    }
+}
@@ -0,0 +1,118 @@
+// Anomalous Zeeman splitting of a line from the Landé factors of its levels.
+
+// Electron charge / (4 pi m_e c), giving the Larmor splitting
+// delta_lambda_B = ZEEMAN_CONSTANT * lambda^2 * B in meters for B in tesla
+pub const ZEEMAN_CONSTANT: f64 = 46.686; // m^-1 T^-1
+
+// Total angular momentum and Landé factor of the two levels of a transition
+#[derive(Clone, Copy, Debug)]
+pub struct ZeemanLevels {
+    pub lower_j: f64,
+    pub lower_g: f64,
+    pub upper_j: f64,
+    pub upper_g: f64,
+}
+
+impl ZeemanLevels {
+    /// Effective Landé factor of the line.
+    pub fn effective_lande_factor(&self) -> f64 {
+        let (jl, gl, ju, gu) = (self.lower_j, self.lower_g, self.upper_j, self.upper_g);
+        0.5 * (gu + gl) + 0.25 * (gu - gl) * (ju * (ju + 1.0) - jl * (jl + 1.0))
+    }
+}
+
+/// Landé factor of a level in LS coupling.
+pub fn lande_factor(l: f64, s: f64, j: f64) -> f64 {
+    if j == 0.0 {
+        return 0.0;
+    }
+    1.0 + (j * (j + 1.0) + s * (s + 1.0) - l * (l + 1.0)) / (2.0 * j * (j + 1.0))
+}
+
+/// Larmor wavelength splitting (m) of a line at `wavelength` (m) in a field of `field_strength` tesla.
+pub fn larmor_splitting(wavelength: f64, field_strength: f64) -> f64 {
+    ZEEMAN_CONSTANT * wavelength * wavelength * field_strength
+}
+
+// One Zeeman component: wavelength shift in units of the Larmor splitting
+// (positive to the red) and relative strength within its group
+#[derive(Clone, Copy, Debug)]
+pub struct ZeemanComponent {
+    pub shift: f64,
+    pub strength: f64,
+}
+
+// Components grouped by polarization; each group's strengths sum to one
+#[derive(Clone, Debug)]
+pub struct ZeemanPattern {
+    pub sigma_blue: Vec<ZeemanComponent>, // M_u - M_l = +1
+    pub pi: Vec<ZeemanComponent>,         // M_u - M_l = 0
+    pub sigma_red: Vec<ZeemanComponent>,  // M_u - M_l = -1
+}
+
+impl ZeemanPattern {
+    pub fn new(levels: &ZeemanLevels) -> Self {
+        let mut pattern = ZeemanPattern {
+            sigma_blue: Vec::new(),
+            pi: Vec::new(),
+            sigma_red: Vec::new(),
+        };
+        let (jl, ju) = (levels.lower_j, levels.upper_j);
+
+        let mut mu = -ju;
+        while mu <= ju + 1.0e-9 {
+            for delta_m in [-1.0, 0.0, 1.0] {
+                let ml = mu - delta_m;
+                if ml.abs() > jl + 1.0e-9 {
+                    continue;
+                }
+                let strength = component_strength(ju, jl, mu, delta_m);
+                if strength <= 0.0 {
+                    continue;
+                }
+                let component = ZeemanComponent {
+                    shift: -(levels.upper_g * mu - levels.lower_g * ml),
+                    strength,
+                };
+                match delta_m as i32 {
+                    1 => pattern.sigma_blue.push(component),
+                    0 => pattern.pi.push(component),
+                    _ => pattern.sigma_red.push(component),
+                }
+            }
+            mu += 1.0;
+        }
+
+        for group in [&mut pattern.sigma_blue, &mut pattern.pi, &mut pattern.sigma_red] {
+            let total: f64 = group.iter().map(|c| c.strength).sum();
+            if total > 0.0 {
+                group.iter_mut().for_each(|c| c.strength /= total);
+            }
+        }
+        pattern
+    }
+}
+
+// Relative strength of the M_u -> M_u - delta_m component, up to a factor
+// common to its polarization group (Condon & Shortley)
+fn component_strength(ju: f64, jl: f64, mu: f64, delta_m: f64) -> f64 {
+    if (ju - jl - 1.0).abs() < 1.0e-9 {
+        match delta_m as i32 {
+            1 => (ju + mu) * (ju + mu - 1.0),
+            0 => 2.0 * (ju * ju - mu * mu),
+            _ => (ju - mu) * (ju - mu - 1.0),
+        }
+    } else if (ju - jl).abs() < 1.0e-9 {
+        match delta_m as i32 {
+            1 => (ju - mu + 1.0) * (ju + mu),
+            0 => 2.0 * mu * mu,
+            _ => (ju + mu + 1.0) * (ju - mu),
+        }
+    } else {
+        match delta_m as i32 {
+            1 => (ju - mu + 1.0) * (ju - mu + 2.0),
+            0 => 2.0 * ((ju + 1.0).powi(2) - mu * mu),
+            _ => (ju + mu + 1.0) * (ju + mu + 2.0),
+        }
+    }
+}
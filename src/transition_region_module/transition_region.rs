@@ -0,0 +1,442 @@
+// The transition region between the chromosphere and the corona.
+//
+// A magnetic loop leg rises from the top of the chromosphere to the
+// coronal apex. Along it the temperature is set by the static energy
+// balance of field-aligned Spitzer conduction, optically thin radiative
+// losses and a uniform volumetric heating:
+//
+//   d/ds (kappa0 T^(5/2) dT/ds) = n_e n_H Lambda(T) - H
+//
+// The leg is shorter than the coronal pressure scale height, so the
+// pressure is uniform along it. With the conductive flux F written as a
+// function of temperature the balance integrates to
+// d(F^2)/dT = 2 kappa0 T^(5/2) (n_e n_H Lambda - H); F vanishes at the apex
+// (symmetric loop) and at the chromospheric footpoint, which fixes the
+// heating rate, and the loop half-length fixes the pressure. Because
+// Lambda(T) does not depend on density, F scales with the pressure and the
+// heights with its inverse, so a single integration at unit pressure gives
+// the whole family of solutions (Rosner, Tucker & Vaiana 1978).
+
+// Only part of each included layer is used here; the layers' own binaries
+// check the rest for dead code
+#[path = "../chromosphere_module/chromosphere.rs"]
+#[allow(dead_code)]
+mod chromosphere;
+#[path = "../corona_module/corona.rs"]
+#[allow(dead_code)]
+mod corona;
+mod uv_lines;
+
+use chromosphere::stratification::{AtmosphereVariant, StratifiedAtmosphere};
+use chromosphere::Chromosphere;
+use corona::coronal_lines::CoronalLine;
+use corona::dem_inversion::EuvObservation;
+use corona::euv_response::{self, Passband, TemperatureResponse};
+use corona::Corona;
+use radiation::atomic_data::AbundanceSet;
+use radiation::planck::BOLTZMANN_CONSTANT;
+use radiation::radiative_loss::{MetalCooling, RadiativeLossFunction};
+use uv_lines::UvLine;
+
+// Spitzer conductivity along the field, kappa0 T^(5/2) (W m^-1 K^-7/2)
+const SPITZER_CONDUCTIVITY: f64 = 9.2e-12;
+// Helium nuclei per hydrogen nucleus, and free electrons per hydrogen
+// nucleus in the fully ionized gas
+const HELIUM_ABUNDANCE: f64 = 0.085;
+const ELECTRONS_PER_HYDROGEN: f64 = 1.0 + 2.0 * HELIUM_ABUNDANCE;
+// Temperature points between the footpoint and the apex
+const PROFILE_POINTS: usize = 4000;
+
+// A loop leg from the top of the chromosphere to the base of the corona
+pub(crate) struct TransitionRegion {
+    pub(crate) base_radius: f64,         // m, top of the chromosphere
+    pub(crate) base_temperature: f64,    // K, chromospheric footpoint
+    pub(crate) coronal_temperature: f64, // K, loop apex
+    pub(crate) half_length: f64,         // m, footpoint to apex along the field
+}
+
+// One point of the solved temperature profile
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct TransitionRegionPoint {
+    pub height: f64,           // m above the footpoint
+    pub temperature: f64,      // K
+    pub electron_density: f64, // m^-3
+    pub hydrogen_density: f64, // m^-3
+    pub conductive_flux: f64,  // W/m^2, carried downward
+}
+
+// Static energy balance of the loop leg, points ordered from the footpoint up
+#[derive(Clone, Debug)]
+pub(crate) struct TemperatureProfile {
+    pub pressure: f64,     // Pa
+    pub heating_rate: f64, // W/m^3
+    pub points: Vec<TransitionRegionPoint>,
+}
+
+impl TransitionRegion {
+    /// The layer that joins a chromosphere to the corona above it: the
+    /// footpoint sits at the top of the chromospheric model and the apex at
+    /// the coronal temperature. The loop is as long as it must be for its
+    /// pressure, with the radiative losses `loss`, to equal the gas pressure
+    /// at the top of the model; without a stratified model it is one corona
+    /// thickness long.
+    pub fn between(chromosphere: &Chromosphere, corona: &Corona, loss: &RadiativeLossFunction) -> Self {
+        let base_temperature = chromosphere
+            .stratification
+            .as_ref()
+            .map(|atmosphere| atmosphere.layers[0].temperature)
+            .unwrap_or(chromosphere.temperature);
+        let mut region = TransitionRegion {
+            base_radius: chromosphere.radius,
+            base_temperature,
+            coronal_temperature: corona.temperature,
+            half_length: corona.radius - chromosphere.radius,
+        };
+        // The energy balance fixes the product of pressure and half-length
+        if let Some(atmosphere) = &chromosphere.stratification {
+            let pressure = region.temperature_profile(loss).pressure;
+            region.half_length *= pressure / atmosphere.layers[0].gas_pressure();
+        }
+        region
+    }
+
+    /// Solves the energy balance for the temperature, density and
+    /// conductive flux along the leg.
+    ///
+    /// # Arguments
+    ///
+    /// * `loss` - The optically thin radiative loss function of the plasma.
+    ///
+    /// # Returns
+    ///
+    /// The profile together with the pressure and uniform heating rate for
+    /// which conduction carries the excess coronal heating down to the
+    /// footpoint, where radiation removes the last of it.
+    pub fn temperature_profile(&self, loss: &RadiativeLossFunction) -> TemperatureProfile {
+        let n = PROFILE_POINTS;
+        let ratio = self.coronal_temperature / self.base_temperature;
+        let temperatures: Vec<f64> = (0..n)
+            .map(|i| self.base_temperature * ratio.powf(i as f64 / (n - 1) as f64))
+            .collect();
+
+        // Segment integrals of kappa0 T^(5/2) and kappa0 T^(5/2) n_e n_H Lambda at unit pressure
+        let conduction = |t: f64| SPITZER_CONDUCTIVITY * t.powf(2.5);
+        let losses = |t: f64| {
+            let hydrogen = hydrogen_density(1.0, t);
+            ELECTRONS_PER_HYDROGEN * hydrogen * hydrogen * loss.evaluate(t)
+        };
+        let segments: Vec<(f64, f64)> = temperatures
+            .windows(2)
+            .map(|pair| {
+                let width = pair[1] - pair[0];
+                let weight = 0.5 * (conduction(pair[0]) + conduction(pair[1])) * width;
+                let loss =
+                    0.5 * (conduction(pair[0]) * losses(pair[0]) + conduction(pair[1]) * losses(pair[1])) * width;
+                (weight, loss)
+            })
+            .collect();
+        // No net flux leaves the footpoint
+        let heating = segments.iter().map(|s| s.1).sum::<f64>() / segments.iter().map(|s| s.0).sum::<f64>();
+
+        // F^2 from the apex, where it vanishes, down to the footpoint
+        let mut flux_squared = vec![0.0; n];
+        for i in (0..n - 1).rev() {
+            let (weight, loss) = segments[i];
+            flux_squared[i] = (flux_squared[i + 1] + 2.0 * (heating * weight - loss)).max(0.0);
+        }
+        flux_squared[0] = 0.0;
+
+        // ds = kappa0 T^(5/2) dT / F, with F^2 linear in T across each segment
+        let mut lengths = vec![0.0; n];
+        for i in 0..n - 1 {
+            let root_sum = flux_squared[i].sqrt() + flux_squared[i + 1].sqrt();
+            let step = if root_sum > 0.0 {
+                2.0 * segments[i].0 / root_sum
+            } else {
+                0.0
+            };
+            lengths[i + 1] = lengths[i] + step;
+        }
+
+        // Heights scale as 1/P and the flux as P
+        let pressure = lengths[n - 1] / self.half_length;
+        let points = (0..n)
+            .map(|i| {
+                let hydrogen = hydrogen_density(pressure, temperatures[i]);
+                TransitionRegionPoint {
+                    height: lengths[i] / pressure,
+                    temperature: temperatures[i],
+                    electron_density: ELECTRONS_PER_HYDROGEN * hydrogen,
+                    hydrogen_density: hydrogen,
+                    conductive_flux: flux_squared[i].sqrt() * pressure,
+                }
+            })
+            .collect();
+        TemperatureProfile {
+            pressure,
+            heating_rate: heating * pressure * pressure,
+            points,
+        }
+    }
+}
+
+impl TemperatureProfile {
+    /// Height (m) above the footpoint where the temperature reaches
+    /// `temperature`, or `None` outside the profile.
+    pub fn height_of_temperature(&self, temperature: f64) -> Option<f64> {
+        self.points
+            .windows(2)
+            .find(|pair| pair[0].temperature <= temperature && pair[1].temperature >= temperature)
+            .map(|pair| {
+                let fraction = (temperature - pair[0].temperature) / (pair[1].temperature - pair[0].temperature);
+                pair[0].height + fraction * (pair[1].height - pair[0].height)
+            })
+    }
+
+    /// Largest downward conductive flux (W/m^2) along the leg.
+    pub fn maximum_conductive_flux(&self) -> f64 {
+        self.points.iter().map(|p| p.conductive_flux).fold(0.0, f64::max)
+    }
+
+    /// Radiative losses integrated along the leg (W/m^2) for the loss
+    /// function the profile was solved with; in balance they equal the
+    /// heating rate times the half-length.
+    pub fn radiated_flux(&self, loss: &RadiativeLossFunction) -> f64 {
+        self.segments()
+            .map(|(temperature, emission_measure)| emission_measure * loss.evaluate(temperature))
+            .sum()
+    }
+
+    /// Differential emission measure n_e n_H ds/dT of the leg, averaged over
+    /// temperature bins.
+    ///
+    /// # Arguments
+    ///
+    /// * `log_edges` - Bin edges in log10 T, increasing.
+    ///
+    /// # Returns
+    ///
+    /// (log10 T at the bin centre, DEM in m^-5 K^-1) for each bin.
+    pub fn differential_emission_measure(&self, log_edges: &[f64]) -> Vec<(f64, f64)> {
+        log_edges
+            .windows(2)
+            .map(|edges| {
+                let (low, high) = (10f64.powf(edges[0]), 10f64.powf(edges[1]));
+                let emission_measure: f64 = self
+                    .segments()
+                    .filter(|&(temperature, _)| temperature >= low && temperature < high)
+                    .fold(0.0, |sum, (_, emission_measure)| sum + emission_measure);
+                (0.5 * (edges[0] + edges[1]), emission_measure / (high - low))
+            })
+            .collect()
+    }
+
+    /// Intensity (W m^-2 sr^-1) of an optically thin line seen straight
+    /// down the leg: the integral of G(T) n_e n_H ds.
+    pub fn line_intensity(&self, line: &UvLine) -> f64 {
+        self.segments()
+            .map(|(temperature, emission_measure)| line.contribution_function(temperature) * emission_measure)
+            .sum()
+    }
+
+    /// Synthetic count rate (DN s^-1 pixel^-1) of an EUV channel looking
+    /// straight down the leg: the integral of R(T) n_e n_H ds.
+    pub fn euv_counts(&self, response: &TemperatureResponse) -> f64 {
+        self.segments()
+            .map(|(temperature, emission_measure)| response.counts(temperature, emission_measure))
+            .sum()
+    }
+
+    // Mean temperature and emission measure n_e n_H ds of each segment;
+    // the apex, where ds/dT diverges, stays integrable this way
+    fn segments(&self) -> impl Iterator<Item = (f64, f64)> + '_ {
+        self.points.windows(2).map(|pair| {
+            let temperature = (pair[0].temperature * pair[1].temperature).sqrt();
+            let density_product = (pair[0].electron_density
+                * pair[0].hydrogen_density
+                * pair[1].electron_density
+                * pair[1].hydrogen_density)
+                .sqrt();
+            (temperature, density_product * (pair[1].height - pair[0].height))
+        })
+    }
+}
+
+// Hydrogen density (m^-3) of the fully ionized gas at `pressure` (Pa)
+fn hydrogen_density(pressure: f64, temperature: f64) -> f64 {
+    pressure / ((1.0 + HELIUM_ABUNDANCE + ELECTRONS_PER_HYDROGEN) * BOLTZMANN_CONSTANT * temperature)
+}
+
+// Constants for the neighbouring layers (example values, as in the layer programs)
+const CHROMOSPHERE_RADIUS: f64 = 7.0e8; // meters
+const CHROMOSPHERE_TEMPERATURE: f64 = 6000.0; // Kelvin
+const CHROMOSPHERE_DENSITY: f64 = 1.0e-5; // kg/m^3
+const CHROMOSPHERE_ENERGY_GENERATION: f64 = 1.0e25; // W/m^3
+const CORONA_RADIUS: f64 = 7.1e8; // meters
+const CORONA_TEMPERATURE: f64 = 1.0e6; // Kelvin
+const CORONA_DENSITY: f64 = 1.0e-6; // kg/m^3
+const CORONA_ENERGY_GENERATION: f64 = 1.0e24; // W/m^3
+const CORONA_MAGNETIC_FIELD: f64 = 1.0e-3; // Tesla
+
+// Temperatures (K) whose heights above the footpoint are reported
+const REPORTED_TEMPERATURES: [f64; 5] = [5.0e4, 1.0e5, 2.5e5, 5.0e5, 9.0e5];
+// Edges of the emission measure bins in log10 T
+const DEM_START: f64 = 4.4;
+const DEM_STEP: f64 = 0.2;
+const DEM_BINS: usize = 8;
+const INVERSION_BINS: usize = 10;
+// Exposure (s) of the synthetic EUV observation of the loop leg
+const EUV_EXPOSURE: f64 = 2.0;
+// Coronal temperatures (K) of the loop scan
+const SCAN_TEMPERATURES: [f64; 3] = [1.0e6, 2.0e6, 3.0e6];
+
+fn main() {
+    let corona = Corona {
+        radius: CORONA_RADIUS,
+        temperature: CORONA_TEMPERATURE,
+        density: CORONA_DENSITY,
+        energy_generation: CORONA_ENERGY_GENERATION,
+        magnetic_field_strength: CORONA_MAGNETIC_FIELD,
+        thermal_structure: None,
+    };
+
+    // Line tables given on the command line join the bundled lines
+    let mut lines = UvLine::bundled();
+    for path in std::env::args().skip(1) {
+        match uv_lines::load_uv_lines(&path) {
+            Ok(extra) => lines.extend(extra),
+            Err(error) => println!("Could not load line table {}: {}", path, error),
+        }
+    }
+
+    let chromosphere = Chromosphere {
+        radius: CHROMOSPHERE_RADIUS,
+        temperature: CHROMOSPHERE_TEMPERATURE,
+        density: CHROMOSPHERE_DENSITY,
+        energy_generation: CHROMOSPHERE_ENERGY_GENERATION,
+        stratification: Some(StratifiedAtmosphere::bundled(AtmosphereVariant::QuietSun)),
+        jets: Vec::new(),
+    };
+    // Coronal (FIP-enhanced) abundances hold along the whole leg
+    let loss = RadiativeLossFunction::computed(&AbundanceSet::coronal(), &MetalCooling::bundled());
+    let transition_region = TransitionRegion::between(&chromosphere, &corona, &loss);
+    let profile = transition_region.temperature_profile(&loss);
+
+    println!(
+        "Transition Region: {:.0} K at r = {:.4e} m to {:.2e} K over {:.1} Mm",
+        transition_region.base_temperature,
+        transition_region.base_radius,
+        transition_region.coronal_temperature,
+        transition_region.half_length * 1.0e-6
+    );
+    // The loop length was chosen for the pressures to match
+    let top = chromosphere
+        .stratification
+        .as_ref()
+        .map(|a| a.layers[0].gas_pressure())
+        .unwrap_or(0.0);
+    println!(
+        "  pressure {:.3e} Pa (chromosphere top {:.3e} Pa), heating {:.3e} W/m^3",
+        profile.pressure, top, profile.heating_rate
+    );
+    println!(
+        "  peak conductive flux {:.3e} W/m^2, radiated {:.3e} W/m^2, heating input {:.3e} W/m^2",
+        profile.maximum_conductive_flux(),
+        profile.radiated_flux(&loss),
+        profile.heating_rate * transition_region.half_length
+    );
+    let classic_loss = RadiativeLossFunction::rosner_1978();
+    let classic = transition_region.temperature_profile(&classic_loss);
+    println!(
+        "  radiative losses {} (peak at {:.2e} K); with {}: pressure {:.3e} Pa, heating {:.3e} W/m^3",
+        loss.name,
+        loss.peak_temperature(),
+        classic_loss.name,
+        classic.pressure,
+        classic.heating_rate
+    );
+    for temperature in REPORTED_TEMPERATURES {
+        if let Some(height) = profile.height_of_temperature(temperature) {
+            println!(
+                "  reaches {:.1e} K at {:.0} km (r = {:.6e} m)",
+                temperature,
+                height * 1.0e-3,
+                transition_region.base_radius + height
+            );
+        }
+    }
+    let log_edges: Vec<f64> = (0..=DEM_BINS).map(|i| DEM_START + DEM_STEP * i as f64).collect();
+    for (log_t, dem) in profile.differential_emission_measure(&log_edges) {
+        // m^-5 to cm^-5
+        println!("  DEM at log T = {:.1}: {:.3e} cm^-5 K^-1", log_t, dem * 1.0e-10);
+    }
+    for line in &lines {
+        let intensity = profile.line_intensity(line);
+        // W m^-2 sr^-1 to erg cm^-2 s^-1 sr^-1
+        println!(
+            "  {}: {:.3e} W m^-2 sr^-1 ({:.1} erg cm^-2 s^-1 sr^-1)",
+            line.label(),
+            intensity,
+            intensity * 1.0e3
+        );
+    }
+    let passbands = Passband::bundled();
+    let responses = euv_response::responses(&passbands, &CoronalLine::bundled());
+    let rates: Vec<f64> = responses.iter().map(|response| profile.euv_counts(response)).collect();
+    let counts: Vec<String> = responses
+        .iter()
+        .zip(&rates)
+        .map(|(response, rate)| format!("{} {:.3e}", response.name, rate))
+        .collect();
+    println!("  EUV count rates {} DN s^-1 pixel^-1", counts.join(", "));
+    // The DEM recovered from the synthetic count rates, to compare with the
+    // input on the same bins, which extend past the loop top. The channels
+    // hardly respond below a few 1e5 K, so the inversions find the loop top
+    // and leave the cooler bins nearly empty
+    let observation = EuvObservation::synthetic("loop leg", &passbands, &rates, EUV_EXPOSURE);
+    let inversion_edges: Vec<f64> = (0..=INVERSION_BINS).map(|i| DEM_START + DEM_STEP * i as f64).collect();
+    let input = profile.differential_emission_measure(&inversion_edges);
+    let measures: Vec<(f64, f64)> = input
+        .iter()
+        .zip(inversion_edges.windows(2))
+        .map(|(&(log_t, dem), edges)| (10f64.powf(log_t), dem * (10f64.powf(edges[1]) - 10f64.powf(edges[0]))))
+        .collect();
+    let total: f64 = measures.iter().map(|&(_, measure)| measure).sum();
+    let bins: Vec<String> = input
+        .iter()
+        .map(|(log_t, dem)| format!("{:.2}: {:.2e}", log_t, dem * 1.0e-10))
+        .collect();
+    println!(
+        "DEM of loop leg (input): EM {:.3e} cm^-5, mean T {:.3e} K; {}",
+        total * 1.0e-10,
+        measures.iter().map(|(t, measure)| t * measure).sum::<f64>() / total,
+        bins.join(", ")
+    );
+    corona::report_dem_inversion(&observation, &responses, &inversion_edges);
+
+    // Hotter coronae sit on denser, more strongly heated loops of the same
+    // length, and their transition regions shine brighter
+    for temperature in SCAN_TEMPERATURES {
+        let hotter = TransitionRegion {
+            coronal_temperature: temperature,
+            ..transition_region
+        };
+        let profile = hotter.temperature_profile(&loss);
+        let intensities: Vec<String> = lines
+            .iter()
+            .map(|line| format!("{:.3e}", profile.line_intensity(line)))
+            .collect();
+        let counts: Vec<String> = responses
+            .iter()
+            .map(|response| format!("{:.3e}", profile.euv_counts(response)))
+            .collect();
+        println!(
+            "Corona at {:.1e} K: pressure {:.3e} Pa, heating {:.3e} W/m^3, line intensities {} W m^-2 sr^-1, EUV {} DN s^-1 pixel^-1",
+            temperature,
+            profile.pressure,
+            profile.heating_rate,
+            intensities.join(" "),
+            counts.join(" ")
+        );
+    }
+}
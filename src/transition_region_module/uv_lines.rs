@@ -0,0 +1,108 @@
+// Collisionally excited ultraviolet lines of the transition region, read
+// from plain-text tables (format documented in
+// data/transition_region/uv_lines.dat).
+
+use radiation::atomic_data::{self, Element};
+use radiation::planck::{BOLTZMANN_CONSTANT, PLANCK_CONSTANT, SPEED_OF_LIGHT};
+
+use std::error::Error;
+use std::f64::consts::PI;
+use std::fs;
+
+// Lines shipped with the repository
+const BUNDLED_LINES: &str = include_str!("../../data/transition_region/uv_lines.dat");
+
+// Collisional excitation rate coefficient 8.629e-6 Omega / (g sqrt(T)) cm^3 s^-1, in m^3 s^-1
+const COLLISION_RATE: f64 = 8.629e-12;
+
+#[derive(Clone, Debug)]
+pub(crate) struct UvLine {
+    pub element: &'static Element,
+    pub ion: String,               // spectroscopic ionization stage, e.g. "IV"
+    pub wavelength: f64,           // m, vacuum
+    pub lower_weight: f64,         // g of the ground level
+    pub collision_strength: f64,   // effective Omega
+    pub peak_log_temperature: f64, // log10 K where the ion is most abundant
+    pub log_width: f64,            // dex, Gaussian width of the ion fraction
+    pub peak_fraction: f64,        // ion fraction at the peak
+}
+
+impl UvLine {
+    /// The C IV, Si IV and O V lines bundled with the repository.
+    pub fn bundled() -> Vec<UvLine> {
+        parse_uv_lines("bundled UV lines", BUNDLED_LINES).expect("bundled UV line table is well formed")
+    }
+
+    /// Label such as "C IV 154.82 nm".
+    pub fn label(&self) -> String {
+        format!("{} {} {:.2} nm", self.element.symbol, self.ion, self.wavelength * 1.0e9)
+    }
+
+    /// Fraction of the element in the emitting ion at `temperature` (K).
+    pub fn ion_fraction(&self, temperature: f64) -> f64 {
+        let offset = (temperature.log10() - self.peak_log_temperature) / self.log_width;
+        self.peak_fraction * (-0.5 * offset * offset).exp()
+    }
+
+    /// Contribution function G(T) in W m^3 sr^-1: the power radiated per
+    /// steradian and per unit n_e n_H.
+    pub fn contribution_function(&self, temperature: f64) -> f64 {
+        let photon_energy = PLANCK_CONSTANT * SPEED_OF_LIGHT / self.wavelength;
+        let excitation = COLLISION_RATE * self.collision_strength / (self.lower_weight * temperature.sqrt())
+            * (-photon_energy / (BOLTZMANN_CONSTANT * temperature)).exp();
+        self.element.abundance() * self.ion_fraction(temperature) * excitation * photon_energy / (4.0 * PI)
+    }
+}
+
+/// Parses a line table (format documented in data/transition_region/uv_lines.dat).
+pub(crate) fn parse_uv_lines(name: &str, text: &str) -> Result<Vec<UvLine>, Box<dyn Error>> {
+    let mut lines = Vec::new();
+    for (number, raw) in text.lines().enumerate() {
+        let content = raw.split('#').next().unwrap_or("");
+        let fields: Vec<&str> = content.split_whitespace().collect();
+        if fields.is_empty() {
+            continue;
+        }
+        if fields.len() < 8 {
+            return Err(format!(
+                "{} line {}: expected 8 columns, found {}",
+                name,
+                number + 1,
+                fields.len()
+            )
+            .into());
+        }
+        let value = |i: usize| -> Result<f64, Box<dyn Error>> {
+            fields[i]
+                .parse::<f64>()
+                .map_err(|e| format!("{} line {}, column {}: {}", name, number + 1, i + 1, e).into())
+        };
+        let element = atomic_data::element(fields[0])
+            .ok_or_else(|| format!("{} line {}: unknown element {}", name, number + 1, fields[0]))?;
+        let line = UvLine {
+            element,
+            ion: fields[1].to_string(),
+            wavelength: value(2)? * 1.0e-9,
+            lower_weight: value(3)?,
+            collision_strength: value(4)?,
+            peak_log_temperature: value(5)?,
+            log_width: value(6)?,
+            peak_fraction: value(7)?,
+        };
+        if line.wavelength <= 0.0 || line.lower_weight <= 0.0 || line.log_width <= 0.0 {
+            return Err(format!(
+                "{} line {}: wavelength, weight and width must be positive",
+                name,
+                number + 1
+            )
+            .into());
+        }
+        lines.push(line);
+    }
+    Ok(lines)
+}
+
+/// Loads a line table from a local file.
+pub(crate) fn load_uv_lines(path: &str) -> Result<Vec<UvLine>, Box<dyn Error>> {
+    parse_uv_lines(path, &fs::read_to_string(path)?)
+}